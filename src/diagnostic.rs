@@ -0,0 +1,440 @@
+use crate::errors::{EvaluationError, LexerError, ParseError};
+use crate::lexer::Span;
+use unicode_width::UnicodeWidthChar;
+
+const RED: &str = "\x1b[1;31m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+// An error as it is shown to the user, pointing at the part of the input that caused it
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    // Which stage of the calculator the error came from, like `ParseError`
+    pub kind: String,
+    pub message: String,
+    pub span: Option<Span>,
+    // Written under the span
+    pub label: String,
+    pub hint: Option<Hint>,
+}
+
+// Advice on how to fix an error, which may point at another part of the input
+#[derive(Debug, PartialEq)]
+pub struct Hint {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn new(kind: &str, message: String, span: Option<Span>, label: &str) -> Diagnostic {
+        Diagnostic {
+            kind: kind.to_string(),
+            message,
+            span,
+            label: label.to_string(),
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, message: &str, span: Option<Span>) -> Diagnostic {
+        self.hint = Some(Hint { message: message.to_string(), span });
+        return self;
+    }
+
+    pub fn from_lexer_error(error: &LexerError) -> Diagnostic {
+        match error {
+            LexerError::InvalidToken(token, span) => {
+                return Diagnostic::new(
+                    "LexerError",
+                    format!("Invalid token in input: {}", token),
+                    Some(*span),
+                    "not a valid token",
+                );
+            }
+            LexerError::InvalidIdentifier(identifier, span) => {
+                return Diagnostic::new(
+                    "LexerError",
+                    format!("Invalid variable name: {}", identifier),
+                    Some(*span),
+                    "not a valid name",
+                )
+                .with_hint("names start with a letter or `_`, followed by letters, digits or `_`", None);
+            }
+        }
+    }
+
+    pub fn from_parse_error(error: &ParseError) -> Diagnostic {
+        match error {
+            ParseError::UnexpectedEndOfInput { expected, span } => {
+                return Diagnostic::new(
+                    "ParseError",
+                    format!("Expected {}, but the input ended.", expected),
+                    Some(*span),
+                    "the input ends here",
+                );
+            }
+            ParseError::MissingClosingParenthesis { opening, found } => {
+                return Diagnostic::new(
+                    "ParseError",
+                    "Missing closing parenthesis.".to_string(),
+                    Some(*found),
+                    "expected `)`",
+                )
+                .with_hint("did you forget a closing parenthesis opened here?", Some(*opening));
+            }
+            ParseError::MissingClosingBracket { opening, found } => {
+                return Diagnostic::new(
+                    "ParseError",
+                    "Missing closing bracket.".to_string(),
+                    Some(*found),
+                    "expected `]`",
+                )
+                .with_hint("did you forget a closing bracket opened here?", Some(*opening));
+            }
+            ParseError::MissingColon(span) => {
+                return Diagnostic::new(
+                    "ParseError",
+                    "Missing ':' in conditional expression.".to_string(),
+                    Some(*span),
+                    "expected `:`",
+                )
+                .with_hint("conditionals are written `condition ? a : b`", None);
+            }
+            ParseError::InvalidParameterList(span) => {
+                return Diagnostic::new(
+                    "ParseError",
+                    "Function parameters must be plain names, each used once.".to_string(),
+                    Some(*span),
+                    "expected distinct names",
+                )
+                .with_hint("functions are defined like `f(x, y) = x^2 + y`, and lambdas like `(x, y) -> x^2 + y`", None);
+            }
+            ParseError::UnexpectedToken { expected, found, span } => {
+                return Diagnostic::new(
+                    "ParseError",
+                    format!("Expected {}, found `{}`.", expected, found),
+                    Some(*span),
+                    "unexpected token",
+                );
+            }
+            ParseError::UnexpectedTokensAtEnd { found, span } => {
+                let hint = if found.starts_with(')') {
+                    "there is no `(` for this `)` to close"
+                } else {
+                    "separate statements with `;`"
+                };
+                return Diagnostic::new(
+                    "ParseError",
+                    format!("Unexpected tokens at end of input: `{}`.", found),
+                    Some(*span),
+                    "not part of the expression",
+                )
+                .with_hint(hint, None);
+            }
+            ParseError::InvalidNumber(token, span) => {
+                return Diagnostic::new(
+                    "ParseError",
+                    format!("Invalid number: {}", token),
+                    Some(*span),
+                    "not a valid number",
+                );
+            }
+        }
+    }
+
+    pub fn from_evaluation_error(error: &EvaluationError) -> Diagnostic {
+        let span = error.span();
+        match error.kind() {
+            EvaluationError::DivisionByZero => {
+                return Diagnostic::new("EvaluationError", "Division by zero.".to_string(), span, "divides by zero");
+            }
+            EvaluationError::InvalidOperation => {
+                return Diagnostic::new("EvaluationError", "Invalid operation.".to_string(), span, "invalid operation");
+            }
+            EvaluationError::NotAFunction => {
+                return Diagnostic::new("EvaluationError", "Not a function".to_string(), span, "not a function");
+            }
+            EvaluationError::Undefined { function, value } => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("{}({}) is undefined.", function, value),
+                    span,
+                    "undefined",
+                );
+            }
+            EvaluationError::CannotAssignAConstant(name) => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("{} is a constant, and cannot be reassigned.", name),
+                    span,
+                    "assigns to a constant",
+                );
+            }
+            EvaluationError::UndefinedVariable(name) => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("The variable {} is not defined.", name),
+                    span,
+                    "not defined",
+                )
+                .with_hint(&format!("assign it a value first, like `{} = 2`", name), None);
+            }
+            EvaluationError::UndefinedFunction(name) => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("The function {} is not defined.", name),
+                    span,
+                    "not defined",
+                )
+                .with_hint(&format!("define it first, like `{}(x) = x^2`", name), None);
+            }
+            EvaluationError::RecursionLimitExceeded(depth) => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("Function calls were nested deeper than the limit of {}.", depth),
+                    span,
+                    "nested too deeply",
+                )
+                .with_hint("the limit can be raised with the `recursion` command", None);
+            }
+            EvaluationError::DomainError { function, value } => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("{} is not defined for {}.", function, value),
+                    span,
+                    "outside the domain",
+                );
+            }
+            EvaluationError::TypeMismatch { expected, found } => {
+                let article = if expected.starts_with(['a', 'e', 'i', 'o', 'u']) { "an" } else { "a" };
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("Expected {} {}, but found a {}.", article, expected, found),
+                    span,
+                    &format!("found a {}", found),
+                );
+            }
+            EvaluationError::ArityMismatch { function, min, max, found } => {
+                let expected = if min == max {
+                    count(*min, "argument")
+                } else if found < min {
+                    format!("at least {}", count(*min, "argument"))
+                } else {
+                    format!("at most {}", count(*max, "argument"))
+                };
+                let given = if *found == 1 { "was" } else { "were" };
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("{}() takes {}, but {} {} given.", function, expected, found, given),
+                    span,
+                    "wrong number of arguments",
+                );
+            }
+            EvaluationError::DimensionMismatch { expected, found } => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("Incompatible units, {} and {}.", expected, found),
+                    span,
+                    "incompatible units",
+                );
+            }
+            EvaluationError::ShapeMismatch { expected, found } => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("Expected a shape of {}, but found {}.", expected, found),
+                    span,
+                    "shapes do not match",
+                );
+            }
+            EvaluationError::IndexOutOfRange { index, length } => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    format!("Index {} is out of range for a list of length {}.", index, length),
+                    span,
+                    "out of range",
+                )
+                .with_hint("lists are indexed from 0, and negative indices count from the end", None);
+            }
+            EvaluationError::SingularMatrix => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    "The matrix is singular.".to_string(),
+                    span,
+                    "singular matrix",
+                )
+                .with_hint("a matrix with a determinant of 0 has no inverse, and `solve(A, b)` has no single solution", None);
+            }
+            EvaluationError::NotConverged(function) => {
+                let diagnostic = Diagnostic::new(
+                    "EvaluationError",
+                    format!("{}() did not converge to a result.", function),
+                    span,
+                    "did not converge",
+                );
+                if function == "solve" {
+                    return diagnostic.with_hint("try another starting point, or an interval where the function changes sign", None);
+                }
+                return diagnostic;
+            }
+            EvaluationError::ResultTooLarge => {
+                return Diagnostic::new(
+                    "EvaluationError",
+                    "The result is too large to represent.".to_string(),
+                    span,
+                    "too large",
+                );
+            }
+            EvaluationError::At { error, .. } => return Diagnostic::from_evaluation_error(error),
+        }
+    }
+
+    // Writes the error under the input it came from, with the span underlined like `^~~~` and
+    // the hint pointing at its own span if it has one. Colours are only used when asked for,
+    // since they would show up as escape codes when the output is not a terminal.
+    pub fn render(&self, input: &str, colour: bool) -> String {
+        let paint = |text: &str, code: &str| -> String {
+            if colour {
+                return format!("{}{}{}", code, text, RESET);
+            }
+            return text.to_string();
+        };
+        let gutter = paint("  |", BLUE);
+
+        let mut lines = vec![format!("{}: {}", paint(&self.kind, RED), self.message)];
+        if let Some(span) = self.span {
+            lines.push(format!("{} {}", gutter, input));
+            lines.push(format!("{} {}", gutter, paint(&underline(input, span, &self.label), RED)));
+        }
+        match &self.hint {
+            Some(Hint { message, span: Some(span) }) if self.span.is_some() => {
+                let hint = underline(input, *span, &format!("hint: {}", message));
+                lines.push(format!("{} {}", gutter, paint(&hint, CYAN)));
+            }
+            Some(Hint { message, .. }) => {
+                lines.push(format!("  {} {}", paint("=", BLUE), paint(&format!("hint: {}", message), CYAN)));
+            }
+            None => (),
+        }
+        return lines.join("\n");
+    }
+}
+
+// A number of things, like "1 argument" or "2 arguments"
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        return format!("1 {}", noun);
+    }
+    return format!("{} {}s", n, noun);
+}
+
+// A `^` under the start of the span and a `~` under the rest of it, followed by the label. The
+// span counts characters, so the line is padded by the width they are shown with, where wide
+// characters like `中` take two columns and tabs are kept so they line up the same way.
+fn underline(input: &str, span: Span, label: &str) -> String {
+    let mut line = String::new();
+    for c in input.chars().take(span.start) {
+        match c {
+            '\t' => line.push('\t'),
+            c => line.push_str(&" ".repeat(c.width().unwrap_or(0))),
+        }
+    }
+    let width: usize = input.chars()
+        .skip(span.start)
+        .take(span.end.saturating_sub(span.start))
+        .map(|c| c.width().unwrap_or(0))
+        .sum();
+    // A span past the end of the input, or an empty one, still gets a `^`
+    let width = width.max(1);
+    line.push_str(&" ".repeat(span.start.saturating_sub(input.chars().count())));
+    line.push('^');
+    line.push_str(&"~".repeat(width - 1));
+    line.push(' ');
+    line.push_str(label);
+    return line;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_missing_closing_parenthesis() {
+        let error = ParseError::MissingClosingParenthesis {
+            opening: Span::new(4, 5),
+            found: Span::new(10, 10),
+        };
+        let rendered = Diagnostic::from_parse_error(&error).render("3 + (5 * 2", false);
+        assert_eq!(
+            rendered,
+            "ParseError: Missing closing parenthesis.\n  \
+             | 3 + (5 * 2\n  \
+             |           ^ expected `)`\n  \
+             |     ^ hint: did you forget a closing parenthesis opened here?"
+        );
+    }
+
+    #[test]
+    fn test_render_underlines_span() {
+        let error = EvaluationError::DivisionByZero.at(Span::new(4, 9));
+        let rendered = Diagnostic::from_evaluation_error(&error).render("2 + 1 / 0", false);
+        assert_eq!(
+            rendered,
+            "EvaluationError: Division by zero.\n  \
+             | 2 + 1 / 0\n  \
+             |     ^~~~~ divides by zero"
+        );
+    }
+
+    #[test]
+    fn test_render_underlines_after_wide_characters_and_tabs() {
+        let error = EvaluationError::DivisionByZero.at(Span::new(8, 14));
+        let rendered = Diagnostic::from_evaluation_error(&error).render("中文 = 2;\t中文 / 0", false);
+        assert_eq!(
+            rendered,
+            "EvaluationError: Division by zero.\n  \
+             | 中文 = 2;\t中文 / 0\n  \
+             |          \t^~~~~~~~ divides by zero"
+        );
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let error = EvaluationError::UndefinedFunction("f".to_string());
+        let rendered = Diagnostic::from_evaluation_error(&error).render("f(2)", false);
+        assert_eq!(
+            rendered,
+            "EvaluationError: The function f is not defined.\n  \
+             = hint: define it first, like `f(x) = x^2`"
+        );
+    }
+
+    #[test]
+    fn test_render_arity_of_variadic_function() {
+        let error = EvaluationError::ArityMismatch {
+            function: "max".to_string(),
+            min: 1,
+            max: usize::MAX,
+            found: 0,
+        }.at(Span::new(0, 5));
+        let rendered = Diagnostic::from_evaluation_error(&error).render("max()", false);
+        assert_eq!(
+            rendered,
+            "EvaluationError: max() takes at least 1 argument, but 0 were given.\n  \
+             | max()\n  \
+             | ^~~~~ wrong number of arguments"
+        );
+    }
+
+    #[test]
+    fn test_render_colour() {
+        let error = ParseError::UnexpectedToken {
+            expected: "an operand after `+`".to_string(),
+            found: ")".to_string(),
+            span: Span::new(4, 5),
+        };
+        let rendered = Diagnostic::from_parse_error(&error).render("1 + )", true);
+        assert!(rendered.starts_with("\x1b[1;31mParseError\x1b[0m: Expected an operand after `+`, found `)`."));
+        assert!(rendered.contains("\x1b[1;31m    ^ unexpected token\x1b[0m"));
+    }
+}
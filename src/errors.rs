@@ -1,16 +1,44 @@
+use crate::lexer::Span;
+use num_bigint::BigInt;
+
+// Every error carries the span of the input that caused it
 #[derive(Debug, PartialEq)]
 pub enum ParseError {
-    UnexpectedEndOfInput,
-    MissingClosingParenthesis,
-    UnexpectedToken(String),
-    UnexpectedTokensAtEnd,
-    //InvalidNumber(String),
+    // What was expected, like "an operand after `+`", and an empty span at the end of the input
+    UnexpectedEndOfInput {
+        expected: String,
+        span: Span,
+    },
+    // The span of the `(` that was never closed, and of where its `)` was expected
+    MissingClosingParenthesis {
+        opening: Span,
+        found: Span,
+    },
+    // The same for a `[` and its `]`
+    MissingClosingBracket {
+        opening: Span,
+        found: Span,
+    },
+    MissingColon(Span),
+    InvalidParameterList(Span),
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    // The tokens left over after a complete statement, separated by spaces
+    UnexpectedTokensAtEnd {
+        found: String,
+        span: Span,
+    },
+    InvalidNumber(String, Span),
 }
 
 #[derive(Debug, PartialEq)]
 pub enum InputError {
     ReadError,
     EmptyInput,
+    InvalidArgument(String),
 }
 
 #[derive(Debug, PartialEq)]
@@ -18,14 +46,94 @@ pub enum EvaluationError {
     DivisionByZero,
     InvalidOperation,
     NotAFunction,
-    Undefined,
+    Undefined {
+        function: String,
+        value: f64,
+    },
     CannotAssignAConstant(String),
-    UndefinedVariable(String)
+    UndefinedVariable(String),
+    UndefinedFunction(String),
+    RecursionLimitExceeded(usize),
+    DomainError {
+        function: String,
+        value: f64,
+    },
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+    // The fewest and most arguments the function takes, where functions like `max` take any
+    // number from the fewest up
+    ArityMismatch {
+        function: String,
+        min: usize,
+        max: usize,
+        found: usize,
+    },
+    // Adding, comparing or converting quantities whose units measure different things
+    DimensionMismatch {
+        expected: String,
+        found: String,
+    },
+    // Lists, matrices, or lists and functions, whose shapes do not fit together, written like
+    // `[3]` or `[2, 3]`
+    ShapeMismatch {
+        expected: String,
+        found: String,
+    },
+    IndexOutOfRange {
+        index: BigInt,
+        length: usize,
+    },
+    // Inverting, or solving a system with, a matrix whose determinant is 0
+    SingularMatrix,
+    // A numerical method, like the root finding in `solve`, that did not find a result
+    NotConverged(String),
+    // A result too large to be held exactly or as a float, like `100000!`
+    ResultTooLarge,
+    // Another error, and the span of the expression it happened in
+    At {
+        error: Box<EvaluationError>,
+        span: Span,
+    },
     // InvalidInput,
 }
 
+impl EvaluationError {
+    // Errors are located at the innermost expression they happen in, so an error that
+    // already has a span keeps it
+    pub fn at(self, span: Span) -> EvaluationError {
+        match self {
+            EvaluationError::At { .. } => self,
+            error => EvaluationError::At { error: Box::new(error), span },
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            EvaluationError::At { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    // The error without its span
+    pub fn kind(&self) -> &EvaluationError {
+        match self {
+            EvaluationError::At { error, .. } => error,
+            error => error,
+        }
+    }
+
+    pub fn without_span(self) -> EvaluationError {
+        match self {
+            EvaluationError::At { error, .. } => *error,
+            error => error,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum LexerError {
-    InvalidToken(String),
-    InvalidIdentifier(String),
+    InvalidToken(String, Span),
+    InvalidIdentifier(String, Span),
 }
@@ -1,45 +1,673 @@
-use crate::parser::AstNode;
+use crate::parser::{AstNode, STACK_RED_ZONE, STACK_SEGMENT_SIZE};
+use crate::lexer::{Span, TokenType};
 use crate::errors::{EvaluationError};
+use crate::matrix::Matrix;
+use crate::precision::{self, Rounding};
+use crate::units::Quantity;
+use crate::value::Value;
+use bigdecimal::{BigDecimal, RoundingMode};
+use libm::{sin, cos, tan, asin, acos, atan, atan2, sinh, cosh, tanh, asinh, acosh, atanh, sqrt, cbrt, exp, log, log10, log2, fabs, floor, ceil, round, trunc, hypot};
+use std::f64::consts::PI;
+use num_complex::Complex64;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
 use std::collections::HashMap;
+use std::rc::Rc;
 
 pub struct Environment {
-    variables: HashMap<String, f64>,
+    variables: HashMap<String, Value>,
+    functions: HashMap<String, Rc<UserFunction>>,
+    // The parameters of each function call currently being evaluated, innermost last
+    scopes: Vec<HashMap<String, Value>>,
+    max_call_depth: usize,
+    settings: Settings,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct UserFunction {
+    pub params: Vec<String>,
+    pub body: AstNode,
+}
+
+// A lambda, with the variables its body refers to as they were when it was created. Variables
+// that were not defined yet, like the lambda's own name in a recursive one, are looked up when
+// it is called instead.
+#[derive(Debug, PartialEq)]
+pub struct Lambda {
+    pub params: Vec<String>,
+    pub body: AstNode,
+    pub captured: HashMap<String, Value>,
+}
+
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 256;
+
+// How the operators and functions compute their results
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Settings {
+    pub angle_mode: AngleMode,
+    // Significant digits for results that cannot be exact, or None to use floats
+    pub precision: Option<u64>,
+    // The word that integer results wrap around in, or None for integers of any size
+    pub word_size: Option<WordSize>,
+}
+
+// A fixed-size integer, as in the programmer mode
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct WordSize {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+impl WordSize {
+    pub fn new(bits: u32, signed: bool) -> Option<WordSize> {
+        match bits {
+            8 | 16 | 32 | 64 => Some(WordSize { bits, signed }),
+            _ => None,
+        }
+    }
+
+    // Keeps the lowest bits of the two's complement, as a fixed-size integer overflows
+    pub fn wrap(&self, value: &BigInt) -> BigInt {
+        let modulus = BigInt::one() << self.bits;
+        let wrapped = value & (&modulus - BigInt::one());
+        if self.signed && wrapped >= (&modulus >> 1) {
+            return wrapped - modulus;
+        }
+        return wrapped;
+    }
+}
+
+// The unit that trigonometric functions take their inputs in, and inverse ones return
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AngleMode {
+    Radians,
+    Degrees,
+    Gradians,
+}
+
+impl AngleMode {
+    pub fn name(&self) -> &str {
+        match self {
+            AngleMode::Radians => "radians",
+            AngleMode::Degrees => "degrees",
+            AngleMode::Gradians => "gradians",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<AngleMode> {
+        match name {
+            "rad" | "radians" => Some(AngleMode::Radians),
+            "deg" | "degrees" => Some(AngleMode::Degrees),
+            "grad" | "gradians" => Some(AngleMode::Gradians),
+            _ => None,
+        }
+    }
+
+    // A whole turn in the angle mode, or None for radians, where it is not exact
+    pub fn full_turn(&self) -> Option<BigRational> {
+        match self {
+            AngleMode::Radians => None,
+            AngleMode::Degrees => Some(BigRational::from_integer(BigInt::from(360))),
+            AngleMode::Gradians => Some(BigRational::from_integer(BigInt::from(400))),
+        }
+    }
+
+    pub fn to_radians(&self, angle: f64) -> f64 {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle * (PI / 180.0),
+            AngleMode::Gradians => angle * (PI / 200.0),
+        }
+    }
+
+    pub fn from_radians(&self, angle: f64) -> f64 {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle * (180.0 / PI),
+            AngleMode::Gradians => angle * (200.0 / PI),
+        }
+    }
+
+    pub fn to_radians_decimal(&self, angle: &BigDecimal, digits: u64) -> BigDecimal {
+        let half_turn = match self {
+            AngleMode::Radians => return angle.round_to(digits),
+            AngleMode::Degrees => BigDecimal::from(180),
+            AngleMode::Gradians => BigDecimal::from(200),
+        };
+        let pi = precision::pi(digits + precision::GUARD_DIGITS);
+        return precision::divide(&(angle * pi), &half_turn, digits);
+    }
+
+    pub fn from_radians_decimal(&self, angle: &BigDecimal, digits: u64) -> BigDecimal {
+        let half_turn = match self {
+            AngleMode::Radians => return angle.round_to(digits),
+            AngleMode::Degrees => BigDecimal::from(180),
+            AngleMode::Gradians => BigDecimal::from(200),
+        };
+        let pi = precision::pi(digits + precision::GUARD_DIGITS);
+        return precision::divide(&(angle * half_turn), &pi, digits);
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub enum EvalResult {
-    Value(f64),
-    Assignment(String, f64),
+    Value(Value),
+    Assignment(String, Value),
+    Definition(String),
     Error(EvaluationError),
 }
 
 pub const CONSTS: [&str; 6] = ["pi", "e", "phi", "tau", "sqrt2", "sqrt3"];
 
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Environment {
     pub fn new() -> Self {
         Environment {
             variables: HashMap::new(),
+            functions: HashMap::new(),
+            scopes: vec![],
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            settings: Settings { angle_mode: AngleMode::Radians, precision: None, word_size: None },
         }
     }
 
-    pub fn set_variable(&mut self, name: String, value: f64) -> Option<EvaluationError> {
-        if CONSTS.contains(&name.as_str()) {
+    // The imaginary unit is a constant as well, though it is read as a token of its own
+    pub fn set_variable(&mut self, name: String, value: Value) -> Option<EvaluationError> {
+        if CONSTS.contains(&name.as_str()) || name == "i" {
             return Some(EvaluationError::CannotAssignAConstant(name));
         }
-        self.variables.insert(name, value);
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name, value),
+            None => self.variables.insert(name, value),
+        };
         return None;
     }
 
-    pub fn get_variable(&self, name: &str) -> Option<&f64> {
+    // Parameters of the innermost function call shadow the global variables
+    pub fn get_variable(&self, name: &str) -> Option<&Value> {
+        if let Some(scope) = self.scopes.last()
+            && let Some(value) = scope.get(name)
+        {
+            return Some(value);
+        }
         self.variables.get(name)
     }
 
+    // A parameter or captured variable of the innermost call, ignoring the global variables
+    pub fn get_local_variable(&self, name: &str) -> Option<&Value> {
+        match self.scopes.last() {
+            Some(scope) => scope.get(name),
+            None => None,
+        }
+    }
+
+    // Defining a function again replaces its old body
+    pub fn define_function(&mut self, name: String, params: Vec<String>, body: AstNode) -> Option<EvaluationError> {
+        if CONSTS.contains(&name.as_str()) {
+            return Some(EvaluationError::CannotAssignAConstant(name));
+        }
+        for param in &params {
+            if CONSTS.contains(&param.as_str()) {
+                return Some(EvaluationError::CannotAssignAConstant(param.clone()));
+            }
+        }
+        self.functions.insert(name, Rc::new(UserFunction { params, body }));
+        return None;
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<Rc<UserFunction>> {
+        self.functions.get(name).cloned()
+    }
+
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    pub fn angle_mode(&self) -> AngleMode {
+        self.settings.angle_mode
+    }
+
+    pub fn set_angle_mode(&mut self, mode: AngleMode) {
+        self.settings.angle_mode = mode;
+    }
+
+    pub fn precision(&self) -> Option<u64> {
+        self.settings.precision
+    }
+
+    pub fn word_size(&self) -> Option<WordSize> {
+        self.settings.word_size
+    }
+
+    pub fn set_word_size(&mut self, word_size: Option<WordSize>) {
+        self.settings.word_size = word_size;
+    }
+
+    // The constants are computed again, to the new precision
+    pub fn set_precision(&mut self, precision: Option<u64>) {
+        self.settings.precision = precision;
+        self.init_consts();
+    }
+
+    fn call_function(&mut self, name: &str, function: &UserFunction, args: Vec<Value>) -> EvalResult {
+        if args.len() != function.params.len() {
+            return EvalResult::Error(EvaluationError::ArityMismatch {
+                function: name.to_string(),
+                min: function.params.len(),
+                max: function.params.len(),
+                found: args.len(),
+            });
+        }
+        if self.scopes.len() >= self.max_call_depth {
+            return EvalResult::Error(EvaluationError::RecursionLimitExceeded(self.max_call_depth));
+        }
+
+        self.scopes.push(function.params.iter().cloned().zip(args).collect());
+        let result = function.body.evaluate(self);
+        self.scopes.pop();
+        result
+    }
+
+    // The captured variables share the lambda's scope with its parameters, which shadow them
+    fn call_lambda(&mut self, name: &str, lambda: &Lambda, args: Vec<Value>) -> EvalResult {
+        if args.len() != lambda.params.len() {
+            return EvalResult::Error(EvaluationError::ArityMismatch {
+                function: name.to_string(),
+                min: lambda.params.len(),
+                max: lambda.params.len(),
+                found: args.len(),
+            });
+        }
+        if self.scopes.len() >= self.max_call_depth {
+            return EvalResult::Error(EvaluationError::RecursionLimitExceeded(self.max_call_depth));
+        }
+
+        let mut scope = lambda.captured.clone();
+        scope.extend(lambda.params.iter().cloned().zip(args));
+        self.scopes.push(scope);
+        let result = lambda.body.evaluate(self);
+        self.scopes.pop();
+        result
+    }
+
+    // Calls a function passed to a higher-order function, which must give a value
+    fn apply_lambda(&mut self, lambda: &Lambda, args: Vec<Value>) -> Result<Value, EvaluationError> {
+        match self.call_lambda("lambda", lambda, args) {
+            EvalResult::Value(value) | EvalResult::Assignment(_, value) => Ok(value),
+            EvalResult::Definition(_) => Err(EvaluationError::InvalidOperation),
+            EvalResult::Error(error) => Err(error),
+        }
+    }
+
+    fn apply_lambda_to_number(&mut self, lambda: &Lambda, x: f64) -> Result<f64, EvaluationError> {
+        match self.apply_lambda(lambda, vec![Value::Number(x)]) {
+            Ok(value) => value.as_number(),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn call_higher_order(&mut self, function: HigherOrderFunction, args: Vec<Value>) -> Result<Value, EvaluationError> {
+        let (min_args, max_args) = function.arity();
+        if args.len() < min_args || args.len() > max_args {
+            return Err(EvaluationError::ArityMismatch {
+                function: function.name().to_string(),
+                min: min_args,
+                max: max_args,
+                found: args.len(),
+            });
+        }
+        if function == HigherOrderFunction::Sum && args.len() < 3 {
+            return match args.as_slice() {
+                [Value::List(values)] => sum_list(values, self.settings),
+                [other] => Err(type_mismatch("list", other)),
+                _ => Err(EvaluationError::ArityMismatch {
+                    function: function.name().to_string(),
+                    min: 3,
+                    max: 3,
+                    found: args.len(),
+                }),
+            };
+        }
+        if function == HigherOrderFunction::Solve
+            && let Value::Matrix(matrix) = &args[0]
+        {
+            return solve_linear(matrix, &args[1..], self.settings);
+        }
+        let lambda = match &args[0] {
+            Value::Lambda(lambda) => lambda.clone(),
+            other => return Err(EvaluationError::TypeMismatch {
+                expected: "function".to_string(),
+                found: other.type_name().to_string(),
+            }),
+        };
+
+        match function {
+            HigherOrderFunction::Map => {
+                let values = match &args[1] {
+                    Value::List(values) => values,
+                    other => return self.apply_lambda(&lambda, vec![other.clone()]),
+                };
+                let mut results: Vec<Value> = Vec::with_capacity(values.len());
+                for value in values {
+                    match self.apply_lambda(&lambda, vec![value.clone()]) {
+                        Ok(result) => results.push(result),
+                        Err(error) => return Err(error),
+                    }
+                }
+                Ok(Value::List(results))
+            }
+            HigherOrderFunction::Sum => {
+                let (first, last) = match (args[1].as_integer(), args[2].as_integer()) {
+                    (Some(first), Some(last)) => (first, last),
+                    (None, _) => return Err(type_mismatch("integer", &args[1])),
+                    (_, None) => return Err(type_mismatch("integer", &args[2])),
+                };
+                let mut total = Value::Integer(BigInt::zero());
+                let mut k = first;
+                while k <= last {
+                    let term = match self.apply_lambda(&lambda, vec![Value::Integer(k.clone())]) {
+                        Ok(term) => term,
+                        Err(error) => return Err(error),
+                    };
+                    total = match total.apply_binary(&TokenType::Addition, term, self.settings) {
+                        Ok(total) => total,
+                        Err(error) => return Err(error),
+                    };
+                    k += 1;
+                }
+                Ok(total)
+            }
+            HigherOrderFunction::Integrate => {
+                let (a, b) = match (args[1].as_number(), args[2].as_number()) {
+                    (Ok(a), Ok(b)) => (a, b),
+                    (Err(error), _) | (_, Err(error)) => return Err(error),
+                };
+                match self.integrate(&lambda, a, b) {
+                    Ok(result) if result.is_finite() => Ok(Value::Number(result)),
+                    Ok(_) => Err(EvaluationError::NotConverged(function.name().to_string())),
+                    Err(error) => Err(error),
+                }
+            }
+            HigherOrderFunction::Solve => {
+                let result = match args.len() {
+                    2 => match args[1].as_number() {
+                        Ok(guess) => self.newton(&lambda, guess),
+                        Err(error) => return Err(error),
+                    },
+                    _ => match (args[1].as_number(), args[2].as_number()) {
+                        (Ok(a), Ok(b)) => self.bisect(&lambda, a, b),
+                        (Err(error), _) | (_, Err(error)) => return Err(error),
+                    },
+                };
+                match result {
+                    Ok(Some(root)) => Ok(Value::Number(root)),
+                    Ok(None) => Err(EvaluationError::NotConverged(function.name().to_string())),
+                    Err(error) => Err(error),
+                }
+            }
+        }
+    }
+
+    // Adaptive Simpson's rule, which splits the interval where the estimate is not yet accurate
+    fn integrate(&mut self, lambda: &Lambda, a: f64, b: f64) -> Result<f64, EvaluationError> {
+        let m = (a + b) / 2.0;
+        let mut values = [0.0; 3];
+        for (value, x) in values.iter_mut().zip([a, m, b]) {
+            *value = match self.apply_lambda_to_number(lambda, x) {
+                Ok(value) => value,
+                Err(error) => return Err(error),
+            };
+        }
+        let [fa, fm, fb] = values;
+        let whole = (b - a) / 6.0 * (fa + 4.0 * fm + fb);
+        // The tolerance is relative to the size of the integral, as large integrands could
+        // otherwise never meet it
+        let tolerance = INTEGRATION_TOLERANCE * whole.abs().max(1.0);
+        self.simpson(lambda, [(a, fa), (m, fm), (b, fb)], whole, tolerance, MAX_INTEGRATION_DEPTH)
+    }
+
+    // The points are the ends and middle of the interval, with the function's values at them
+    fn simpson(
+        &mut self,
+        lambda: &Lambda,
+        points: [(f64, f64); 3],
+        whole: f64,
+        tolerance: f64,
+        depth: u32,
+    ) -> Result<f64, EvaluationError> {
+        let [(a, fa), (m, fm), (b, fb)] = points;
+        let left_m = (a + m) / 2.0;
+        let right_m = (m + b) / 2.0;
+        let f_left_m = match self.apply_lambda_to_number(lambda, left_m) {
+            Ok(value) => value,
+            Err(error) => return Err(error),
+        };
+        let f_right_m = match self.apply_lambda_to_number(lambda, right_m) {
+            Ok(value) => value,
+            Err(error) => return Err(error),
+        };
+        let left = (m - a) / 6.0 * (fa + 4.0 * f_left_m + fm);
+        let right = (b - m) / 6.0 * (fm + 4.0 * f_right_m + fb);
+        if depth == 0 || (left + right - whole).abs() <= 15.0 * tolerance {
+            return Ok(left + right + (left + right - whole) / 15.0);
+        }
+        let left = match self.simpson(lambda, [(a, fa), (left_m, f_left_m), (m, fm)], left, tolerance / 2.0, depth - 1) {
+            Ok(value) => value,
+            Err(error) => return Err(error),
+        };
+        let right = match self.simpson(lambda, [(m, fm), (right_m, f_right_m), (b, fb)], right, tolerance / 2.0, depth - 1) {
+            Ok(value) => value,
+            Err(error) => return Err(error),
+        };
+        Ok(left + right)
+    }
+
+    // Newton's method from a starting point, with the derivative estimated by a central
+    // difference. Returns None if it does not settle on a root.
+    fn newton(&mut self, lambda: &Lambda, guess: f64) -> Result<Option<f64>, EvaluationError> {
+        let mut x = guess;
+        for _ in 0..MAX_SOLVE_ITERATIONS {
+            let fx = match self.apply_lambda_to_number(lambda, x) {
+                Ok(value) => value,
+                Err(error) => return Err(error),
+            };
+            if fx == 0.0 {
+                return Ok(Some(x));
+            }
+            let h = 1e-7 * x.abs().max(1.0);
+            let (f_above, f_below) = match (
+                self.apply_lambda_to_number(lambda, x + h),
+                self.apply_lambda_to_number(lambda, x - h),
+            ) {
+                (Ok(above), Ok(below)) => (above, below),
+                (Err(error), _) | (_, Err(error)) => return Err(error),
+            };
+            let derivative = (f_above - f_below) / (2.0 * h);
+            if derivative == 0.0 || !derivative.is_finite() {
+                return Ok(None);
+            }
+            let step = fx / derivative;
+            x -= step;
+            if !x.is_finite() {
+                return Ok(None);
+            }
+            if step.abs() <= SOLVE_TOLERANCE * x.abs().max(1.0) {
+                return Ok(Some(x));
+            }
+        }
+        Ok(None)
+    }
+
+    // Bisection of an interval where the function changes sign, which always finds a root
+    // if the function is continuous. Returns None if it does not change sign.
+    fn bisect(&mut self, lambda: &Lambda, a: f64, b: f64) -> Result<Option<f64>, EvaluationError> {
+        let (mut a, mut b) = (a.min(b), a.max(b));
+        let (mut fa, fb) = match (self.apply_lambda_to_number(lambda, a), self.apply_lambda_to_number(lambda, b)) {
+            (Ok(fa), Ok(fb)) => (fa, fb),
+            (Err(error), _) | (_, Err(error)) => return Err(error),
+        };
+        if fa == 0.0 {
+            return Ok(Some(a));
+        }
+        if fb == 0.0 {
+            return Ok(Some(b));
+        }
+        if fa.signum() == fb.signum() || fa.is_nan() || fb.is_nan() {
+            return Ok(None);
+        }
+        for _ in 0..MAX_BISECTIONS {
+            let m = (a + b) / 2.0;
+            if m <= a || m >= b {
+                break;
+            }
+            let fm = match self.apply_lambda_to_number(lambda, m) {
+                Ok(value) => value,
+                Err(error) => return Err(error),
+            };
+            if fm == 0.0 {
+                return Ok(Some(m));
+            }
+            if fm.signum() == fa.signum() {
+                a = m;
+                fa = fm;
+            } else {
+                b = m;
+            }
+        }
+        Ok(Some((a + b) / 2.0))
+    }
+
     pub fn init_consts(&mut self) {
-        self.variables.insert("pi".to_string(), std::f64::consts::PI);
-        self.variables.insert("e".to_string(), std::f64::consts::E);
-        self.variables.insert("phi".to_string(), 1.618033988749895);
-        self.variables.insert("tau".to_string(), std::f64::consts::TAU);
-        self.variables.insert("sqrt2".to_string(), 1.4142135623730951);
-        self.variables.insert("sqrt3".to_string(), 1.7320508075688772);
+        match self.settings.precision {
+            Some(digits) => {
+                let pi = precision::pi(digits);
+                let sqrt5 = precision::sqrt(&BigDecimal::from(5), digits + precision::GUARD_DIGITS);
+                let phi = ((sqrt5 + BigDecimal::from(1)).half()).round_to(digits);
+                self.variables.insert("pi".to_string(), Value::Decimal(pi.clone()));
+                self.variables.insert("e".to_string(), Value::Decimal(precision::exp(&BigDecimal::from(1), digits)));
+                self.variables.insert("phi".to_string(), Value::Decimal(phi));
+                let tau = precision::pi(digits + precision::GUARD_DIGITS).double().round_to(digits);
+                self.variables.insert("tau".to_string(), Value::Decimal(tau));
+                self.variables.insert("sqrt2".to_string(), Value::Decimal(precision::sqrt(&BigDecimal::from(2), digits)));
+                self.variables.insert("sqrt3".to_string(), Value::Decimal(precision::sqrt(&BigDecimal::from(3), digits)));
+                return;
+            }
+            None => (),
+        }
+        self.variables.insert("pi".to_string(), Value::Number(std::f64::consts::PI));
+        self.variables.insert("e".to_string(), Value::Number(std::f64::consts::E));
+        self.variables.insert("phi".to_string(), Value::Number(1.618033988749895));
+        self.variables.insert("tau".to_string(), Value::Number(std::f64::consts::TAU));
+        self.variables.insert("sqrt2".to_string(), Value::Number(std::f64::consts::SQRT_2));
+        self.variables.insert("sqrt3".to_string(), Value::Number(1.7320508075688772));
+    }
+}
+
+const INTEGRATION_TOLERANCE: f64 = 1e-12;
+const MAX_INTEGRATION_DEPTH: u32 = 20;
+const SOLVE_TOLERANCE: f64 = 1e-14;
+const MAX_SOLVE_ITERATIONS: usize = 100;
+const MAX_BISECTIONS: usize = 2000;
+
+fn sum_list(values: &[Value], settings: Settings) -> Result<Value, EvaluationError> {
+    let mut total = Value::Integer(BigInt::zero());
+    for value in values {
+        total = match total.apply_binary(&TokenType::Addition, value.clone(), settings) {
+            Ok(total) => total,
+            Err(error) => return Err(error),
+        };
+    }
+    Ok(total)
+}
+
+// The x in `A x = b`, which is a list when b is, and a matrix with a column for each column
+// of b when b is a matrix
+fn solve_linear(matrix: &Matrix, args: &[Value], settings: Settings) -> Result<Value, EvaluationError> {
+    let b = match args {
+        [Value::List(values)] if values.len() == matrix.rows => Matrix::from_column(values.clone()),
+        [Value::List(_)] => return Err(EvaluationError::ShapeMismatch {
+            expected: format!("[{}]", matrix.rows),
+            found: args[0].shape(),
+        }),
+        [Value::Matrix(b)] => b.clone(),
+        [other] => return Err(type_mismatch("list", other)),
+        _ => return Err(EvaluationError::ArityMismatch {
+            function: HigherOrderFunction::Solve.name().to_string(),
+            min: 2,
+            max: 2,
+            found: args.len() + 1,
+        }),
+    };
+    match (matrix.solve(&b, settings), &args[0]) {
+        (Ok(x), Value::List(_)) => Ok(Value::List(x.elements().to_vec())),
+        (Ok(x), _) => Ok(Value::Matrix(x)),
+        (Err(error), _) => Err(error),
+    }
+}
+
+fn type_mismatch(expected: &str, found: &Value) -> EvaluationError {
+    EvaluationError::TypeMismatch {
+        expected: expected.to_string(),
+        found: found.type_name().to_string(),
+    }
+}
+
+// The built-in functions that take a function as their first argument. These need to call back
+// into the environment, so they are called like user functions rather than as keywords, and
+// their names are not reserved: a user function or variable of the same name takes their place.
+//
+//  - `map(f, v)` applies f to each element of the list v, or to v itself if it is not a list
+//  - `sum(f, a, b)` adds up f(k) for each integer k from a to b, exactly where f allows it,
+//    and `sum(v)` adds up the elements of a list
+//  - `integrate(f, a, b)` integrates f from a to b numerically
+//  - `solve(f, x0)` finds a root of f near x0, and `solve(f, a, b)` one between a and b.
+//    `solve(A, b)` instead solves the linear system `A x = b` for a matrix A.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum HigherOrderFunction {
+    Map,
+    Sum,
+    Integrate,
+    Solve,
+}
+
+impl HigherOrderFunction {
+    pub fn name(&self) -> &str {
+        match self {
+            HigherOrderFunction::Map => "map",
+            HigherOrderFunction::Sum => "sum",
+            HigherOrderFunction::Integrate => "integrate",
+            HigherOrderFunction::Solve => "solve",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<HigherOrderFunction> {
+        match name {
+            "map" => Some(HigherOrderFunction::Map),
+            "sum" => Some(HigherOrderFunction::Sum),
+            "integrate" => Some(HigherOrderFunction::Integrate),
+            "solve" => Some(HigherOrderFunction::Solve),
+            _ => None,
+        }
+    }
+
+    // The smallest and largest number of arguments, including the function
+    pub fn arity(&self) -> (usize, usize) {
+        match self {
+            HigherOrderFunction::Map => (2, 2),
+            HigherOrderFunction::Sum => (1, 3),
+            HigherOrderFunction::Integrate => (3, 3),
+            HigherOrderFunction::Solve => (2, 3),
+        }
     }
 }
 
@@ -48,72 +676,1362 @@ pub enum Function {
     Sin,
     Cos,
     Tan,
+    Sec,
+    Csc,
+    Cot,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asinh,
+    Acosh,
+    Atanh,
+    Sqrt,
+    Cbrt,
+    Exp,
+    Ln,
+    Log,
+    Log10,
+    Log2,
+    Abs,
+    Floor,
+    Ceil,
+    Round,
+    Trunc,
+    Sign,
+    Min,
+    Max,
+    Mod,
+    Hypot,
+    Re,
+    Im,
+    Arg,
+    Conj,
+    Len,
+    Dot,
+    Cross,
+    Norm,
+    Transpose,
+    Det,
+    Inv,
+    Rank,
+    Eig,
 }
 
-impl AstNode {
-    pub fn evaluate(&self, environment: &mut Environment) -> EvalResult {
+pub const FUNCTIONS: [Function; 46] = [
+    Function::Sin, Function::Cos, Function::Tan, Function::Sec, Function::Csc, Function::Cot,
+    Function::Asin, Function::Acos, Function::Atan, Function::Atan2,
+    Function::Sinh, Function::Cosh, Function::Tanh, Function::Asinh, Function::Acosh, Function::Atanh,
+    Function::Sqrt, Function::Cbrt,
+    Function::Exp, Function::Ln, Function::Log, Function::Log10, Function::Log2,
+    Function::Abs, Function::Floor, Function::Ceil, Function::Round, Function::Trunc,
+    Function::Sign, Function::Min, Function::Max, Function::Mod, Function::Hypot,
+    Function::Re, Function::Im, Function::Arg, Function::Conj,
+    Function::Len, Function::Dot, Function::Cross, Function::Norm,
+    Function::Transpose, Function::Det, Function::Inv, Function::Rank, Function::Eig,
+];
+
+impl Function {
+    pub fn name(&self) -> &str {
         match self {
-            AstNode::Number(value) => EvalResult::Value(*value),
-            AstNode::UnaryOp {operator, operand} => {
-                let a: f64 = match operand.evaluate(environment) {
-                    EvalResult::Value(result) => result,
-                    EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
-                    EvalResult::Error(error) => return EvalResult::Error(error),
-                };
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Sec => "sec",
+            Function::Csc => "csc",
+            Function::Cot => "cot",
+            Function::Asin => "asin",
+            Function::Acos => "acos",
+            Function::Atan => "atan",
+            Function::Atan2 => "atan2",
+            Function::Sinh => "sinh",
+            Function::Cosh => "cosh",
+            Function::Tanh => "tanh",
+            Function::Asinh => "asinh",
+            Function::Acosh => "acosh",
+            Function::Atanh => "atanh",
+            Function::Sqrt => "sqrt",
+            Function::Cbrt => "cbrt",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+            Function::Log => "log",
+            Function::Log10 => "log10",
+            Function::Log2 => "log2",
+            Function::Abs => "abs",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Round => "round",
+            Function::Trunc => "trunc",
+            Function::Sign => "sign",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Mod => "mod",
+            Function::Hypot => "hypot",
+            Function::Re => "re",
+            Function::Im => "im",
+            Function::Arg => "arg",
+            Function::Conj => "conj",
+            Function::Len => "len",
+            Function::Dot => "dot",
+            Function::Cross => "cross",
+            Function::Norm => "norm",
+            Function::Transpose => "transpose",
+            Function::Det => "det",
+            Function::Inv => "inv",
+            Function::Rank => "rank",
+            Function::Eig => "eig",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Function> {
+        FUNCTIONS.into_iter().find(|function| function.name() == name)
+    }
 
-                match operator.apply_unary(a) {
-                    Ok(result) => EvalResult::Value(result),
-                    Err(error) => EvalResult::Error(error),
+    // The smallest and largest number of arguments the function accepts
+    pub fn arity(&self) -> (usize, usize) {
+        match self {
+            Function::Log => (1, 2),
+            Function::Min | Function::Max => (1, usize::MAX),
+            Function::Mod | Function::Hypot | Function::Atan2 | Function::Dot | Function::Cross => (2, 2),
+            _ => (1, 1),
+        }
+    }
+
+    // Calls the function on evaluated arguments, keeping the result exact where the function
+    // allows it, and otherwise working to the precision if one is set, or with floats. Complex
+    // arguments, and real ones the function takes out of the real numbers, give complex results.
+    pub fn call(&self, args: &[Value], settings: Settings) -> Result<Value, EvaluationError> {
+        if self.takes_lists() {
+            return self.apply_list(args, settings);
+        }
+        if self.takes_matrices() {
+            return self.apply_matrix(args, settings);
+        }
+        // `min` and `max` of a single list compare its elements
+        if let [Value::List(values)] = args
+            && matches!(self, Function::Min | Function::Max)
+        {
+            return self.call(values, settings);
+        }
+        if args.iter().any(|arg| matches!(arg, Value::List(_))) {
+            return self.broadcast(args, settings);
+        }
+        // Matrices are broadcast over as lists of their rows, which puts the result back together
+        if args.iter().any(|arg| matches!(arg, Value::Matrix(_))) {
+            let rows: Vec<Value> = args.iter()
+                .map(|arg| match arg {
+                    Value::Matrix(matrix) => Value::List(matrix.row_lists()),
+                    other => other.clone(),
+                })
+                .collect();
+            return match self.broadcast(&rows, settings) {
+                Ok(Value::List(rows)) => Ok(Value::from_list(rows)),
+                result => result,
+            };
+        }
+
+        if args.iter().any(|arg| matches!(arg, Value::Complex(_))) || self.has_complex_result(args) {
+            let mut values: Vec<Complex64> = Vec::with_capacity(args.len());
+            for arg in args {
+                match arg.as_complex() {
+                    Some(value) => values.push(value),
+                    None => return Err(EvaluationError::TypeMismatch {
+                        expected: "number".to_string(),
+                        found: arg.type_name().to_string(),
+                    }),
                 }
             }
-            AstNode::BinaryOp {operator, operand_1, operand_2} => {
-                let a: f64 = match operand_1.evaluate(environment) {
-                    EvalResult::Value(result) => result,
-                    EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
-                    EvalResult::Error(error) => return EvalResult::Error(error),
-                };
+            return match self.apply_complex(&values, settings.angle_mode) {
+                Ok(result) => Ok(Value::from_complex(result)),
+                Err(error) => Err(error),
+            };
+        }
 
-                let b: f64 = match operand_2.evaluate(environment) {
-                    EvalResult::Value(result) => result,
-                    EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
-                    EvalResult::Error(error) => return EvalResult::Error(error),
+        // An exact angle in degrees or gradians is reduced to within a turn, where multiples of
+        // 30 and 45 degrees have exact results, so `sin(30)` is exactly 0.5
+        if let [arg] = args
+            && let Some(x) = arg.as_rational()
+            && let Some(turn) = settings.angle_mode.full_turn()
+            && self.is_trigonometric()
+        {
+            let reduced = &x - &turn * (&x / &turn).floor();
+            match self.apply_exact_angle(&reduced, &turn, &x) {
+                Some(Ok(result)) => return Ok(Value::from_rational(result)),
+                Some(Err(error)) => return Err(error),
+                None => (),
+            }
+            if reduced != x {
+                return self.call(&[Value::from_rational(reduced)], settings);
+            }
+        }
+
+        let exact: Vec<BigRational> = args.iter().filter_map(|arg| arg.as_rational()).collect();
+        let is_exact = exact.len() == args.len();
+        if is_exact {
+            match self.apply_exact(&exact) {
+                Some(Ok(result)) => return Ok(Value::from_rational(result)),
+                Some(Err(error)) => return Err(error),
+                None => (),
+            }
+        }
+
+        if let Some(digits) = settings.precision {
+            let decimals: Vec<BigDecimal> = args.iter()
+                .filter_map(|arg| arg.as_decimal(digits + precision::GUARD_DIGITS))
+                .collect();
+            if decimals.len() == args.len() {
+                return match self.apply_decimal(&decimals, settings.angle_mode, digits, is_exact) {
+                    Ok(result) => Ok(Value::Decimal(result)),
+                    Err(error) => Err(error),
                 };
+            }
+        }
+
+        let mut values: Vec<f64> = Vec::with_capacity(args.len());
+        for arg in args {
+            match arg.as_number() {
+                Ok(value) => values.push(value),
+                Err(error) => return Err(error),
+            }
+        }
+
+        // Poles are reported by `apply`, so an infinity from finite arguments is an overflow
+        match self.apply(&values, settings.angle_mode, is_exact) {
+            Ok(result) if result.is_infinite() && values.iter().all(|value| value.is_finite()) => {
+                Err(EvaluationError::ResultTooLarge)
+            }
+            Ok(result) => Ok(Value::Number(result)),
+            Err(error) => Err(error),
+        }
+    }
+
+    // Applies the function to already evaluated arguments.
+    //
+    // Domain rules, reported as `EvaluationError::DomainError` instead of NaN:
+    //  - `asin(x)` and `acos(x)` require `-1 <= x <= 1`
+    //  - `acosh(x)` requires `x >= 1`, and `atanh(x)` requires `-1 < x < 1`
+    //  - `sqrt(x)` requires `x >= 0`
+    //  - `ln(x)`, `log10(x)`, `log2(x)` and `log(x)` require `x > 0`
+    //  - `log(x, base)` also requires `base > 0` and `base != 1`
+    //  - `mod(x, 0)` is a division by zero
+    //
+    // Poles, where the function goes to infinity, are reported as `EvaluationError::Undefined`:
+    //  - `tan(x)` and `sec(x)` at odd multiples of pi/2
+    //  - `cot(x)` and `csc(x)` at multiples of pi
+    //  - `atanh(x)` at -1 and 1
+    //
+    // An exact argument is on a pole of the trigonometric functions only if it is exactly 0, as
+    // the others are irrational in radians and found by `apply_exact_angle` in degrees and
+    // gradians. Otherwise the argument is a float that may be a rounded pole, see `is_at_pole`.
+    //
+    // Forward trigonometric functions take their input in the angle mode, and the inverse
+    // ones return their result in it. Errors report the input as it was given.
+    //
+    // `log(x)` on its own is the base 10 logarithm, and `mod(x, y)` takes the sign of `y`.
+    pub fn apply(&self, args: &[f64], angle_mode: AngleMode, exact: bool) -> Result<f64, EvaluationError> {
+        match self.check_arity(args.len()) {
+            Some(error) => return Err(error),
+            None => (),
+        }
 
-                match operator.apply_binary(a, b) {
-                    Ok(result) => EvalResult::Value(result),
-                    Err(error) => EvalResult::Error(error),
+        let x = args[0];
+        let angle = angle_mode.to_radians(x);
+        let result = match self {
+            Function::Sin => sin(angle),
+            Function::Cos => cos(angle),
+            Function::Tan | Function::Sec => {
+                if !exact && is_at_pole(angle, cos(angle)) {
+                    return Err(self.undefined(x));
                 }
+                if *self == Function::Tan { tan(angle) } else { 1.0 / cos(angle) }
             }
-            AstNode::Function {function, args} => {
-                let a: f64 = match args.evaluate(environment) {
-                    EvalResult::Value(result) => result,
-                    EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
-                    EvalResult::Error(error) => return EvalResult::Error(error),
+            Function::Cot | Function::Csc => {
+                if angle == 0.0 || !exact && is_at_pole(angle, sin(angle)) {
+                    return Err(self.undefined(x));
+                }
+                if *self == Function::Cot { 1.0 / tan(angle) } else { 1.0 / sin(angle) }
+            }
+            Function::Asin | Function::Acos => {
+                if !(-1.0..=1.0).contains(&x) {
+                    return Err(self.domain_error(x));
+                }
+                angle_mode.from_radians(if *self == Function::Asin { asin(x) } else { acos(x) })
+            }
+            Function::Atan => angle_mode.from_radians(atan(x)),
+            Function::Atan2 => angle_mode.from_radians(atan2(x, args[1])),
+            Function::Sinh => sinh(x),
+            Function::Cosh => cosh(x),
+            Function::Tanh => tanh(x),
+            Function::Asinh => asinh(x),
+            Function::Acosh => {
+                if x < 1.0 {
+                    return Err(self.domain_error(x));
+                }
+                acosh(x)
+            }
+            Function::Atanh => {
+                if x == 1.0 || x == -1.0 {
+                    return Err(self.undefined(x));
+                }
+                if !(-1.0..=1.0).contains(&x) {
+                    return Err(self.domain_error(x));
+                }
+                atanh(x)
+            }
+            Function::Sqrt => {
+                if x < 0.0 {
+                    return Err(self.domain_error(x));
+                }
+                sqrt(x)
+            }
+            Function::Cbrt => cbrt(x),
+            Function::Exp => exp(x),
+            Function::Ln | Function::Log | Function::Log10 | Function::Log2 => {
+                if x <= 0.0 {
+                    return Err(self.domain_error(x));
+                }
+                match (self, args.get(1)) {
+                    (Function::Ln, _) => log(x),
+                    (Function::Log2, _) => log2(x),
+                    (Function::Log, Some(&base)) => {
+                        if base <= 0.0 || base == 1.0 {
+                            return Err(self.domain_error(base));
+                        }
+                        log(x) / log(base)
+                    }
+                    _ => log10(x),
+                }
+            }
+            Function::Abs => fabs(x),
+            Function::Floor => floor(x),
+            Function::Ceil => ceil(x),
+            Function::Round => round(x),
+            Function::Trunc => trunc(x),
+            Function::Sign => {
+                if x == 0.0 { 0.0 } else { x.signum() }
+            }
+            Function::Min => args.iter().cloned().fold(f64::INFINITY, f64::min),
+            Function::Max => args.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Function::Mod => {
+                let y = args[1];
+                if y == 0.0 {
+                    return Err(EvaluationError::DivisionByZero);
+                }
+                x - y * floor(x / y)
+            }
+            Function::Hypot => hypot(x, args[1]),
+            Function::Re | Function::Conj => x,
+            Function::Im => 0.0,
+            Function::Arg => angle_mode.from_radians(atan2(0.0, x)),
+            Function::Len | Function::Dot | Function::Cross | Function::Norm => return Err(self.not_a_list()),
+            Function::Transpose | Function::Det | Function::Inv | Function::Rank | Function::Eig => {
+                return Err(self.not_a_matrix());
+            }
+        };
+
+        // Anything that still turns a number into NaN is outside the function's domain
+        if result.is_nan() && !args.iter().any(|arg| arg.is_nan()) {
+            return Err(self.domain_error(x));
+        }
+        Ok(result)
+    }
+
+    // Applies the function to arbitrary-precision arguments, following the same rules as
+    // `apply`, and rounds the result to the given number of significant digits
+    pub fn apply_decimal(&self, args: &[BigDecimal], angle_mode: AngleMode, digits: u64, exact: bool) -> Result<BigDecimal, EvaluationError> {
+        match self.check_arity(args.len()) {
+            Some(error) => return Err(error),
+            None => (),
+        }
+
+        let work = digits + precision::GUARD_DIGITS;
+        let x = &args[0];
+        // Errors report the argument as a float
+        let value = x.to_f64().unwrap_or(f64::NAN);
+        let one = BigDecimal::from(1);
+        let result = match self {
+            Function::Sin | Function::Cos | Function::Tan | Function::Sec | Function::Csc | Function::Cot => {
+                let angle = angle_mode.to_radians_decimal(x, work);
+                let pole = match self {
+                    Function::Tan | Function::Sec if !exact => {
+                        precision::is_multiple_of_pi(&(&angle - precision::pi(work).half()), digits)
+                    }
+                    Function::Csc | Function::Cot if !exact => precision::is_multiple_of_pi(&angle, digits),
+                    Function::Csc | Function::Cot => angle.is_zero(),
+                    _ => false,
                 };
-                
-                match function.apply_function(a) {
-                    Ok(result) => EvalResult::Value(result),
-                    Err(error) => EvalResult::Error(error),
+                if pole {
+                    return Err(self.undefined(value));
+                }
+                let sine = precision::sin(&angle, work);
+                let cosine = precision::cos(&angle, work);
+                match self {
+                    Function::Sin => sine,
+                    Function::Cos => cosine,
+                    Function::Tan => precision::divide(&sine, &cosine, work),
+                    Function::Sec => precision::divide(&one, &cosine, work),
+                    Function::Csc => precision::divide(&one, &sine, work),
+                    _ => precision::divide(&cosine, &sine, work),
+                }
+            }
+            Function::Asin | Function::Acos => {
+                if x.abs() > one {
+                    return Err(self.domain_error(value));
+                }
+                let angle = if *self == Function::Asin { precision::asin(x, work) } else { precision::acos(x, work) };
+                angle_mode.from_radians_decimal(&angle, work)
+            }
+            Function::Atan => angle_mode.from_radians_decimal(&precision::atan(x, work), work),
+            Function::Atan2 => angle_mode.from_radians_decimal(&precision::atan2(x, &args[1], work), work),
+            Function::Sinh => precision::sinh(x, work),
+            Function::Cosh => precision::cosh(x, work),
+            Function::Tanh => precision::tanh(x, work),
+            Function::Asinh => precision::asinh(x, work),
+            Function::Acosh => {
+                if *x < one {
+                    return Err(self.domain_error(value));
+                }
+                precision::acosh(x, work)
+            }
+            Function::Atanh => {
+                if x.abs() == one {
+                    return Err(self.undefined(value));
+                }
+                if x.abs() > one {
+                    return Err(self.domain_error(value));
+                }
+                precision::atanh(x, work)
+            }
+            Function::Sqrt => {
+                if x.is_negative() {
+                    return Err(self.domain_error(value));
+                }
+                precision::sqrt(x, work)
+            }
+            Function::Cbrt => precision::cbrt(x, work),
+            Function::Exp => precision::exp(x, work),
+            Function::Ln | Function::Log | Function::Log10 | Function::Log2 => {
+                if !x.is_positive() {
+                    return Err(self.domain_error(value));
+                }
+                let logarithm = precision::ln(x, work);
+                match (self, args.get(1)) {
+                    (Function::Ln, _) => logarithm,
+                    (Function::Log2, _) => precision::divide(&logarithm, &precision::ln(&BigDecimal::from(2), work), work),
+                    (Function::Log, Some(base)) => {
+                        if !base.is_positive() || *base == one {
+                            return Err(self.domain_error(base.to_f64().unwrap_or(f64::NAN)));
+                        }
+                        precision::divide(&logarithm, &precision::ln(base, work), work)
+                    }
+                    _ => precision::divide(&logarithm, &precision::ln(&BigDecimal::from(10), work), work),
+                }
+            }
+            Function::Abs => x.abs(),
+            Function::Floor => x.with_scale_round(0, RoundingMode::Floor),
+            Function::Ceil => x.with_scale_round(0, RoundingMode::Ceiling),
+            Function::Round => x.with_scale_round(0, RoundingMode::HalfUp),
+            Function::Trunc => x.with_scale_round(0, RoundingMode::Down),
+            Function::Sign => BigDecimal::from(x.signum().to_i32().unwrap_or(0)),
+            Function::Min => args.iter().fold(x.clone(), |min, arg| if *arg < min { arg.clone() } else { min }),
+            Function::Max => args.iter().fold(x.clone(), |max, arg| if *arg > max { arg.clone() } else { max }),
+            Function::Mod => {
+                let y = &args[1];
+                if y.is_zero() {
+                    return Err(EvaluationError::DivisionByZero);
+                }
+                let quotient = precision::divide(x, y, work + precision::GUARD_DIGITS).with_scale_round(0, RoundingMode::Floor);
+                x - y * quotient
+            }
+            Function::Hypot => precision::sqrt(&(x.square() + args[1].square()), work),
+            Function::Re | Function::Conj => x.clone(),
+            Function::Im => BigDecimal::zero(),
+            Function::Arg => {
+                if !x.is_negative() {
+                    return Ok(BigDecimal::zero());
+                }
+                angle_mode.from_radians_decimal(&precision::pi(work), work)
+            }
+            Function::Len | Function::Dot | Function::Cross | Function::Norm => return Err(self.not_a_list()),
+            Function::Transpose | Function::Det | Function::Inv | Function::Rank | Function::Eig => {
+                return Err(self.not_a_matrix());
+            }
+        };
+        Ok(result.round_to(digits))
+    }
+
+    fn check_arity(&self, found: usize) -> Option<EvaluationError> {
+        let (min_args, max_args) = self.arity();
+        if found < min_args || found > max_args {
+            return Some(EvaluationError::ArityMismatch {
+                function: self.name().to_string(),
+                min: min_args,
+                max: max_args,
+                found,
+            });
+        }
+        return None;
+    }
+
+    // Applies the function without leaving exact arithmetic, which is possible for the functions
+    // that only round or compare, and for roots of perfect powers. Returns None when the result
+    // has to be computed with floats instead, which also reports any errors in the arguments.
+    pub fn apply_exact(&self, args: &[BigRational]) -> Option<Result<BigRational, EvaluationError>> {
+        let (min_args, max_args) = self.arity();
+        if args.len() < min_args || args.len() > max_args {
+            return None;
+        }
+
+        let x = &args[0];
+        match self {
+            Function::Abs => Some(Ok(x.abs())),
+            Function::Floor => Some(Ok(x.floor())),
+            Function::Ceil => Some(Ok(x.ceil())),
+            Function::Round => Some(Ok(x.round())),
+            Function::Trunc => Some(Ok(x.trunc())),
+            Function::Sign => Some(Ok(x.signum())),
+            Function::Min => args.iter().min().cloned().map(Ok),
+            Function::Max => args.iter().max().cloned().map(Ok),
+            Function::Mod => {
+                let y = &args[1];
+                if y.is_zero() {
+                    return Some(Err(EvaluationError::DivisionByZero));
+                }
+                Some(Ok(x - y * (x / y).floor()))
+            }
+            Function::Sqrt if !x.is_negative() => {
+                let numerator = x.numer().sqrt();
+                let denominator = x.denom().sqrt();
+                if &(&numerator * &numerator) != x.numer() || &(&denominator * &denominator) != x.denom() {
+                    return None;
+                }
+                Some(Ok(BigRational::new(numerator, denominator)))
+            }
+            Function::Cbrt => {
+                let numerator = x.numer().cbrt();
+                let denominator = x.denom().cbrt();
+                if &numerator.pow(3) != x.numer() || &denominator.pow(3) != x.denom() {
+                    return None;
+                }
+                Some(Ok(BigRational::new(numerator, denominator)))
+            }
+            Function::Re | Function::Conj => Some(Ok(x.clone())),
+            Function::Im => Some(Ok(BigRational::zero())),
+            Function::Arg if !x.is_negative() => Some(Ok(BigRational::zero())),
+            _ => None,
+        }
+    }
+
+    fn is_trigonometric(&self) -> bool {
+        matches!(self, Function::Sin | Function::Cos | Function::Tan | Function::Sec | Function::Csc | Function::Cot)
+    }
+
+    // The trigonometric functions of an angle between 0 and a turn, where the angle is a multiple
+    // of a twelfth or an eighth of the turn and the result is rational. Returns None otherwise,
+    // and reports poles with the input as it was given.
+    fn apply_exact_angle(&self, angle: &BigRational, turn: &BigRational, x: &BigRational) -> Option<Result<BigRational, EvaluationError>> {
+        let half = || BigRational::new(BigInt::one(), BigInt::from(2));
+        // The sine of each twelfth of a turn, where None is an irrational multiple of sqrt(3)
+        let sine = |twelfths: usize| -> Option<BigRational> {
+            match twelfths % 12 {
+                0 | 6 => Some(BigRational::zero()),
+                1 | 5 => Some(half()),
+                3 => Some(BigRational::one()),
+                7 | 11 => Some(-half()),
+                9 => Some(-BigRational::one()),
+                _ => None,
+            }
+        };
+
+        let twelfths = angle * BigRational::from_integer(BigInt::from(12)) / turn;
+        let eighths = angle * BigRational::from_integer(BigInt::from(8)) / turn;
+        let (sin_x, cos_x, tan_x) = if twelfths.is_integer() {
+            let twelfths = twelfths.to_integer().to_usize()?;
+            let (sin_x, cos_x) = (sine(twelfths), sine(twelfths + 3));
+            let tan_x = match (&sin_x, &cos_x) {
+                (Some(sin_x), Some(cos_x)) if !cos_x.is_zero() => Some(sin_x / cos_x),
+                _ => None,
+            };
+            (sin_x, cos_x, tan_x)
+        } else if eighths.is_integer() {
+            // Odd eighths of a turn, where the sine and cosine are ±sqrt(2)/2 but the tangent is ±1
+            let eighths = eighths.to_integer().to_usize()?;
+            let tan_x = if eighths % 4 == 1 { BigRational::one() } else { -BigRational::one() };
+            (None, None, Some(tan_x))
+        } else {
+            return None;
+        };
+
+        let undefined = || Some(Err(self.undefined(x.to_f64().unwrap_or(f64::NAN))));
+        match self {
+            Function::Sin => sin_x.map(Ok),
+            Function::Cos => cos_x.map(Ok),
+            Function::Tan | Function::Sec if cos_x.as_ref().is_some_and(|cos_x| cos_x.is_zero()) => undefined(),
+            Function::Cot | Function::Csc if sin_x.as_ref().is_some_and(|sin_x| sin_x.is_zero()) => undefined(),
+            Function::Tan => tan_x.map(Ok),
+            Function::Sec => cos_x.map(|cos_x| Ok(cos_x.recip())),
+            Function::Csc => sin_x.map(|sin_x| Ok(sin_x.recip())),
+            Function::Cot => match (sin_x, cos_x) {
+                (Some(sin_x), Some(cos_x)) => Some(Ok(cos_x / sin_x)),
+                _ => tan_x.map(|tan_x| Ok(tan_x.recip())),
+            },
+            _ => None,
+        }
+    }
+
+    // Whether the function leaves the real numbers for these real arguments, which is the
+    // case for square roots and logarithms of negative numbers
+    pub fn has_complex_result(&self, args: &[Value]) -> bool {
+        match self {
+            Function::Sqrt | Function::Ln | Function::Log | Function::Log10 | Function::Log2 => {
+                args.iter().any(|arg| match arg.as_number() {
+                    Ok(value) => value < 0.0,
+                    Err(_) => false,
+                })
+            }
+            _ => false,
+        }
+    }
+
+    // Applies the function to complex arguments, giving the principal value. Functions that
+    // only make sense for real numbers, like `floor` or `min`, report a type mismatch.
+    //
+    // Trigonometric functions follow the angle mode as in `apply`, and `arg(z)` returns its
+    // angle in it.
+    pub fn apply_complex(&self, args: &[Complex64], angle_mode: AngleMode) -> Result<Complex64, EvaluationError> {
+        match self.check_arity(args.len()) {
+            Some(error) => return Err(error),
+            None => (),
+        }
+
+        let z = args[0];
+        let angle = z * angle_mode.to_radians(1.0);
+        let one = Complex64::new(1.0, 0.0);
+        let result = match self {
+            Function::Sin => angle.sin(),
+            Function::Cos => angle.cos(),
+            Function::Tan => angle.tan(),
+            Function::Sec => one / angle.cos(),
+            Function::Csc => one / angle.sin(),
+            Function::Cot => one / angle.tan(),
+            Function::Asin => z.asin() * angle_mode.from_radians(1.0),
+            Function::Acos => z.acos() * angle_mode.from_radians(1.0),
+            Function::Atan => z.atan() * angle_mode.from_radians(1.0),
+            Function::Sinh => z.sinh(),
+            Function::Cosh => z.cosh(),
+            Function::Tanh => z.tanh(),
+            Function::Asinh => z.asinh(),
+            Function::Acosh => z.acosh(),
+            Function::Atanh => z.atanh(),
+            Function::Sqrt => z.sqrt(),
+            Function::Cbrt => z.cbrt(),
+            Function::Exp => z.exp(),
+            Function::Ln | Function::Log | Function::Log10 | Function::Log2 => {
+                if z.is_zero() {
+                    return Err(self.domain_error(0.0));
+                }
+                match (self, args.get(1)) {
+                    (Function::Ln, _) => z.ln(),
+                    (Function::Log2, _) => z.log2(),
+                    (Function::Log, Some(&base)) => {
+                        if base.is_zero() || base.is_one() {
+                            return Err(self.domain_error(base.re));
+                        }
+                        z.ln() / base.ln()
+                    }
+                    _ => z.log10(),
                 }
             }
-            AstNode::Assignment {name, value} => {
-                let a: f64 = match value.evaluate(environment) {
-                    EvalResult::Value(result) => result,
-                    EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
+            Function::Abs => Complex64::new(z.norm(), 0.0),
+            Function::Re => Complex64::new(z.re, 0.0),
+            Function::Im => Complex64::new(z.im, 0.0),
+            Function::Arg => Complex64::new(angle_mode.from_radians(z.arg()), 0.0),
+            Function::Conj => z.conj(),
+            _ => {
+                return Err(EvaluationError::TypeMismatch {
+                    expected: "real number".to_string(),
+                    found: "complex number".to_string(),
+                });
+            }
+        };
+
+        // Poles such as `tan(pi/2 + 0i)` come out as infinities or NaN
+        if !result.is_finite() && args.iter().all(|arg| arg.is_finite()) {
+            return Err(self.undefined(z.re));
+        }
+        Ok(result)
+    }
+
+    // The functions that work on whole lists, rather than being applied to each element
+    fn takes_lists(&self) -> bool {
+        matches!(self, Function::Len | Function::Dot | Function::Cross | Function::Norm)
+    }
+
+    // The functions of linear algebra, which work on whole matrices
+    fn takes_matrices(&self) -> bool {
+        matches!(self, Function::Transpose | Function::Det | Function::Inv | Function::Rank | Function::Eig)
+    }
+
+    // Applies the function to each element of the list arguments, with the other arguments as
+    // they are, so `sin([0, pi])` is `[sin(0), sin(pi)]` and `mod([5, 7], 3)` is `[2, 1]`
+    fn broadcast(&self, args: &[Value], settings: Settings) -> Result<Value, EvaluationError> {
+        // The first list argument, and its length, which the others must have too
+        let mut first: Option<(&Value, usize)> = None;
+        for arg in args {
+            if let Value::List(values) = arg {
+                match first {
+                    Some((list, length)) if length != values.len() => {
+                        return Err(EvaluationError::ShapeMismatch { expected: list.shape(), found: arg.shape() });
+                    }
+                    Some(_) => (),
+                    None => first = Some((arg, values.len())),
+                }
+            }
+        }
+        let length = match first {
+            Some((_, length)) => length,
+            None => 0,
+        };
+
+        let mut results: Vec<Value> = Vec::with_capacity(length);
+        for i in 0..length {
+            let elements: Vec<Value> = args.iter()
+                .map(|arg| match arg {
+                    Value::List(values) => values[i].clone(),
+                    other => other.clone(),
+                })
+                .collect();
+            match self.call(&elements, settings) {
+                Ok(result) => results.push(result),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Value::List(results))
+    }
+
+    // `len(v)`, `dot(a, b)`, `cross(a, b)` for lists of 3 and `norm(v)`, the Euclidean length,
+    // which are all exact when the elements are
+    fn apply_list(&self, args: &[Value], settings: Settings) -> Result<Value, EvaluationError> {
+        match self.check_arity(args.len()) {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        let mut lists: Vec<&Vec<Value>> = Vec::with_capacity(args.len());
+        for arg in args {
+            match arg {
+                Value::List(values) => lists.push(values),
+                other => return Err(EvaluationError::TypeMismatch {
+                    expected: "list".to_string(),
+                    found: other.type_name().to_string(),
+                }),
+            }
+        }
+
+        let multiply = |a: &Value, b: &Value| a.clone().apply_binary(&TokenType::Multiplication, b.clone(), settings);
+        match self {
+            Function::Len => Ok(Value::Integer(BigInt::from(lists[0].len()))),
+            Function::Dot => {
+                if lists[0].len() != lists[1].len() {
+                    return Err(EvaluationError::ShapeMismatch { expected: args[0].shape(), found: args[1].shape() });
+                }
+                let mut total = Value::Integer(BigInt::zero());
+                for (a, b) in lists[0].iter().zip(lists[1]) {
+                    total = match multiply(a, b) {
+                        Ok(product) => match total.apply_binary(&TokenType::Addition, product, settings) {
+                            Ok(total) => total,
+                            Err(error) => return Err(error),
+                        },
+                        Err(error) => return Err(error),
+                    };
+                }
+                Ok(total)
+            }
+            Function::Cross => {
+                for (arg, list) in args.iter().zip(&lists) {
+                    if list.len() != 3 {
+                        return Err(EvaluationError::ShapeMismatch { expected: "[3]".to_string(), found: arg.shape() });
+                    }
+                }
+                let (a, b) = (lists[0], lists[1]);
+                let mut components: Vec<Value> = Vec::with_capacity(3);
+                for (i, j) in [(1, 2), (2, 0), (0, 1)] {
+                    let component = match (multiply(&a[i], &b[j]), multiply(&a[j], &b[i])) {
+                        (Ok(first), Ok(second)) => first.apply_binary(&TokenType::Subtraction, second, settings),
+                        (Err(error), _) | (_, Err(error)) => return Err(error),
+                    };
+                    match component {
+                        Ok(component) => components.push(component),
+                        Err(error) => return Err(error),
+                    }
+                }
+                Ok(Value::List(components))
+            }
+            Function::Norm => {
+                let mut total = Value::Integer(BigInt::zero());
+                for value in lists[0] {
+                    let square = match Function::Abs.call(std::slice::from_ref(value), settings) {
+                        Ok(magnitude) => multiply(&magnitude, &magnitude),
+                        Err(error) => return Err(error),
+                    };
+                    total = match square {
+                        Ok(square) => match total.apply_binary(&TokenType::Addition, square, settings) {
+                            Ok(total) => total,
+                            Err(error) => return Err(error),
+                        },
+                        Err(error) => return Err(error),
+                    };
+                }
+                Function::Sqrt.call(&[total], settings)
+            }
+            _ => Err(EvaluationError::InvalidOperation),
+        }
+    }
+
+    // `transpose(A)`, `det(A)`, `inv(A)`, `rank(A)` and `eig(A)`, the eigenvalues of a matrix of
+    // up to 3 by 3. Elimination keeps matrices of exact numbers exact, see `Matrix::row_reduce`.
+    fn apply_matrix(&self, args: &[Value], settings: Settings) -> Result<Value, EvaluationError> {
+        match self.check_arity(args.len()) {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        let matrix = match &args[0] {
+            Value::Matrix(matrix) => matrix,
+            other => return Err(EvaluationError::TypeMismatch {
+                expected: "matrix".to_string(),
+                found: other.type_name().to_string(),
+            }),
+        };
+        match self {
+            Function::Transpose => Ok(Value::Matrix(matrix.transpose())),
+            Function::Det => matrix.determinant(settings),
+            Function::Inv => match matrix.inverse(settings) {
+                Ok(inverse) => Ok(Value::Matrix(inverse)),
+                Err(error) => Err(error),
+            },
+            Function::Rank => match matrix.rank(settings) {
+                Ok(rank) => Ok(Value::Integer(BigInt::from(rank))),
+                Err(error) => Err(error),
+            },
+            Function::Eig => match matrix.eigenvalues(settings) {
+                Ok(eigenvalues) => Ok(Value::List(eigenvalues)),
+                Err(error) => Err(error),
+            },
+            _ => Err(EvaluationError::InvalidOperation),
+        }
+    }
+
+    fn not_a_list(&self) -> EvaluationError {
+        EvaluationError::TypeMismatch { expected: "list".to_string(), found: "number".to_string() }
+    }
+
+    fn not_a_matrix(&self) -> EvaluationError {
+        EvaluationError::TypeMismatch { expected: "matrix".to_string(), found: "number".to_string() }
+    }
+
+    fn domain_error(&self, value: f64) -> EvaluationError {
+        EvaluationError::DomainError { function: self.name().to_string(), value }
+    }
+
+    fn undefined(&self, value: f64) -> EvaluationError {
+        EvaluationError::Undefined { function: self.name().to_string(), value }
+    }
+}
+
+// Whether a float angle in radians lies on a pole, where `distance` is the sine or cosine that is
+// zero there, and so is about as far as the angle is from it. The rounding in values like `pi/2`
+// and `101*pi/2` grows with the angle, and so does the tolerance. A whole number other than 0,
+// which includes every float too large to have a fraction, is never on a pole.
+fn is_at_pole(angle: f64, distance: f64) -> bool {
+    if angle != 0.0 && angle.fract() == 0.0 {
+        return false;
+    }
+    distance.abs() <= 4.0 * f64::EPSILON * angle.abs().max(1.0)
+}
+
+impl AstNode {
+    // Every level of nesting and every call passes through here, so deep expressions and the
+    // call depth limit, however far it is raised, move on to a new stack segment instead of
+    // overflowing
+    pub fn evaluate(&self, environment: &mut Environment) -> EvalResult {
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || self.evaluate_node(environment))
+    }
+
+    fn evaluate_node(&self, environment: &mut Environment) -> EvalResult {
+        match self {
+            AstNode::Number(value) => AstNode::evaluate_number(value, environment),
+            AstNode::Boolean(value) => EvalResult::Value(Value::Boolean(*value)),
+            AstNode::ImaginaryUnit => EvalResult::Value(Value::Complex(Complex64::i())),
+            // A variable with the same name as a unit takes its place, so `h = 10; 2 h` is 20
+            AstNode::Unit(name, span) => AstNode::evaluate_unit(name, span, environment),
+            AstNode::UnaryOp {operator, operand, span} => AstNode::evaluate_unary(operator, operand, span, environment),
+            AstNode::PostfixOp {operator, operand, span} => AstNode::evaluate_postfix(operator, operand, span, environment),
+            AstNode::BinaryOp {operator, operand_1, operand_2, span} => AstNode::evaluate_binary(operator, operand_1, operand_2, span, environment),
+            AstNode::Function {function, args, span} => AstNode::evaluate_function(function, args, span, environment),
+            // Only the chosen branch is evaluated, so the other may be undefined
+            AstNode::Conditional {condition, if_true, if_false} => {
+                match AstNode::evaluate_condition(condition, environment) {
+                    Ok(true) => if_true.evaluate(environment),
+                    Ok(false) => if_false.evaluate(environment),
+                    Err(result) => result,
+                }
+            }
+            AstNode::Assignment {name, value, span} => AstNode::evaluate_assignment(name, value, span, environment),
+            // A user function named on its own is a value, so it can be passed like a lambda
+            AstNode::Variable(name, span) => AstNode::evaluate_variable(name, span, environment),
+            AstNode::Lambda {params, body, span} => AstNode::evaluate_lambda(params, body, span, environment),
+            AstNode::FunctionDefinition {name, params, body, span} => AstNode::evaluate_definition(name, params, body, span, environment),
+            AstNode::Call {name, args, span} => AstNode::evaluate_call(name, args, span, environment),
+            AstNode::Apply {callee, args, span} => AstNode::evaluate_apply(callee, args, span, environment),
+            AstNode::List {elements, span: _} => AstNode::evaluate_list(elements, environment),
+            AstNode::Matrix {rows, span} => AstNode::evaluate_matrix(rows, span, environment),
+            AstNode::Index {operand, index, span} => AstNode::evaluate_index(operand, index, span, environment),
+            AstNode::Slice {operand, start, end, span} => AstNode::evaluate_slice(operand, start, end, span, environment),
+            AstNode::Invalid => EvalResult::Error(EvaluationError::InvalidOperation),
+            // Statements run in order against the same environment, and the last one gives the result
+            AstNode::Sequence(statements) => AstNode::evaluate_sequence(statements, environment),
+        }
+    }
+
+    // Evaluates an operand that must give a value, passing anything else back to be returned
+    #[inline(never)]
+    fn evaluate_value(&self, environment: &mut Environment) -> Result<Value, EvalResult> {
+        match self.evaluate(environment) {
+            EvalResult::Value(result) => Ok(result),
+            other => Err(other),
+        }
+    }
+
+    // Each kind of node is evaluated in its own function, which keeps the frame of the recursive
+    // evaluate small, as every level of a deep recursion goes through it several times
+    #[inline(never)]
+    fn evaluate_number(value: &BigRational, environment: &mut Environment) -> EvalResult {
+        EvalResult::Value(Value::from_rational(value.clone()).wrap(environment.word_size()))
+    }
+
+    #[inline(never)]
+    fn evaluate_unit(name: &str, span: &Span, environment: &mut Environment) -> EvalResult {
+        if let Some(value) = environment.get_variable(name) {
+            return EvalResult::Value(value.clone());
+        }
+        match Quantity::from_unit(name) {
+            Some(quantity) => EvalResult::Value(Value::Quantity(Box::new(quantity))),
+            None => EvalResult::Error(EvaluationError::UndefinedVariable(name.to_string()).at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_condition(condition: &AstNode, environment: &mut Environment) -> Result<bool, EvalResult> {
+        match condition.evaluate(environment) {
+            EvalResult::Value(result) => Ok(result.is_truthy()),
+            EvalResult::Error(error) => Err(EvalResult::Error(error)),
+            other => Err(other),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_definition(name: &str, params: &[String], body: &AstNode, span: &Span, environment: &mut Environment) -> EvalResult {
+        match environment.define_function(name.to_string(), params.to_vec(), body.clone()) {
+            Some(error) => EvalResult::Error(error.at(*span)),
+            None => EvalResult::Definition(name.to_string()),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_unary(operator: &TokenType, operand: &AstNode, span: &Span, environment: &mut Environment) -> EvalResult {
+        let a: Value = match operand.evaluate(environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
+            EvalResult::Definition(name) => return EvalResult::Definition(name),
+            EvalResult::Error(error) => return EvalResult::Error(error),
+        };
+
+        match a.apply_unary(operator) {
+            Ok(result) => EvalResult::Value(result.wrap(environment.word_size())),
+            Err(error) => EvalResult::Error(error.at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_postfix(operator: &TokenType, operand: &AstNode, span: &Span, environment: &mut Environment) -> EvalResult {
+        let a: Value = match operand.evaluate(environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
+            EvalResult::Definition(name) => return EvalResult::Definition(name),
+            EvalResult::Error(error) => return EvalResult::Error(error),
+        };
+
+        match a.apply_postfix(operator, environment.settings()) {
+            Ok(result) => EvalResult::Value(result.wrap(environment.word_size())),
+            Err(error) => EvalResult::Error(error.at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_binary(operator: &TokenType, operand_1: &AstNode, operand_2: &AstNode, span: &Span, environment: &mut Environment) -> EvalResult {
+        let a: Value = match operand_1.evaluate_value(environment) {
+            Ok(result) => result,
+            Err(result) => return result,
+        };
+
+        // Logical operators short-circuit, leaving the right operand unevaluated
+        match operator {
+            TokenType::And if !a.is_truthy() => return EvalResult::Value(Value::Boolean(false)),
+            TokenType::Or if a.is_truthy() => return EvalResult::Value(Value::Boolean(true)),
+            _ => (),
+        }
+
+        let b: Value = match operand_2.evaluate_value(environment) {
+            Ok(result) => result,
+            Err(result) => return result,
+        };
+        AstNode::apply_binary(operator, a, b, span, environment)
+    }
+
+    #[inline(never)]
+    fn apply_binary(operator: &TokenType, a: Value, b: Value, span: &Span, environment: &mut Environment) -> EvalResult {
+        match a.apply_binary(operator, b, environment.settings()) {
+            Ok(result) => EvalResult::Value(result.wrap(environment.word_size())),
+            Err(error) => EvalResult::Error(error.at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_function(function: &TokenType, args: &[AstNode], span: &Span, environment: &mut Environment) -> EvalResult {
+        // An angle written with its unit, as in `sin(30°)`, is taken in that unit rather than
+        // converted to the angle mode, so it stays exact in radians too
+        let mut settings = environment.settings();
+        let mut args = args;
+        if let TokenType::Keyword(function) = function
+            && function.is_trigonometric()
+            && let [AstNode::PostfixOp {operator: TokenType::AngleUnit(unit), operand, ..}] = args
+        {
+            settings.angle_mode = *unit;
+            args = std::slice::from_ref(operand.as_ref());
+        }
+
+        let mut values: Vec<Value> = Vec::with_capacity(args.len());
+        for arg in args {
+            match arg.evaluate(environment) {
+                EvalResult::Value(result) => values.push(result),
+                EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
+                EvalResult::Definition(name) => return EvalResult::Definition(name),
+                EvalResult::Error(error) => return EvalResult::Error(error),
+            };
+        }
+
+        let result = match function {
+            TokenType::Keyword(function) => function.call(&values, settings),
+            _ => Err(EvaluationError::NotAFunction),
+        };
+        match result {
+            Ok(result) => EvalResult::Value(result.wrap(environment.word_size())),
+            Err(error) => EvalResult::Error(error.at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_assignment(name: &str, value: &AstNode, span: &Span, environment: &mut Environment) -> EvalResult {
+        let a: Value = match value.evaluate(environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Assignment(name, value) => return EvalResult::Assignment(name, value),
+            EvalResult::Definition(name) => return EvalResult::Definition(name),
+            EvalResult::Error(error) => return EvalResult::Error(error),
+        };
+        match environment.set_variable(name.to_string(), a.clone()) {
+            Some(error) => return EvalResult::Error(error.at(*span)),
+            None => (),
+        };
+        EvalResult::Assignment(name.to_string(), a)
+    }
+
+    #[inline(never)]
+    fn evaluate_variable(name: &str, span: &Span, environment: &mut Environment) -> EvalResult {
+        match environment.get_variable(name) {
+            Some(value) => EvalResult::Value(value.clone()),
+            None => match environment.get_function(name) {
+                Some(function) => EvalResult::Value(Value::Lambda(Rc::new(Lambda {
+                    params: function.params.clone(),
+                    body: function.body.clone(),
+                    captured: HashMap::new(),
+                }))),
+                None => EvalResult::Error(EvaluationError::UndefinedVariable(name.to_string()).at(*span)),
+            },
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_lambda(params: &[String], body: &AstNode, span: &Span, environment: &mut Environment) -> EvalResult {
+        for param in params {
+            if CONSTS.contains(&param.as_str()) {
+                return EvalResult::Error(EvaluationError::CannotAssignAConstant(param.clone()).at(*span));
+            }
+        }
+        let mut names: Vec<&str> = vec![];
+        body.variable_names(&mut names);
+        let mut captured: HashMap<String, Value> = HashMap::new();
+        for name in names {
+            if params.iter().any(|param| param == name) || CONSTS.contains(&name) {
+                continue;
+            }
+            if let Some(value) = environment.get_variable(name) {
+                captured.insert(name.to_string(), value.clone());
+            }
+        }
+        EvalResult::Value(Value::Lambda(Rc::new(Lambda {
+            params: params.to_vec(),
+            body: body.clone(),
+            captured,
+        })))
+    }
+
+    #[inline(never)]
+    fn evaluate_call(name: &str, args: &[AstNode], span: &Span, environment: &mut Environment) -> EvalResult {
+        let values: Vec<Value> = match AstNode::evaluate_arguments(args, environment) {
+            Ok(values) => values,
+            Err(result) => return result,
+        };
+
+        // A parameter shadows a function with the same name, so a lambda passed in can be called.
+        // The body of a function was parsed from an earlier input, so its spans mean nothing
+        // here and errors inside it are located at the call instead.
+        if environment.get_local_variable(name).is_none()
+            && let Some(function) = environment.get_function(name)
+        {
+            return match environment.call_function(name, &function, values) {
+                EvalResult::Error(error) => EvalResult::Error(error.without_span().at(*span)),
+                result => result,
+            };
+        }
+
+        let value = match environment.get_variable(name) {
+            Some(value) => value.clone(),
+            None => return AstNode::call_built_in(name, values, span, environment),
+        };
+        if let Value::Lambda(lambda) = &value {
+            return match environment.call_lambda(name, lambda, values) {
+                EvalResult::Error(error) => EvalResult::Error(error.without_span().at(*span)),
+                result => result,
+            };
+        }
+        AstNode::multiply_call(value, values, span, environment)
+    }
+
+    // A lambda written in the same input as its arguments keeps the location of errors in its
+    // body, while one given by a function is located at the call
+    #[inline(never)]
+    fn evaluate_apply(callee: &AstNode, args: &[AstNode], span: &Span, environment: &mut Environment) -> EvalResult {
+        let value = match callee.evaluate_value(environment) {
+            Ok(value) => value,
+            Err(result) => return result,
+        };
+        let values: Vec<Value> = match AstNode::evaluate_arguments(args, environment) {
+            Ok(values) => values,
+            Err(result) => return result,
+        };
+        let lambda = match value {
+            Value::Lambda(lambda) => lambda,
+            value => return AstNode::multiply_call(value, values, span, environment),
+        };
+        match environment.call_lambda("lambda", &lambda, values) {
+            EvalResult::Error(error) if matches!(callee, AstNode::Lambda {..}) => EvalResult::Error(error.at(*span)),
+            EvalResult::Error(error) => EvalResult::Error(error.without_span().at(*span)),
+            result => result,
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_arguments(args: &[AstNode], environment: &mut Environment) -> Result<Vec<Value>, EvalResult> {
+        let mut values: Vec<Value> = Vec::with_capacity(args.len());
+        for arg in args {
+            match arg.evaluate(environment) {
+                EvalResult::Value(result) => values.push(result),
+                EvalResult::Error(error) => return Err(EvalResult::Error(error)),
+                other => return Err(other),
+            };
+        }
+        Ok(values)
+    }
+
+    #[inline(never)]
+    fn call_built_in(name: &str, values: Vec<Value>, span: &Span, environment: &mut Environment) -> EvalResult {
+        match HigherOrderFunction::from_name(name) {
+            Some(function) => match environment.call_higher_order(function, values) {
+                Ok(result) => EvalResult::Value(result.wrap(environment.word_size())),
+                Err(error) => EvalResult::Error(error.without_span().at(*span)),
+            },
+            None => EvalResult::Error(EvaluationError::UndefinedFunction(name.to_string()).at(*span)),
+        }
+    }
+
+    // Not a function, so `x(2)` is the implicit multiplication `x * 2`
+    #[inline(never)]
+    fn multiply_call(value: Value, mut values: Vec<Value>, span: &Span, environment: &mut Environment) -> EvalResult {
+        if values.len() != 1 {
+            return EvalResult::Error(EvaluationError::NotAFunction.at(*span));
+        }
+        match value.apply_binary(&TokenType::Multiplication, values.remove(0), environment.settings()) {
+            Ok(result) => EvalResult::Value(result),
+            Err(error) => EvalResult::Error(error.at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_list(elements: &[AstNode], environment: &mut Environment) -> EvalResult {
+        let mut values: Vec<Value> = Vec::with_capacity(elements.len());
+        for element in elements {
+            match element.evaluate(environment) {
+                EvalResult::Value(result) => values.push(result),
+                EvalResult::Error(error) => return EvalResult::Error(error),
+                other => return other,
+            };
+        }
+        EvalResult::Value(Value::from_list(values))
+    }
+
+    #[inline(never)]
+    fn evaluate_matrix(rows: &[Vec<AstNode>], span: &Span, environment: &mut Environment) -> EvalResult {
+        let mut values: Vec<Value> = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut elements: Vec<Value> = Vec::with_capacity(row.len());
+            for element in row {
+                match element.evaluate(environment) {
+                    EvalResult::Value(result) => elements.push(result),
                     EvalResult::Error(error) => return EvalResult::Error(error),
+                    other => return other,
                 };
-                match environment.set_variable(name.clone(), a) {
-                    Some(error) => return EvalResult::Error(error),
-                    None => (),
-                };
-                EvalResult::Assignment(name.clone(), a)
             }
-            AstNode::Variable(name) => {
-                match environment.get_variable(name) {
-                    Some(value) => EvalResult::Value(*value),
-                    None => EvalResult::Error(EvaluationError::UndefinedVariable(name.clone())),
+            // Every row must be as long as the first
+            if let Some(Value::List(first)) = values.first()
+                && first.len() != elements.len()
+            {
+                return EvalResult::Error(EvaluationError::ShapeMismatch {
+                    expected: format!("[{}]", first.len()),
+                    found: format!("[{}]", elements.len()),
+                }.at(*span));
+            }
+            values.push(Value::List(elements));
+        }
+        EvalResult::Value(Value::from_list(values))
+    }
+
+    #[inline(never)]
+    fn evaluate_index(operand: &AstNode, index: &AstNode, span: &Span, environment: &mut Environment) -> EvalResult {
+        let a: Value = match operand.evaluate(environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Error(error) => return EvalResult::Error(error),
+            other => return other,
+        };
+        let b: Value = match index.evaluate(environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Error(error) => return EvalResult::Error(error),
+            other => return other,
+        };
+        match a.index(&b) {
+            Ok(result) => EvalResult::Value(result),
+            Err(error) => EvalResult::Error(error.at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_slice(operand: &AstNode, start: &Option<Box<AstNode>>, end: &Option<Box<AstNode>>, span: &Span, environment: &mut Environment) -> EvalResult {
+        let a: Value = match operand.evaluate(environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Error(error) => return EvalResult::Error(error),
+            other => return other,
+        };
+        let mut bounds: Vec<Option<Value>> = Vec::with_capacity(2);
+        for bound in [start, end] {
+            match bound {
+                Some(bound) => match bound.evaluate(environment) {
+                    EvalResult::Value(result) => bounds.push(Some(result)),
+                    EvalResult::Error(error) => return EvalResult::Error(error),
+                    other => return other,
+                },
+                None => bounds.push(None),
+            }
+        }
+        let end = bounds.pop().flatten();
+        let start = bounds.pop().flatten();
+        match a.slice(start, end) {
+            Ok(result) => EvalResult::Value(result),
+            Err(error) => EvalResult::Error(error.at(*span)),
+        }
+    }
+
+    #[inline(never)]
+    fn evaluate_sequence(statements: &[AstNode], environment: &mut Environment) -> EvalResult {
+        let mut result = EvalResult::Error(EvaluationError::InvalidOperation);
+        for statement in statements {
+            result = statement.evaluate(environment);
+            if let EvalResult::Error(_) = result {
+                return result;
+            }
+        }
+        result
+    }
+}
+
+impl AstNode {
+    // The names of the variables the expression refers to, which a lambda captures
+    fn variable_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            AstNode::Variable(name, _) | AstNode::Unit(name, _) => names.push(name),
+            AstNode::UnaryOp {operand, ..} | AstNode::PostfixOp {operand, ..} => operand.variable_names(names),
+            AstNode::BinaryOp {operand_1, operand_2, ..} => {
+                operand_1.variable_names(names);
+                operand_2.variable_names(names);
+            }
+            AstNode::Conditional {condition, if_true, if_false} => {
+                condition.variable_names(names);
+                if_true.variable_names(names);
+                if_false.variable_names(names);
+            }
+            AstNode::Function {args, ..} | AstNode::Call {args, ..} => {
+                // `x(2)` may be the implicit multiplication `x * 2`
+                if let AstNode::Call {name, ..} = self {
+                    names.push(name);
+                }
+                for arg in args {
+                    arg.variable_names(names);
+                }
+            }
+            AstNode::Apply {callee, args, ..} => {
+                callee.variable_names(names);
+                for arg in args {
+                    arg.variable_names(names);
+                }
+            }
+            AstNode::Assignment {value, ..} => value.variable_names(names),
+            AstNode::List {elements, ..} => {
+                for element in elements {
+                    element.variable_names(names);
+                }
+            }
+            AstNode::Matrix {rows, ..} => {
+                for element in rows.iter().flatten() {
+                    element.variable_names(names);
+                }
+            }
+            AstNode::Index {operand, index, ..} => {
+                operand.variable_names(names);
+                index.variable_names(names);
+            }
+            AstNode::Slice {operand, start, end, ..} => {
+                operand.variable_names(names);
+                for bound in [start, end].into_iter().flatten() {
+                    bound.variable_names(names);
+                }
+            }
+            AstNode::FunctionDefinition {body, ..} | AstNode::Lambda {body, ..} => body.variable_names(names),
+            AstNode::Sequence(statements) => {
+                for statement in statements {
+                    statement.variable_names(names);
                 }
             }
+            AstNode::Number(_) | AstNode::Boolean(_) | AstNode::ImaginaryUnit | AstNode::Invalid => (),
         }
     }
 }
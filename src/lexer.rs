@@ -1,13 +1,18 @@
-use crate::errors::{EvaluationError, LexerError};
-use libm::{sin, cos, tan};
-use std::f64::consts::{PI, FRAC_PI_2};
-use crate::evaluator::{Function, CONSTS};
+use crate::errors::LexerError;
+use crate::evaluator::{AngleMode, Function, CONSTS};
+use crate::locale::Locale;
+use crate::units;
 use unicode_ident::{is_xid_start, is_xid_continue};
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum TokenType {
     Number,
+    Boolean,
     Identifier,
+    // `i`, the imaginary unit
+    ImaginaryUnit,
+    // A physical unit such as `m` or `km`, see `TokenVector::expects_unit`
+    Unit,
     Keyword(Function),
     Equals,
 
@@ -18,6 +23,25 @@ pub enum TokenType {
     Division,
     Addition,
     Subtraction,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Equality,
+    NotEqual,
+    And,
+    Or,
+    Not,
+    // `to` or `in`, which converts a quantity to another unit
+    Conversion,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    BitwiseNot,
+    ShiftLeft,
+    ShiftRight,
+    // `->`, between the parameters and the body of a lambda
+    Arrow,
 
     // DELIMITERS
     LeftParenthesis,
@@ -29,10 +53,16 @@ pub enum TokenType {
 
     // PUNCTUATION
     Exclamation,
+    DoubleExclamation,
+    AngleUnit(AngleMode),
     Comma,
     Question,
     Colon,
     Semicolon,
+
+    // A word or character that could not be read, left in place of it so the rest of the
+    // input can still be tokenised
+    Invalid,
 }
 
 impl TokenType {
@@ -97,269 +127,460 @@ impl TokenType {
         }
     }
 */
-    pub fn apply_unary(&self, operand: f64) -> Result<f64, EvaluationError> {
-        match self {
-            TokenType::Negation => Ok(-operand),
-            _ => return Err(EvaluationError::InvalidOperation),
-        }
-    }
-
-    pub fn apply_function(&self, value: f64) -> Result<f64, EvaluationError> {
-        match self {
-            TokenType::Keyword(function) => match function {
-                Function::Sin => Ok(sin(value)),// TODO: sin(value)
-                Function::Cos => Ok(cos(value)),// TODO: cos(value)
-                Function::Tan => {
-                    let k = value * (2.0 / PI);
-                    if ((value - k * FRAC_PI_2).abs() < 1e-10) && (k as i64 % 2 != 0) { 
-                        Err(EvaluationError::Undefined)
-                    } else {
-                        Ok(tan(value))
-                    }
-                }
-            }
-            _ => return Err(EvaluationError::NotAFunction)
-        }
-    }
-    pub fn apply_binary(&self, operand_1: f64, operand_2: f64) -> Result<f64, EvaluationError> {
-        match self {
-            TokenType::Exponentiation => Ok(operand_1.powf(operand_2)),
-            TokenType::Multiplication => Ok(operand_1 * operand_2),
-            TokenType::Division => {
-                if operand_2 != 0.0 {
-                    return Ok(operand_1 / operand_2);
-                } else {
-                    return Err(EvaluationError::DivisionByZero);
-                }
-            }
-            TokenType::Addition => Ok(operand_1 + operand_2),
-            TokenType::Subtraction => Ok(operand_1 - operand_2),
-            _ => return Err(EvaluationError::InvalidOperation),
-        }
-    }
 }
 
 trait TokenVector {
-    fn push_word(&mut self, word: &str) -> Option<LexerError>;
+    fn push_word(&mut self, word: &str, span: Span) -> Option<LexerError>;
+    fn expects_unit(&self) -> bool;
 }
 
 impl TokenVector for Vec<Token> {
-    fn push_word(&mut self, word: &str) -> Option<LexerError> {
-        let mut split_index = 0;
-        let mut decimal_found = false;
-
-        for (i, c) in word.char_indices() {
-            if c.is_ascii_digit() {
-                split_index = i + c.len_utf8();
-            } else if c == '.' && !decimal_found {
-                decimal_found = true;
-                split_index = i + c.len_utf8();
-            } else {
-                break;
+    // The span is where the word was written, which may be longer than the word if a locale's
+    // group separators were left out of it
+    fn push_word(&mut self, word: &str, span: Span) -> Option<LexerError> {
+        // Hexadecimal, binary and octal literals are whole words, as their digits can be letters
+        if split_radix(word).is_some() {
+            if let Some(prev) = self.last() {
+                match prev.token_type {
+                    TokenType::Number
+                    | TokenType::Identifier
+                    | TokenType::ImaginaryUnit
+                    | TokenType::RightParenthesis
+                    | TokenType::RightBracket
+                    | TokenType::RightBrace => self.push(
+                        Token::new(TokenType::Multiplication, String::from("*"), Span::new(span.start, span.start))
+                    ),
+                    _ => (),
+                }
             }
+            self.push(Token::new(TokenType::Number, word.to_string(), span));
+            return None;
         }
-        let (number_part, rest) = word.split_at(split_index);
-        if !rest.is_empty() {
-            let token_type = match get_token_type(&rest) {
-                Ok(token_type) => token_type,
-                Err(error) => return Some(error),
-            };                
-            if matches!(token_type, TokenType::Identifier | TokenType::Keyword(_)) && !rest.is_empty() {
-                if let Some(prev) = self.last() {
-                    if !prev.lexeme.is_empty() {
-                        match prev.token_type {
-                            TokenType::Number
-                            | TokenType::Identifier
-                            | TokenType::RightParenthesis
-                            | TokenType::RightBracket
-                            | TokenType::RightBrace => self.push(
-                                Token::new(TokenType::Multiplication, String::from("*"))
-                            ),
-                            _ => (),
-                        }
+        // A prefix promises digits in its base, so `0b102` or `0x` is a mistake, not `0 * b102`
+        if let Some(prefix) = word.get(..2)
+            && ["0x", "0b", "0o"].contains(&prefix.to_lowercase().as_str())
+        {
+            self.push(Token::new(TokenType::Invalid, word.to_string(), span));
+            return Some(LexerError::InvalidToken(word.to_string(), span));
+        }
+
+        let (number_part, rest) = word.split_at(number_length(word));
+        // A `_` only separates digits, so one that is doubled or ends a number or starts its
+        // exponent, as in `1__0`, `1_` or `1e_3`, is a mistake. `3_x` is still `3 * _x`.
+        let starts_with_digit = |rest: &str| rest.starts_with(|c: char| c.is_ascii_digit());
+        let after_separators = rest.trim_start_matches('_');
+        let misplaced_separator = (rest.starts_with('_') && (after_separators.is_empty() || starts_with_digit(after_separators)))
+            || rest.strip_prefix(['e', 'E'])
+                .and_then(|exponent| exponent.strip_prefix('_'))
+                .is_some_and(starts_with_digit);
+        if !number_part.is_empty() && misplaced_separator {
+            self.push(Token::new(TokenType::Invalid, word.to_string(), span));
+            return Some(LexerError::InvalidToken(word.to_string(), span));
+        }
+        let rest_start = span.end - rest.chars().count();
+        let number_span = Span::new(span.start, rest_start);
+        let rest_span = Span::new(rest_start, span.end);
+        // A unit name directly after a number, as in `3m`, is always a unit
+        let rest_type = if rest.is_empty() {
+            None
+        } else if (!number_part.is_empty() || self.expects_unit()) && units::is_unit(rest) {
+            Some(TokenType::Unit)
+        } else {
+            match get_token_type(rest) {
+                Some(token_type) => Some(token_type),
+                None => {
+                    if !number_part.is_empty() {
+                        self.push(Token::new(TokenType::Number, number_part.to_string(), number_span));
                     }
-                };
+                    self.push(Token::new(TokenType::Invalid, rest.to_string(), rest_span));
+                    return Some(LexerError::InvalidIdentifier(rest.to_string(), rest_span));
+                }
+            }
+        };
+        if let Some(token_type) = &rest_type {
+            // Units are not multiplied in, as the parser binds them to the number before them
+            if matches!(token_type, TokenType::Identifier | TokenType::ImaginaryUnit | TokenType::Keyword(_))
+                && let Some(prev) = self.last()
+                && !prev.lexeme.is_empty()
+            {
+                match prev.token_type {
+                    TokenType::Number
+                    | TokenType::Identifier
+                    | TokenType::ImaginaryUnit
+                    | TokenType::Unit
+                    | TokenType::RightParenthesis
+                    | TokenType::RightBracket
+                    | TokenType::RightBrace => self.push(
+                        Token::new(TokenType::Multiplication, String::from("*"), Span::new(span.start, span.start))
+                    ),
+                    _ => (),
+                }
             }
         }
         
         if !number_part.is_empty() {
-            self.push(Token::new(TokenType::Number, number_part.to_string()));
-            if !rest.is_empty() {
-                let rest_token_type = match get_token_type(&rest) {
-                    Ok(token_type) => token_type,
-                    Err(error) => return Some(error),
-                };
-                self.push(Token::new(TokenType::Multiplication, String::from("*")));
+            self.push(Token::new(TokenType::Number, number_part.to_string(), number_span));
+            // A unit written directly after a number, as in `30deg`, marks the angle's unit
+            if let Some(unit) = AngleMode::from_name(rest) {
+                self.push(Token::new(TokenType::AngleUnit(unit), rest.to_string(), rest_span));
+            } else if let Some(rest_token_type) = rest_type {
+                if rest_token_type != TokenType::Unit {
+                    self.push(Token::new(TokenType::Multiplication, String::from("*"), Span::new(rest_start, rest_start)));
+                }
                 if CONSTS.contains(&rest.to_lowercase().as_str()) {
-                    self.push(Token::new(rest_token_type, rest.to_lowercase().to_string()));
+                    self.push(Token::new(rest_token_type, rest.to_lowercase().to_string(), rest_span));
                 } else {
-                    self.push(Token::new(rest_token_type, rest.to_string()));
+                    self.push(Token::new(rest_token_type, rest.to_string(), rest_span));
                 }
             }
-        } else {
-            let token_type = match get_token_type(&word) {
-                Ok(token_type) => token_type,
-                Err(error) => return Some(error),
-            };
+        } else if let Some(token_type) = rest_type {
             if CONSTS.contains(&word.to_lowercase().as_str()) {
-                self.push(Token::new(token_type, word.to_lowercase().to_string()));
+                self.push(Token::new(token_type, word.to_lowercase().to_string(), span));
             } else {
-                self.push(Token::new(token_type, word.to_string()));
+                self.push(Token::new(token_type, word.to_string(), span));
             }
         }
 
         return None;
         
     }
+
+    // Unit names are units after an operand, like a number, a variable or a parenthesis, after
+    // another unit or a conversion, and in the rest of a compound unit such as `km/h`. Anywhere
+    // else they are identifiers, so `m` can still be a variable.
+    fn expects_unit(&self) -> bool {
+        let mut tokens = self.iter().rev();
+        match tokens.next() {
+            Some(token) => match token.token_type {
+                TokenType::Number
+                | TokenType::Identifier
+                | TokenType::Unit
+                | TokenType::RightParenthesis
+                | TokenType::RightBracket
+                | TokenType::Conversion => true,
+                TokenType::Multiplication | TokenType::Division => match tokens.next() {
+                    Some(token) => token.token_type == TokenType::Unit,
+                    None => false,
+                },
+                _ => false,
+            },
+            None => false,
+        }
+    }
+}
+
+// Where something was written in the input, as character offsets from the start of it, with
+// the end exclusive
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    // The span from the start of this one to the end of the other
+    pub fn to(&self, other: Span) -> Span {
+        Span { start: self.start, end: other.end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String) -> Token {
-        Token { token_type, lexeme }
+    pub fn new(token_type: TokenType, lexeme: String, span: Span) -> Token {
+        Token { token_type, lexeme, span }
+    }
+
+    // Tokens added by the lexer, like the `*` in `2x`, have an empty span where they were added
+    pub fn is_implicit(&self) -> bool {
+        self.span.is_empty()
     }
 }
 
-pub fn tokenise(string: String) -> Result<Vec<Token>, LexerError> {
+pub fn tokenise(string: String) -> Result<Vec<Token>, Vec<LexerError>> {
+    return tokenise_in_locale(string, Locale::Plain);
+}
+
+// Numbers may be written with the separators of a locale, like `1,234,567.89`. These are
+// read here, so the number tokens are always written with a `.` and no groups.
+pub fn tokenise_in_locale(string: String, locale: Locale) -> Result<Vec<Token>, Vec<LexerError>> {
+    let (tokens, errors) = tokenise_with_errors(string, locale);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    return Ok(tokens);
+}
+
+// Tokenises the whole input even when parts of it cannot be read, so every problem can be
+// reported at once. Each part that could not be read is left as an invalid token.
+pub fn tokenise_with_errors(string: String, locale: Locale) -> (Vec<Token>, Vec<LexerError>) {
     let mut tokens: Vec<Token> = vec![];
+    let mut errors: Vec<LexerError> = vec![];
     let mut word = String::new();
 
-    for char in string.chars() {
+    let mut chars = string.chars().enumerate().peekable();
+    let mut word_start = 0;
+    while let Some((position, char)) = chars.next() {
         match char {
             ' ' | '\n' | '\t' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
                 word.clear();
             }
 
+            // The sign of an exponent, as in `1.5e-9`, is part of the number
+            '-' | '+' if is_mantissa(&word) && chars.peek().is_some_and(|(_, c)| c.is_ascii_digit()) => {
+                word.push(char);
+            }
+
             // EQUALS (ASSIGNMENT)
             '=' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Equals, char.to_string()));
+                if chars.peek().map(|&(_, c)| c) == Some('=') {
+                    chars.next();
+                    tokens.push(Token::new(TokenType::Equality, String::from("=="), Span::new(position, position + 2)));
+                } else {
+                    tokens.push(Token::new(TokenType::Equals, char.to_string(), Span::new(position, position + 1)));
+                }
                 word.clear();
             }
 
             //OPERATORS
+            '<' | '>' => {
+                if !word.is_empty() {
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
+                        None => (),
+                    };
+                }
+                if chars.peek().map(|&(_, c)| c) == Some(char) {
+                    chars.next();
+                    if char == '<' {
+                        tokens.push(Token::new(TokenType::ShiftLeft, String::from("<<"), Span::new(position, position + 2)));
+                    } else {
+                        tokens.push(Token::new(TokenType::ShiftRight, String::from(">>"), Span::new(position, position + 2)));
+                    }
+                    word.clear();
+                    continue;
+                }
+                let or_equal = chars.peek().map(|&(_, c)| c) == Some('=');
+                if or_equal {
+                    chars.next();
+                }
+                match (char, or_equal) {
+                    ('<', false) => tokens.push(Token::new(TokenType::LessThan, String::from("<"), Span::new(position, position + 1))),
+                    ('<', true) => tokens.push(Token::new(TokenType::LessThanOrEqual, String::from("<="), Span::new(position, position + 2))),
+                    (_, false) => tokens.push(Token::new(TokenType::GreaterThan, String::from(">"), Span::new(position, position + 1))),
+                    (_, true) => tokens.push(Token::new(TokenType::GreaterThanOrEqual, String::from(">="), Span::new(position, position + 2))),
+                }
+                word.clear();
+            }
+            '&' | '|' => {
+                if !word.is_empty() {
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
+                        None => (),
+                    };
+                }
+                // A single `&` or `|` is bitwise, a double one is logical
+                let logical = chars.peek().map(|&(_, c)| c) == Some(char);
+                if logical {
+                    chars.next();
+                }
+                match (char, logical) {
+                    ('&', true) => tokens.push(Token::new(TokenType::And, String::from("&&"), Span::new(position, position + 2))),
+                    ('&', false) => tokens.push(Token::new(TokenType::BitwiseAnd, String::from("&"), Span::new(position, position + 1))),
+                    (_, true) => tokens.push(Token::new(TokenType::Or, String::from("||"), Span::new(position, position + 2))),
+                    (_, false) => tokens.push(Token::new(TokenType::BitwiseOr, String::from("|"), Span::new(position, position + 1))),
+                }
+                word.clear();
+            }
+            '~' => {
+                if !word.is_empty() {
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
+                        None => (),
+                    };
+                }
+                tokens.push(Token::new(TokenType::BitwiseNot, char.to_string(), Span::new(position, position + 1)));
+                word.clear();
+            }
             '^' => {
                 if !word.is_empty() {
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Exponentiation, char.to_string()));
+                tokens.push(Token::new(TokenType::Exponentiation, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             '*' => {
                 if !word.is_empty() {
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Multiplication, char.to_string()));
+                tokens.push(Token::new(TokenType::Multiplication, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             '/' => {
                 if !word.is_empty() {
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Division, char.to_string()));
+                tokens.push(Token::new(TokenType::Division, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             } 
             '+' => {
                 if !word.is_empty() {
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Addition, char.to_string()));
+                tokens.push(Token::new(TokenType::Addition, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             '-' => {
                 if !word.is_empty() {
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
+                // `->` is always an arrow, whatever comes before it, so `x->-x` is a lambda
+                if chars.peek().map(|&(_, c)| c) == Some('>') {
+                    chars.next();
+                    tokens.push(Token::new(TokenType::Arrow, String::from("->"), Span::new(position, position + 2)));
+                    word.clear();
+                    continue;
+                }
                 let prev = match tokens.last() {
                     Some(token) => token.lexeme.clone(),
                     None => String::new(),
                 };
                 if !prev.is_empty() {
                     match get_token_type(&prev) {
-                        Ok(TokenType::Number) | Ok(TokenType::Identifier) => tokens.push(
-                            Token::new(TokenType::Subtraction, char.to_string())
+                        Some(TokenType::Number)
+                        | Some(TokenType::Identifier)
+                        | Some(TokenType::ImaginaryUnit)
+                        | Some(TokenType::RightParenthesis)
+                        | Some(TokenType::RightBracket)
+                        | Some(TokenType::RightBrace)
+                        | Some(TokenType::Exclamation)
+                        | Some(TokenType::DoubleExclamation)
+                        | Some(TokenType::AngleUnit(_)) => tokens.push(
+                            Token::new(TokenType::Subtraction, char.to_string(), Span::new(position, position + 1))
                             ),
-                        Err(error) => return Err(error),
-                        _ => tokens.push(Token::new(TokenType::Negation, char.to_string())),
+                        _ => tokens.push(Token::new(TokenType::Negation, char.to_string(), Span::new(position, position + 1))),
                     }
                 } else {
-                    tokens.push(Token::new(TokenType::Negation, char.to_string()));
+                    tokens.push(Token::new(TokenType::Negation, char.to_string(), Span::new(position, position + 1)));
                 }
                 word.clear();
             }
 
             // DELIMITERS
             '(' => {
-                if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                // A `_` ending a number is only allowed when it is called, as in `2_(3)`
+                let number = word.trim_end_matches('_');
+                if !number.is_empty() && number.len() < word.len() && number_length(number) == number.len() {
+                    let split = word_start + number.chars().count();
+                    tokens.push_word(number, Span::new(word_start, split));
+                    tokens.push_word(&word[number.len()..], Span::new(split, position));
+                } else if !word.is_empty() {
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
+                // A unit name followed by a parenthesis is a call, of a function like `min` or of
+                // one the user defined, like `g(x) = x + 1`
+                if let Some(token) = tokens.last()
+                    && token.token_type == TokenType::Unit
+                    && let Some(token) = tokens.pop()
+                {
+                    if let Some(prev) = tokens.last()
+                        && matches!(
+                            prev.token_type,
+                            TokenType::Number
+                                | TokenType::Identifier
+                                | TokenType::ImaginaryUnit
+                                | TokenType::Unit
+                                | TokenType::RightParenthesis
+                                | TokenType::RightBracket
+                                | TokenType::RightBrace
+                        )
+                    {
+                        let start = token.span.start;
+                        tokens.push(Token::new(TokenType::Multiplication, String::from("*"), Span::new(start, start)));
+                    }
+                    let token_type = match Function::from_name(&token.lexeme) {
+                        Some(function) => TokenType::Keyword(function),
+                        None => TokenType::Identifier,
+                    };
+                    tokens.push(Token::new(token_type, token.lexeme, token.span));
+                }
                 let prev = match tokens.last() {
                     Some(token) => token.lexeme.clone(),
                     None => String::new(),
                 };
+                // An identifier followed by a parenthesis may be a function call, so the parser decides
                 if !prev.is_empty() {
                     match get_token_type(&prev) {
-                        Ok(TokenType::Number) 
-                        | Ok(TokenType::Identifier) 
-                        | Ok(TokenType::RightParenthesis)
-                        | Ok(TokenType::RightBrace) => tokens.push(
-                            Token::new(TokenType::Multiplication, String::from("*"))
+                        Some(TokenType::Number) 
+                        | Some(TokenType::ImaginaryUnit)
+                        | Some(TokenType::RightParenthesis)
+                        | Some(TokenType::RightBracket)
+                        | Some(TokenType::RightBrace) => tokens.push(
+                            Token::new(TokenType::Multiplication, String::from("*"), Span::new(position, position))
                         ),
-                        Err(error) => return Err(error),
                         _ => (),
                     }
                 }
-                tokens.push(Token::new(TokenType::LeftParenthesis, char.to_string()));    
+                tokens.push(Token::new(TokenType::LeftParenthesis, char.to_string(), Span::new(position, position + 1)));    
                 word.clear();
             }
             ')' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::RightParenthesis, char.to_string()));
+                tokens.push(Token::new(TokenType::RightParenthesis, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             '{' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
@@ -369,34 +590,34 @@ pub fn tokenise(string: String) -> Result<Vec<Token>, LexerError> {
                 };
                 if !prev.is_empty() {
                     match get_token_type(&prev) {
-                        Ok(TokenType::Number) 
-                        | Ok(TokenType::Identifier) 
-                        | Ok(TokenType::RightParenthesis)
-                        | Ok(TokenType::RightBracket)
-                        |  Ok(TokenType::RightBrace) => tokens.push(
-                            Token::new(TokenType::Multiplication, String::from("*"))
+                        Some(TokenType::Number) 
+                        | Some(TokenType::Identifier)
+                        | Some(TokenType::ImaginaryUnit)
+                        | Some(TokenType::RightParenthesis)
+                        | Some(TokenType::RightBracket)
+                        |  Some(TokenType::RightBrace) => tokens.push(
+                            Token::new(TokenType::Multiplication, String::from("*"), Span::new(position, position))
                         ),
-                        Err(error) => return Err(error),
                         _ => (),
                     }
                 }
-                tokens.push(Token::new(TokenType::LeftBrace, char.to_string()));
+                tokens.push(Token::new(TokenType::LeftBrace, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             '}' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::RightBrace, char.to_string()));
+                tokens.push(Token::new(TokenType::RightBrace, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             '[' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
@@ -404,135 +625,280 @@ pub fn tokenise(string: String) -> Result<Vec<Token>, LexerError> {
                     Some(token) => token.lexeme.clone(),
                     None => String::new(),
                 };
+                // After a name, a `)` or a `]` the bracket indexes into a list, as in `v[0]`
                 if !prev.is_empty() {
                     match get_token_type(&prev) {
-                        Ok(TokenType::Number) 
-                        | Ok(TokenType::Identifier)
-                        | Ok(TokenType::RightParenthesis) 
-                        | Ok(TokenType::RightBracket)
-                        | Ok(TokenType::RightBrace) => tokens.push(
-                            Token::new(TokenType::Multiplication, String::from("*"))
+                        Some(TokenType::Number) 
+                        | Some(TokenType::ImaginaryUnit)
+                        | Some(TokenType::RightBrace) => tokens.push(
+                            Token::new(TokenType::Multiplication, String::from("*"), Span::new(position, position))
                         ),
-                        Err(error) => return Err(error),
                         _ => (),
                     }
                 }
-                tokens.push(Token::new(TokenType::LeftBracket, char.to_string()));
+                tokens.push(Token::new(TokenType::LeftBracket, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             } 
             ']' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::RightBracket, char.to_string()));
+                tokens.push(Token::new(TokenType::RightBracket, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
 
             // PUNCTUATION
             '!' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Exclamation, char.to_string()));
+                if chars.peek().map(|&(_, c)| c) == Some('!') {
+                    chars.next();
+                    tokens.push(Token::new(TokenType::DoubleExclamation, String::from("!!"), Span::new(position, position + 2)));
+                } else if chars.peek().map(|&(_, c)| c) == Some('=') {
+                    chars.next();
+                    tokens.push(Token::new(TokenType::NotEqual, String::from("!="), Span::new(position, position + 2)));
+                } else {
+                    tokens.push(Token::new(TokenType::Exclamation, char.to_string(), Span::new(position, position + 1)));
+                }
+                word.clear();
+            }
+            '°' => {
+                if !word.is_empty() { 
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
+                        None => (),
+                    };
+                }
+                tokens.push(Token::new(TokenType::AngleUnit(AngleMode::Degrees), char.to_string(), Span::new(position, position + 1)));
+                word.clear();
+            }
+            // A group separator comes before exactly three digits, as in `1,234`
+            _ if Some(char) == locale.group_separator() && is_integer_part(&word) && {
+                let mut lookahead = chars.clone();
+                (0..3).all(|_| lookahead.next().is_some_and(|(_, c)| c.is_ascii_digit()))
+                    && !lookahead.next().is_some_and(|(_, c)| c.is_ascii_digit() || c == '_')
+            } => (),
+            ',' if locale.decimal_separator() == ',' && is_integer_part(&word)
+                && chars.peek().is_some_and(|(_, c)| c.is_ascii_digit()) =>
+            {
+                word.push('.');
+            }
+            // In a locale where `.` groups digits, it cannot also be a decimal point
+            '.' if locale.decimal_separator() != '.' => {
+                if word.is_empty() {
+                    word_start = position;
+                }
+                let lexeme = format!("{}{}", word, char);
+                let span = Span::new(word_start, position + 1);
+                tokens.push(Token::new(TokenType::Invalid, lexeme.clone(), span));
+                errors.push(LexerError::InvalidToken(lexeme, span));
                 word.clear();
             }
             ',' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Comma, char.to_string()));
+                tokens.push(Token::new(TokenType::Comma, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             '?' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Question, char.to_string()));
+                tokens.push(Token::new(TokenType::Question, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             ':' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Colon, char.to_string()));
+                tokens.push(Token::new(TokenType::Colon, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             ';' => {
                 if !word.is_empty() { 
-                    match tokens.push_word(&word) {
-                        Some(error) => return Err(error),
+                    match tokens.push_word(&word, Span::new(word_start, position)) {
+                        Some(error) => errors.push(error),
                         None => (),
                     };
                 }
-                tokens.push(Token::new(TokenType::Semicolon, char.to_string()));
+                tokens.push(Token::new(TokenType::Semicolon, char.to_string(), Span::new(position, position + 1)));
                 word.clear();
             }
             _ => {
+                if word.is_empty() {
+                    word_start = position;
+                }
                 word.push(char);
             }
         }
     }
     if !word.is_empty() { 
-        match tokens.push_word(&word) {
-            Some(error) => return Err(error),
+        match tokens.push_word(&word, Span::new(word_start, string.chars().count())) {
+            Some(error) => errors.push(error),
             None => (),
         };
     }
-    return Ok(tokens);
+    return (tokens, errors);
 }
 
-fn get_token_type(token: &str) -> Result<TokenType, LexerError> {
+// The length of the number at the start of a word: digits with at most one `.`, and an
+// exponent like `e23` or `E-9` if digits follow the `e`. A word like `2e` or `2ex` is
+// left as `2` followed by the rest, so `e` is still the constant there.
+fn number_length(word: &str) -> usize {
+    let mut length = 0;
+    let mut decimal_found = false;
+    for (i, c) in word.char_indices() {
+        if is_digit(word, i, 10) {
+            length = i + 1;
+        } else if c == '.' && !decimal_found {
+            decimal_found = true;
+            length = i + 1;
+        } else {
+            break;
+        }
+    }
+    if !word[..length].chars().any(|c| c.is_ascii_digit()) {
+        return length;
+    }
+
+    let exponent = &word[length..];
+    let mut exponent_chars = exponent.char_indices().peekable();
+    match exponent_chars.next() {
+        Some((_, 'e')) | Some((_, 'E')) => (),
+        _ => return length,
+    }
+    if let Some((_, '-')) | Some((_, '+')) = exponent_chars.peek() {
+        exponent_chars.next();
+    }
+    let mut exponent_length = 0;
+    for (i, _) in exponent_chars {
+        if !is_digit(exponent, i, 10) {
+            break;
+        }
+        exponent_length = i + 1;
+    }
+    if exponent_length == 0 {
+        return length;
+    }
+    return length + exponent_length;
+}
+
+// Whether the character at a position is a digit, or a `_` separating two digits as in
+// `1_000_000`
+fn is_digit(word: &str, position: usize, radix: u32) -> bool {
+    let mut chars = word[position..].chars();
+    match chars.next() {
+        Some('_') => {
+            word[..position].chars().next_back().is_some_and(|c| c.is_digit(radix))
+                && chars.next().is_some_and(|c| c.is_digit(radix))
+        }
+        Some(c) => c.is_digit(radix),
+        None => false,
+    }
+}
+
+// Whether a word is the whole part of a number so far, which a locale's separators may follow
+fn is_integer_part(word: &str) -> bool {
+    return word.starts_with(|c: char| c.is_ascii_digit()) && word.chars().all(|c| c.is_ascii_digit() || c == '_');
+}
+
+// Whether a word is a number waiting for the sign of its exponent, like `1.5e`
+fn is_mantissa(word: &str) -> bool {
+    match word.strip_suffix(['e', 'E']) {
+        Some(mantissa) => !mantissa.is_empty() && number_length(mantissa) == mantissa.len() && mantissa != ".",
+        None => false,
+    }
+}
+
+// The radix and digits of a hexadecimal (`0xFF`), binary (`0b1010`) or octal (`0o755`)
+// literal, or None if the word is not one
+pub fn split_radix(word: &str) -> Option<(u32, &str)> {
+    let radix = match word.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0b") | Some("0B") => 2,
+        Some("0o") | Some("0O") => 8,
+        _ => return None,
+    };
+    let digits = &word[2..];
+    if digits.is_empty() || !digits.char_indices().all(|(i, _)| is_digit(digits, i, radix)) {
+        return None;
+    }
+    return Some((radix, digits));
+}
+
+fn get_token_type(token: &str) -> Option<TokenType> {
     match token {
-        "^" => Ok(TokenType::Exponentiation),
-        "*" => Ok(TokenType::Multiplication),
-        "/" => Ok(TokenType::Division),
-        "+" => Ok(TokenType::Addition),
-        "-" => Ok(TokenType::Subtraction),
-
-        "(" => Ok(TokenType::LeftParenthesis),
-        ")" => Ok(TokenType::RightParenthesis),
-        "{" => Ok(TokenType::LeftBrace),
-        "}" => Ok(TokenType::RightBrace),
-        "[" => Ok(TokenType::LeftBracket),
-        "]" => Ok(TokenType::RightBracket),
-
-        "!" => Ok(TokenType::Exclamation),
-        "," => Ok(TokenType::Comma),
-        "?" => Ok(TokenType::Question),
-        ":" => Ok(TokenType::Colon),
-        ";" => Ok(TokenType::Semicolon),
-
-        _ if token.parse::<f64>().is_ok() => Ok(TokenType::Number),
-
-        "sin" => Ok(TokenType::Keyword(Function::Sin)),
-        "cos" => Ok(TokenType::Keyword(Function::Cos)),
-        "tan" => Ok(TokenType::Keyword(Function::Tan)),
+        "=" => Some(TokenType::Equals),
+        "^" => Some(TokenType::Exponentiation),
+        "*" => Some(TokenType::Multiplication),
+        "/" => Some(TokenType::Division),
+        "+" => Some(TokenType::Addition),
+        "-" => Some(TokenType::Subtraction),
+        "<" => Some(TokenType::LessThan),
+        "<=" => Some(TokenType::LessThanOrEqual),
+        ">" => Some(TokenType::GreaterThan),
+        ">=" => Some(TokenType::GreaterThanOrEqual),
+        "==" => Some(TokenType::Equality),
+        "!=" => Some(TokenType::NotEqual),
+        "&&" => Some(TokenType::And),
+        "||" => Some(TokenType::Or),
+        "not" => Some(TokenType::Not),
+        "&" => Some(TokenType::BitwiseAnd),
+        "|" => Some(TokenType::BitwiseOr),
+        "xor" => Some(TokenType::BitwiseXor),
+        "~" => Some(TokenType::BitwiseNot),
+        "<<" => Some(TokenType::ShiftLeft),
+        ">>" => Some(TokenType::ShiftRight),
+        "->" => Some(TokenType::Arrow),
+        "true" | "false" => Some(TokenType::Boolean),
+        "i" => Some(TokenType::ImaginaryUnit),
+        "to" | "in" => Some(TokenType::Conversion),
+
+        "(" => Some(TokenType::LeftParenthesis),
+        ")" => Some(TokenType::RightParenthesis),
+        "{" => Some(TokenType::LeftBrace),
+        "}" => Some(TokenType::RightBrace),
+        "[" => Some(TokenType::LeftBracket),
+        "]" => Some(TokenType::RightBracket),
+
+        "!" => Some(TokenType::Exclamation),
+        "!!" => Some(TokenType::DoubleExclamation),
+        "°" => Some(TokenType::AngleUnit(AngleMode::Degrees)),
+        "," => Some(TokenType::Comma),
+        "?" => Some(TokenType::Question),
+        ":" => Some(TokenType::Colon),
+        ";" => Some(TokenType::Semicolon),
+
+        _ if !token.is_empty() && number_length(token) == token.len() && token != "." => Some(TokenType::Number),
+        _ if split_radix(token).is_some() => Some(TokenType::Number),
+
+        _ if Function::from_name(token).is_some() => Function::from_name(token).map(TokenType::Keyword),
         _ if {
             let mut chars = token.chars();
             match chars.next() {
                 Some(c) if c == '_' || is_xid_start(c) => chars.all(is_xid_continue),
                 _ => false,      
             }
-        } => Ok(TokenType::Identifier),
+        } => Some(TokenType::Identifier),
 
-        _ => {
-            return Err(LexerError::InvalidIdentifier(token.to_string()));
-        }
+        _ => None,
     }
 }
 
@@ -572,6 +938,7 @@ mod tests {
     #[test]
     fn test_get_token_type_punctuation() {
         assert_eq!(get_token_type("!").unwrap(), TokenType::Exclamation);
+        assert_eq!(get_token_type("!!").unwrap(), TokenType::DoubleExclamation);
         assert_eq!(get_token_type(",").unwrap(), TokenType::Comma);
         assert_eq!(get_token_type("?").unwrap(), TokenType::Question);
         assert_eq!(get_token_type(":").unwrap(), TokenType::Colon);
@@ -599,18 +966,21 @@ mod tests {
         assert_eq!(get_token_type("sin").unwrap(), TokenType::Keyword(Function::Sin));
         assert_eq!(get_token_type("cos").unwrap(), TokenType::Keyword(Function::Cos));
         assert_eq!(get_token_type("tan").unwrap(), TokenType::Keyword(Function::Tan));
+        assert_eq!(get_token_type("sqrt").unwrap(), TokenType::Keyword(Function::Sqrt));
+        assert_eq!(get_token_type("log10").unwrap(), TokenType::Keyword(Function::Log10));
+        assert_eq!(get_token_type("max").unwrap(), TokenType::Keyword(Function::Max));
     }
 
     #[test]
     fn test_push_word_basic() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("foo");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         }
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Identifier, lexeme: "foo".to_string() }
+            Token { token_type: TokenType::Identifier, lexeme: "foo".to_string(), span: Span::new(0, 3) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -619,12 +989,12 @@ mod tests {
     fn test_push_word_number() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("2");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Number, lexeme: "2".to_string() }
+            Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -633,14 +1003,14 @@ mod tests {
     fn test_push_leading_number() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("2foo");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-            Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-            Token { token_type: TokenType::Identifier, lexeme: "foo".to_string() },
+            Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+            Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+            Token { token_type: TokenType::Identifier, lexeme: "foo".to_string(), span: Span::new(1, 4) },
         ];
         assert_eq!(tokens, expected_tokens)
     }
@@ -649,12 +1019,12 @@ mod tests {
     fn test_push_utf8() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("foo_bar_π");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Identifier, lexeme: "foo_bar_π".to_string() }
+            Token { token_type: TokenType::Identifier, lexeme: "foo_bar_π".to_string(), span: Span::new(0, 9) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -663,12 +1033,12 @@ mod tests {
     fn test_push_word_with_double_digit_number() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("42");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Number, lexeme: "42".to_string() }
+            Token { token_type: TokenType::Number, lexeme: "42".to_string(), span: Span::new(0, 2) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -677,12 +1047,12 @@ mod tests {
     fn test_push_word_with_decimal_number() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("3.14");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Number, lexeme: "3.14".to_string() }
+            Token { token_type: TokenType::Number, lexeme: "3.14".to_string(), span: Span::new(0, 4) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -691,14 +1061,14 @@ mod tests {
     fn test_push_word_with_decimal_number_and_identifier() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("3.14foo");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Number, lexeme: "3.14".to_string() },
-            Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-            Token { token_type: TokenType::Identifier, lexeme: "foo".to_string() },
+            Token { token_type: TokenType::Number, lexeme: "3.14".to_string(), span: Span::new(0, 4) },
+            Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(4, 4) },
+            Token { token_type: TokenType::Identifier, lexeme: "foo".to_string(), span: Span::new(4, 7) },
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -707,27 +1077,27 @@ mod tests {
     fn test_push_word_with_trailing_number() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("foo42");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Identifier, lexeme: "foo42".to_string() },
+            Token { token_type: TokenType::Identifier, lexeme: "foo42".to_string(), span: Span::new(0, 5) },
         ];
         assert_eq!(tokens, expected_tokens);
     }
 
-    #[should_panic(expected = "LexerError: InvalidIdentifier(\"🍕\")")]
+    #[should_panic(expected = "LexerError: InvalidIdentifier(\"🍕\", Span { start: 0, end: 1 })")]
     #[test]
     fn test_push_word_with_leading_emoji() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("🍕");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Identifier, lexeme: "🍕".to_string() }
+            Token { token_type: TokenType::Identifier, lexeme: "🍕".to_string(), span: Span::new(0, 1) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -736,12 +1106,12 @@ mod tests {
     fn test_push_word_with_leading_underscore() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("_foo");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Identifier, lexeme: "_foo".to_string() }
+            Token { token_type: TokenType::Identifier, lexeme: "_foo".to_string(), span: Span::new(0, 4) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -750,14 +1120,14 @@ mod tests {
     fn test_push_word_with_leading_number_and_underscore() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("3_foo");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-            Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-            Token { token_type: TokenType::Identifier, lexeme: "_foo".to_string() },
+            Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(0, 1) },
+            Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+            Token { token_type: TokenType::Identifier, lexeme: "_foo".to_string(), span: Span::new(1, 5) },
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -766,12 +1136,12 @@ mod tests {
     fn test_push_word_only_underscore() {
         let mut tokens: Vec<Token> = vec![];
         let word = String::from("_");
-        match tokens.push_word(&word) {
+        match tokens.push_word(&word, Span::new(0, word.chars().count())) {
             Some(error) => panic!("LexerError: {:?}", error),
             None => (),
         };
         let expected_tokens: Vec<Token> = vec![
-            Token { token_type: TokenType::Identifier, lexeme: "_".to_string() }
+            Token { token_type: TokenType::Identifier, lexeme: "_".to_string(), span: Span::new(0, 1) }
         ];
         assert_eq!(tokens, expected_tokens);
     }
@@ -1,4 +1,13 @@
+// The codebase favours explicit `return`s and `match` over `?` and `if let` for readability
+#![allow(clippy::needless_return, clippy::single_match, clippy::question_mark)]
+
 pub mod errors;
 pub mod lexer;
 pub mod parser;
-pub mod evaluator;
\ No newline at end of file
+pub mod evaluator;
+pub mod precision;
+pub mod value;
+pub mod matrix;
+pub mod units;
+pub mod locale;
+pub mod diagnostic;
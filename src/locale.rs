@@ -0,0 +1,127 @@
+// How numbers are written, both when they are typed in and when results are shown
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Locale {
+    // `1234567.89`, with commas only separating arguments
+    Plain,
+    // `1,234,567.89`
+    Us,
+    // `1.234.567,89`
+    Eu,
+}
+
+impl Locale {
+    pub fn name(&self) -> &str {
+        match self {
+            Locale::Plain => "plain",
+            Locale::Us => "US",
+            Locale::Eu => "European",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Locale> {
+        match name {
+            "plain" | "off" => Some(Locale::Plain),
+            "us" | "US" => Some(Locale::Us),
+            "eu" | "EU" => Some(Locale::Eu),
+            _ => None,
+        }
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            Locale::Plain | Locale::Us => '.',
+            Locale::Eu => ',',
+        }
+    }
+
+    // The separator between groups of three digits, if the locale has one
+    pub fn group_separator(&self) -> Option<char> {
+        match self {
+            Locale::Plain => None,
+            Locale::Us => Some(','),
+            Locale::Eu => Some('.'),
+        }
+    }
+
+    // Rewrites every number in a result, which is written with a `.` and no groups, in this
+    // locale. Numbers inside complex numbers and quantities are rewritten too. Numbers in lists
+    // and matrices are not grouped, as a `,` there separates elements and a matrix's columns
+    // are already lined up.
+    pub fn format(&self, text: &str) -> String {
+        if *self == Locale::Plain {
+            return text.to_string();
+        }
+        let mut formatted = String::with_capacity(text.len());
+        let mut number = String::new();
+        let mut depth = 0;
+        for c in text.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+                continue;
+            }
+            if !number.is_empty() {
+                formatted.push_str(&self.format_number(&number, depth == 0));
+                number.clear();
+            }
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => (),
+            }
+            formatted.push(c);
+        }
+        if !number.is_empty() {
+            formatted.push_str(&self.format_number(&number, depth == 0));
+        }
+        return formatted;
+    }
+
+    fn format_number(&self, number: &str, grouped: bool) -> String {
+        let (whole, fraction) = match number.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (number, None),
+        };
+        let mut formatted = String::with_capacity(number.len() + whole.len() / 3);
+        for (i, digit) in whole.chars().enumerate() {
+            if grouped && i > 0 && (whole.len() - i) % 3 == 0 {
+                match self.group_separator() {
+                    Some(separator) => formatted.push(separator),
+                    None => (),
+                }
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal_separator());
+            formatted.push_str(fraction);
+        }
+        return formatted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Locale::Us.format("-1234567.89"), "-1,234,567.89");
+        assert_eq!(Locale::Eu.format("-1234567.89"), "-1.234.567,89");
+        assert_eq!(Locale::Eu.format("1500 + 2.5i"), "1.500 + 2,5i");
+        assert_eq!(Locale::Plain.format("1234567.89"), "1234567.89");
+    }
+
+    #[test]
+    fn test_format_small_numbers() {
+        assert_eq!(Locale::Us.format("123"), "123");
+        assert_eq!(Locale::Us.format("0.000015"), "0.000015");
+    }
+
+    #[test]
+    fn test_format_lists_without_groups() {
+        assert_eq!(Locale::Us.format("[1234, 5]"), "[1234, 5]");
+        assert_eq!(Locale::Eu.format("[1234.5, 2]"), "[1234,5, 2]");
+        assert_eq!(Locale::Us.format("[ 1234  2 ]\n[    3  4 ]"), "[ 1234  2 ]\n[    3  4 ]");
+        assert_eq!(Locale::Us.format("1234 m"), "1,234 m");
+    }
+}
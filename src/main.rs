@@ -1,9 +1,14 @@
-use terminal_calculator::lexer::{Token, TokenType, tokenise};
+// The codebase favours explicit `return`s and `match` over `?` and `if let` for readability
+#![allow(clippy::needless_return, clippy::single_match, clippy::question_mark)]
+
+use terminal_calculator::lexer::{Token, TokenType, tokenise_in_locale};
+use terminal_calculator::locale::Locale;
 use terminal_calculator::parser::{AstNode, construct_ast};
-use terminal_calculator::evaluator::{Environment, EvalResult};
-use terminal_calculator::errors::{ParseError, InputError, EvaluationError, LexerError};
-use clearscreen;
-use std::io::{stdin, stdout, Write};
+use terminal_calculator::evaluator::{AngleMode, Environment, EvalResult, WordSize};
+use terminal_calculator::value::{Base, Value};
+use terminal_calculator::diagnostic::Diagnostic;
+use terminal_calculator::errors::InputError;
+use std::io::{stdin, stdout, IsTerminal, Write};
 use std::env;
 
 macro_rules! debug_println {
@@ -16,20 +21,55 @@ macro_rules! debug_println {
 
 struct Context {
     debug_mode: bool,
+    print_each: bool,
+    show_fractions: bool,
+    show_polar: bool,
+    angle_mode: AngleMode,
+    precision: Option<u64>,
+    // The base integers are shown in and the word they wrap around in, in programmer mode
+    programmer: Option<(Base, WordSize)>,
+    locale: Locale,
+    // Whether errors are coloured, which is only done when writing to a terminal
+    colour: bool,
     included_tokens: String,
 }
 
+impl Context {
+    fn new() -> Self {
+        Context {
+            debug_mode: false,
+            print_each: false,
+            show_fractions: false,
+            show_polar: false,
+            angle_mode: AngleMode::Radians,
+            precision: None,
+            programmer: None,
+            locale: Locale::Plain,
+            colour: stdout().is_terminal(),
+            included_tokens: String::new(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum Command {
     Exit,
     Debug,
+    Each,
+    Fractions,
+    Polar,
+    RecursionLimit(usize),
+    AngleMode(AngleMode),
+    Precision(Option<u64>),
+    Programmer(Option<(Base, WordSize)>),
+    Locale(Locale),
     Evaluate(String),
     Clear,
 }
 
 // Displays a welcome message and starts the REPL 
 fn main() {
-    let mut context = Context { debug_mode: false, included_tokens: String::new() };
+    let mut context = Context::new();
     let mut environment = Environment::new();
     environment.init_consts();
 
@@ -38,8 +78,15 @@ fn main() {
     if argv.len() > 1 {
         context = parse_args(argv);
     }
+    environment.set_angle_mode(context.angle_mode);
+    if context.precision.is_some() {
+        environment.set_precision(context.precision);
+    }
+    if let Some((_, word_size)) = context.programmer {
+        environment.set_word_size(Some(word_size));
+    }
 
-    if context.included_tokens.len() > 0 {
+    if !context.included_tokens.is_empty() {
         evaluate(&context.included_tokens, &context, &mut environment);
         return;
         
@@ -49,7 +96,7 @@ fn main() {
 }
 
 // READ-EVALUATE-PRINT-LOOP (REPL)
-fn repl(context: &mut Context, environment: &mut Environment) -> () {
+fn repl(context: &mut Context, environment: &mut Environment) {
     let mut running: bool = true;
     while running {
         let input: Command = match input(None) {
@@ -63,6 +110,10 @@ fn repl(context: &mut Context, environment: &mut Environment) -> () {
                     InputError::EmptyInput => {
                         continue;
                     }
+                    InputError::InvalidArgument(argument) => {
+                        println!("Error: Invalid argument: {}", argument);
+                        continue;
+                    }
                 }
             }
         };
@@ -80,7 +131,74 @@ fn repl(context: &mut Context, environment: &mut Environment) -> () {
                     println!("Debug mode disabled.");
                 }
             }
-            Command::Evaluate(input) => evaluate(&input, &context, environment),
+            Command::Each => {
+                context.print_each = !context.print_each;
+                if context.print_each {
+                    println!("Printing the result of every statement.");
+                } else {
+                    println!("Printing the result of the last statement only.");
+                }
+            }
+            Command::Fractions => {
+                context.show_fractions = !context.show_fractions;
+                if context.show_fractions {
+                    println!("Showing exact results as fractions.");
+                } else {
+                    println!("Showing exact results as decimals.");
+                }
+            }
+            Command::Polar => {
+                context.show_polar = !context.show_polar;
+                if context.show_polar {
+                    println!("Showing complex results in polar form.");
+                } else {
+                    println!("Showing complex results in rectangular form.");
+                }
+            }
+            Command::RecursionLimit(depth) => {
+                environment.set_max_call_depth(depth);
+                println!("Recursion limit set to {}.", depth);
+            }
+            Command::AngleMode(mode) => {
+                environment.set_angle_mode(mode);
+                println!("Angles are now in {}.", mode.name());
+            }
+            Command::Precision(precision) => {
+                environment.set_precision(precision);
+                match precision {
+                    Some(digits) => println!("Computing with {} significant digits.", digits),
+                    None => println!("Computing with floating point numbers."),
+                }
+            }
+            Command::Programmer(programmer) => {
+                context.programmer = programmer;
+                match programmer {
+                    Some((base, word_size)) => {
+                        environment.set_word_size(Some(word_size));
+                        println!(
+                            "Programmer mode: {}, {}-bit {}.",
+                            base.name(),
+                            word_size.bits,
+                            if word_size.signed { "signed" } else { "unsigned" },
+                        );
+                    }
+                    None => {
+                        environment.set_word_size(None);
+                        println!("Programmer mode disabled.");
+                    }
+                }
+            }
+            Command::Locale(locale) => {
+                context.locale = locale;
+                match locale.group_separator() {
+                    Some(separator) => println!(
+                        "Numbers now use the {} format, with '{}' between groups of digits and '{}' as the decimal separator.",
+                        locale.name(), separator, locale.decimal_separator(),
+                    ),
+                    None => println!("Numbers now use the plain format."),
+                }
+            }
+            Command::Evaluate(input) => evaluate(&input, context, environment),
         }
     }
 }
@@ -98,10 +216,7 @@ fn input(output: Option<&str>) -> Result<Command, InputError> {
         Err(_) => return Err(InputError::ReadError),
     }
 
-    match parse_command(input) {
-        Ok(command) => Ok(command),
-        Err(error) => Err(error),
-    }
+    parse_command(input)
 }
 
 fn parse_command(input: String) -> Result<Command, InputError> {
@@ -109,33 +224,99 @@ fn parse_command(input: String) -> Result<Command, InputError> {
     match input {
         "exit" => return Ok(Command::Exit),
         "debug" | "dbg" => return Ok(Command::Debug),
+        "each" => return Ok(Command::Each),
+        "fraction" | "fractions" => return Ok(Command::Fractions),
+        "polar" => return Ok(Command::Polar),
         "clear" => return Ok(Command::Clear),
+        "programmer" => match parse_programmer("") {
+            Some(programmer) => return Ok(Command::Programmer(programmer)),
+            None => return Err(InputError::InvalidArgument(input.to_string())),
+        },
         _ => if input.is_empty() {
             return Err(InputError::EmptyInput);
+        } else if let Some(argument) = input.strip_prefix("recursion ") {
+            match argument.trim().parse::<usize>() {
+                Ok(depth) => return Ok(Command::RecursionLimit(depth)),
+                Err(_) => return Err(InputError::InvalidArgument(argument.trim().to_string())),
+            }
+        } else if let Some(argument) = input.strip_prefix("precision ") {
+            match parse_precision(argument.trim()) {
+                Some(precision) => return Ok(Command::Precision(precision)),
+                None => return Err(InputError::InvalidArgument(argument.trim().to_string())),
+            }
+        } else if let Some(argument) = input.strip_prefix("programmer ") {
+            match parse_programmer(argument.trim()) {
+                Some(programmer) => return Ok(Command::Programmer(programmer)),
+                None => return Err(InputError::InvalidArgument(argument.trim().to_string())),
+            }
+        } else if let Some(argument) = input.strip_prefix("locale ") {
+            match Locale::from_name(argument.trim()) {
+                Some(locale) => return Ok(Command::Locale(locale)),
+                None => return Err(InputError::InvalidArgument(argument.trim().to_string())),
+            }
+        } else if let Some(argument) = input.strip_prefix("angle ") {
+            match AngleMode::from_name(argument.trim()) {
+                Some(mode) => return Ok(Command::AngleMode(mode)),
+                None => return Err(InputError::InvalidArgument(argument.trim().to_string())),
+            }
         } else {
             return Ok(Command::Evaluate(input.to_string()));
         },
     }
 }
 
+// A number of significant digits, or `off` to go back to floating point
+fn parse_precision(argument: &str) -> Option<Option<u64>> {
+    if argument == "off" {
+        return Some(None);
+    }
+    match argument.parse::<u64>() {
+        Ok(digits) if digits > 0 => Some(Some(digits)),
+        _ => None,
+    }
+}
+
+// A base, word size and signedness in any order, like `hex 32 unsigned`, or `off` to leave
+// programmer mode. Anything left out is hexadecimal, 64-bit and signed.
+fn parse_programmer(argument: &str) -> Option<Option<(Base, WordSize)>> {
+    if argument == "off" {
+        return Some(None);
+    }
+    let mut base = Base::Hexadecimal;
+    let mut bits = 64;
+    let mut signed = true;
+    for word in argument.split([' ', ',']).filter(|word| !word.is_empty()) {
+        if let Some(name) = Base::from_name(word) {
+            base = name;
+        } else if word == "signed" || word == "unsigned" {
+            signed = word == "signed";
+        } else {
+            match word.parse::<u32>() {
+                Ok(size) => bits = size,
+                Err(_) => return None,
+            }
+        }
+    }
+    let word_size = match WordSize::new(bits, signed) {
+        Some(word_size) => word_size,
+        None => return None,
+    };
+    return Some(Some((base, word_size)));
+}
+
 // Evaluates the input
-fn evaluate(input: &str, context: &Context, mut environment: &mut Environment) -> () {
+fn evaluate(input: &str, context: &Context, environment: &mut Environment) {
     debug_println!(context, "\nInput: {}", input); 
     debug_println!(context, "Tokenising..."); 
     
-    let tokens: Vec<Token> = match tokenise(input.to_owned()) {
+    let tokens: Vec<Token> = match tokenise_in_locale(input.to_owned(), context.locale) {
         Ok(tokens) => tokens,
-        Err(error) => {
-            match error {
-                LexerError::InvalidToken(token) => {
-                    println!("LexerError: Invalid token in input: {}", token);
-                    return;
-                }
-                LexerError::InvalidIdentifier(identifier) => {
-                    println!("LexerError: Invalid variable name: {}", identifier);
-                    return;
-                }
+        // Every token that could not be read is reported, and nothing is evaluated
+        Err(errors) => {
+            for error in errors {
+                println!("{}", Diagnostic::from_lexer_error(&error).render(input, context.colour));
             }
+            return;
         }
     };
     
@@ -150,77 +331,111 @@ fn evaluate(input: &str, context: &Context, mut environment: &mut Environment) -
 
     let ast: AstNode = match construct_ast(&tokens) {
         Ok(ast) => ast,
-        Err(error) => {
-            match error {
-                ParseError::UnexpectedEndOfInput => {
-                    println!("ParseError: Unexpected end of input.");
-                    return;
-                }
-                ParseError::MissingClosingParenthesis => {
-                    println!("ParseError: Missing closing parenthesis.");
-                    return;
-                }
-                ParseError::UnexpectedToken(token) => {
-                    println!("ParseError: Unexpected token: {}", token);
-                    return;
-                }
-                ParseError::UnexpectedTokensAtEnd => {
-                    println!("ParseError: Unexpected tokens at end of input.");
-                    return;
-                }
-                /* ParseError::InvalidNumber(token) => {
-                    println!("ParseError: Invalid number: {}", token);
-                    return;
-                } */
+        Err(errors) => {
+            for error in errors {
+                println!("{}", Diagnostic::from_parse_error(&error).render(input, context.colour));
             }
+            return;
         }
     };
 
     debug_println!(context, "AST Generated.\n");
 
-    match ast.evaluate(&mut environment) {
-        EvalResult::Value(result) => println!("Result: {}", result),
-        EvalResult::Error(error) => {
-            match error {
-                EvaluationError::DivisionByZero => {
-                     println!("EvaluationError: Division by zero.");
-                     return;
-                }
-                EvaluationError::InvalidOperation => {
-                    println!("EvaluationError: Invalid operation.");
-                    return;
-                }
-                EvaluationError::NotAFunction => {
-                    println!("EvaluationError: Not a function");
-                    return;
-                }
-                EvaluationError::Undefined => {
-                    println!("EvaluationError: tan(x) is Undefined.");
-                    return;
-                }
-                EvaluationError::CannotAssignAConstant(name) => {
-                    println!("EvaluationError: {} is a constant, and cannot be reassigned.", name);
-                    return;
+    // Only the last statement of a sequence is printed, unless every result was asked for
+    let statements: Vec<AstNode> = match ast {
+        AstNode::Sequence(statements) if context.print_each => statements,
+        ast => vec![ast],
+    };
+
+    for statement in statements {
+        match statement.evaluate(environment) {
+            EvalResult::Value(result) => print_result("Result: ", &format_value(&result, context, environment)),
+            EvalResult::Error(error) => {
+                println!("{}", Diagnostic::from_evaluation_error(&error).render(input, context.colour));
+                return;
+            }
+            EvalResult::Assignment(name, value) => {
+                if context.print_each {
+                    print_result(&format!("{} = ", name), &format_value(&value, context, environment));
                 }
-                EvaluationError::UndefinedVariable(name) => {
-                    println!("EvaluationError: The variable {} is not defined.", name);
-                    return;
+            }
+            EvalResult::Definition(name) => {
+                if context.print_each {
+                    println!("Defined {}().", name);
                 }
-                // EvaluationError::InvalidInput => {
-                //     println!("EvaluationError: Invalid input.");
-                //     return;
-                // }
             }
+        };
+    }
+}
+
+// Exact results are written as decimals, unless fractions were asked for, and fractions
+// without a finite decimal are cut off at the precision if one has been set. Complex results
+// may be written in polar form instead. The numbers are then written in the locale.
+fn format_value(value: &Value, context: &Context, environment: &Environment) -> String {
+    // Matrices are written as a table, with each element formatted on its own
+    if let Value::Matrix(matrix) = value {
+        let rows: Vec<Vec<String>> = matrix.elements()
+            .chunks(matrix.columns)
+            .map(|row| row.iter().map(|element| format_value(element, context, environment)).collect())
+            .collect();
+        return write_table(&rows);
+    }
+    if let Some((base, word_size)) = context.programmer {
+        return value.to_base_string(base, Some(word_size));
+    }
+    let text = if context.show_polar && matches!(value, Value::Complex(_) | Value::List(_)) {
+        value.to_polar_string(environment.angle_mode())
+    } else if context.show_fractions {
+        value.to_fraction_string()
+    } else {
+        match environment.precision() {
+            Some(digits) => value.to_decimal_string(digits),
+            None => value.to_string(),
         }
-        EvalResult::Assignment(_name, _value) => (),
     };
+    return context.locale.format(&text);
+}
+
+// Lines up the columns of a table, with each row in brackets on its own line, like
+//   [ 1  -2 ]
+//   [ 3   4 ]
+fn write_table(rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = match rows.first() {
+        Some(row) => vec![0; row.len()],
+        None => vec![],
+    };
+    for row in rows {
+        for (width, element) in widths.iter_mut().zip(row) {
+            *width = (*width).max(element.chars().count());
+        }
+    }
+    let lines: Vec<String> = rows.iter()
+        .map(|row| {
+            let elements: Vec<String> = row.iter()
+                .zip(&widths)
+                .map(|(element, width)| format!("{:>width$}", element, width = width))
+                .collect();
+            format!("[ {} ]", elements.join("  "))
+        })
+        .collect();
+    return lines.join("\n");
+}
+
+// Results written over several lines, like matrices, have their later lines lined up under
+// the first one
+fn print_result(prefix: &str, text: &str) {
+    let indent = format!("\n{}", " ".repeat(prefix.chars().count()));
+    println!("{}{}", prefix, text.replace('\n', &indent));
 }
 
 fn print_tokens(tokens: &Vec<Token>) {
     for token in tokens {
         match token.token_type {
             TokenType::Number => println!("Type: Number, Lexeme: {}", token.lexeme),
+            TokenType::Boolean => println!("Type: Boolean, Lexeme: {}", token.lexeme),
             TokenType::Identifier => println!("Type: Identifier, Lexeme: {}", token.lexeme),
+            TokenType::ImaginaryUnit => println!("Type: Imaginary unit, Lexeme: {}", token.lexeme),
+            TokenType::Unit => println!("Type: Unit, Lexeme: {}", token.lexeme),
             TokenType::Keyword(_) => println!("Type: Keyword, Lexeme: {}", token.lexeme),
             TokenType::Equals => println!("Type: Assignment, Lexeme: {}", token.lexeme),
 
@@ -250,7 +465,77 @@ fn print_tokens(tokens: &Vec<Token>) {
                 token.lexeme,
                 ), 
 
+            TokenType::LessThan => println!(
+                "Type: Binary Operator, Less than, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::LessThanOrEqual => println!(
+                "Type: Binary Operator, Less than or equal, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::GreaterThan => println!(
+                "Type: Binary Operator, Greater than, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::GreaterThanOrEqual => println!(
+                "Type: Binary Operator, Greater than or equal, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::Equality => println!(
+                "Type: Binary Operator, Equality, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::NotEqual => println!(
+                "Type: Binary Operator, Not equal, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::And => println!(
+                "Type: Binary Operator, Logical and, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::Or => println!(
+                "Type: Binary Operator, Logical or, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::Not => println!(
+                "Type: Unary Operator, Logical not, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::Conversion => println!(
+                "Type: Binary Operator, Unit conversion, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::BitwiseAnd => println!(
+                "Type: Binary Operator, Bitwise and, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::BitwiseOr => println!(
+                "Type: Binary Operator, Bitwise or, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::BitwiseXor => println!(
+                "Type: Binary Operator, Bitwise xor, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::BitwiseNot => println!(
+                "Type: Unary Operator, Bitwise not, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::ShiftLeft => println!(
+                "Type: Binary Operator, Left shift, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::ShiftRight => println!(
+                "Type: Binary Operator, Right shift, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::Arrow => println!(
+                "Type: Arrow, Lexeme: {}",
+                token.lexeme,
+                ),
+
             // PUNCTUATION
+            TokenType::Invalid => println!("Type: Invalid, Lexeme: {}", token.lexeme),
             TokenType::Semicolon => println!(
                 "Type: Punctuation, Semicolon, Lexeme: {}", 
                 token.lexeme,
@@ -267,6 +552,14 @@ fn print_tokens(tokens: &Vec<Token>) {
                 "Type: Punctuation, Exclamation mark, Lexeme: {}", 
                 token.lexeme,
                 ),
+            TokenType::DoubleExclamation => println!(
+                "Type: Punctuation, Double exclamation mark, Lexeme: {}", 
+                token.lexeme,
+                ),
+            TokenType::AngleUnit(_) => println!(
+                "Type: Postfix Operator, Angle unit, Lexeme: {}", 
+                token.lexeme,
+                ),
             TokenType::Question => println!(
                 "Type: Punctuation, Question mark, Lexeme: {}", 
                 token.lexeme,
@@ -300,27 +593,53 @@ fn print_tokens(tokens: &Vec<Token>) {
         }
     }
     println!("Token printing complete.");
-    print!("\n");
+    println!();
 }
 
+// Flags may be given either before or after the calculation, but not in the middle of it
 fn parse_args(args: Vec<String>) -> Context {
-    let mut context = Context { debug_mode: false, included_tokens: String::new() };
-    if args.len() > 1 {
-        if args[1] == "--debug" {
+    let mut context = Context::new();
+    let mut first = 1;
+    let mut last = args.len();
+
+    while first < last && apply_flag(&args[first], &mut context) {
+        first += 1;
+    }
+    while last > first && apply_flag(&args[last - 1], &mut context) {
+        last -= 1;
+    }
+
+    if first < last {
+        context.included_tokens = args[first..last].join(" ");
+    }
+    context
+}
+
+// Applies a CLI flag to the context, returning false if the argument is not a flag
+fn apply_flag(arg: &str, context: &mut Context) -> bool {
+    match arg {
+        "--debug" => {
             context.debug_mode = true;
             println!("Debug mode enabled.");
-            context.included_tokens = args[2..].join(" ");
-        } else if match args.last() {
-            Some(last) => last == "--debug",
-            None => false,
-        } {
-            context.debug_mode = true;
-            context.included_tokens = args[1..args.len() - 1].join(" ");
-        } else {
-            context.included_tokens = args[1..].join(" ");
         }
+        "--each" => context.print_each = true,
+        "--fractions" => context.show_fractions = true,
+        "--polar" => context.show_polar = true,
+        "--rad" => context.angle_mode = AngleMode::Radians,
+        "--deg" => context.angle_mode = AngleMode::Degrees,
+        "--grad" => context.angle_mode = AngleMode::Gradians,
+        "--programmer" => context.programmer = parse_programmer("").flatten(),
+        _ => if let Some(precision) = arg.strip_prefix("--precision=").and_then(parse_precision) {
+            context.precision = precision;
+        } else if let Some(programmer) = arg.strip_prefix("--programmer=").and_then(parse_programmer) {
+            context.programmer = programmer;
+        } else if let Some(locale) = arg.strip_prefix("--locale=").and_then(Locale::from_name) {
+            context.locale = locale;
+        } else {
+            return false;
+        },
     }
-    context
+    return true;
 }
 
 #[cfg(test)]
@@ -333,7 +652,7 @@ mod tests {
     fn test_cli_arg_parsing_1() {
         let args = vec!["calc".to_string(), "--debug".to_string(), "3 + 5".to_string()];
         let context = parse_args(args);
-        assert_eq!(context.debug_mode, true);
+        assert!(context.debug_mode);
         assert_eq!(context.included_tokens, "3 + 5");
     }
 
@@ -342,7 +661,7 @@ mod tests {
     fn test_cli_arg_parsing_2() {
         let args = vec!["calc".to_string(), "3 + 5".to_string(), "--debug".to_string()];
         let context = parse_args(args);
-        assert_eq!(context.debug_mode, true);
+        assert!(context.debug_mode);
         assert_eq!(context.included_tokens, "3 + 5");
     }
 
@@ -351,7 +670,7 @@ mod tests {
     fn test_cli_arg_parsing_only_debug() {
         let args = vec!["calc".to_string(), "--debug".to_string()];
         let context = parse_args(args);
-        assert_eq!(context.debug_mode, true);
+        assert!(context.debug_mode);
         assert_eq!(context.included_tokens, "");
     }
 
@@ -360,7 +679,7 @@ mod tests {
     fn test_cli_arg_parsing_no_debug() {
         let args = vec!["calc".to_string(), "3 + 5".to_string()];
         let context = parse_args(args);
-        assert_eq!(context.debug_mode, false);
+        assert!(!context.debug_mode);
         assert_eq!(context.included_tokens, "3 + 5");
     }
 
@@ -369,7 +688,7 @@ mod tests {
     fn test_cli_arg_parsing_no_debug_no_input() {
         let args = vec!["calc".to_string()];
         let context = parse_args(args);
-        assert_eq!(context.debug_mode, false);
+        assert!(!context.debug_mode);
         assert_eq!(context.included_tokens, "");
     }
 
@@ -405,6 +724,186 @@ mod tests {
         assert_eq!(command, Command::Debug);
     }
 
+    // Checks that the columns of a matrix are lined up
+    #[test]
+    fn test_write_table() {
+        let rows = vec![
+            vec!["1".to_string(), "-2".to_string()],
+            vec!["10".to_string(), "4".to_string()],
+        ];
+        assert_eq!(write_table(&rows), "[  1  -2 ]\n[ 10   4 ]");
+    }
+
+    // Checks that the --each flag is parsed alongside --debug
+    #[test]
+    fn test_cli_arg_parsing_each() {
+        let args = vec![
+            "calc".to_string(),
+            "--each".to_string(),
+            "r = 3; r^2".to_string(),
+            "--debug".to_string(),
+        ];
+        let context = parse_args(args);
+        assert!(context.debug_mode);
+        assert!(context.print_each);
+        assert_eq!(context.included_tokens, "r = 3; r^2");
+    }
+
+    // Checks that parse_command correctly identifies the each command
+    #[test]
+    fn test_input_reading_each() {
+        let input = "each";
+        let command = parse_command(input.to_string()).unwrap();
+        assert_eq!(command, Command::Each);
+    }
+
+    // Checks that parse_command correctly identifies the recursion limit command
+    #[test]
+    fn test_input_reading_recursion_limit() {
+        let input = "recursion 500";
+        let command = parse_command(input.to_string()).unwrap();
+        assert_eq!(command, Command::RecursionLimit(500));
+    }
+
+    // Checks that parse_command rejects a recursion limit that is not a number
+    #[test]
+    fn test_input_reading_recursion_limit_invalid() {
+        let input = "recursion many";
+        let command = parse_command(input.to_string());
+        assert_eq!(command, Err(InputError::InvalidArgument("many".to_string())));
+    }
+
+    // Checks that the fractions flag is parsed
+    #[test]
+    fn test_cli_arg_parsing_fractions() {
+        let args = vec!["calc".to_string(), "1/3".to_string(), "--fractions".to_string()];
+        let context = parse_args(args);
+        assert!(context.show_fractions);
+        assert_eq!(context.included_tokens, "1/3");
+    }
+
+    // Checks that parse_command correctly identifies the fractions command
+    #[test]
+    fn test_input_reading_fractions() {
+        let input = "fractions";
+        let command = parse_command(input.to_string()).unwrap();
+        assert_eq!(command, Command::Fractions);
+    }
+
+    // Checks that the polar flag is parsed
+    #[test]
+    fn test_cli_arg_parsing_polar() {
+        let args = vec!["calc".to_string(), "--polar".to_string(), "1+i".to_string()];
+        let context = parse_args(args);
+        assert!(context.show_polar);
+        assert_eq!(context.included_tokens, "1+i");
+    }
+
+    // Checks that parse_command correctly identifies the polar command
+    #[test]
+    fn test_input_reading_polar() {
+        let input = "polar";
+        let command = parse_command(input.to_string()).unwrap();
+        assert_eq!(command, Command::Polar);
+    }
+
+    // Checks that the precision flag is parsed
+    #[test]
+    fn test_cli_arg_parsing_precision() {
+        let args = vec!["calc".to_string(), "--precision=50".to_string(), "pi".to_string()];
+        let context = parse_args(args);
+        assert_eq!(context.precision, Some(50));
+        assert_eq!(context.included_tokens, "pi");
+    }
+
+    // Checks that parse_command correctly identifies the precision command
+    #[test]
+    fn test_input_reading_precision() {
+        let input = "precision 50";
+        let command = parse_command(input.to_string()).unwrap();
+        assert_eq!(command, Command::Precision(Some(50)));
+        let command = parse_command("precision off".to_string()).unwrap();
+        assert_eq!(command, Command::Precision(None));
+    }
+
+    // Checks that parse_command rejects a precision of no digits
+    #[test]
+    fn test_input_reading_precision_invalid() {
+        let input = "precision 0";
+        let command = parse_command(input.to_string());
+        assert_eq!(command, Err(InputError::InvalidArgument("0".to_string())));
+    }
+
+    // Checks that the angle mode flags are parsed
+    #[test]
+    fn test_cli_arg_parsing_angle_mode() {
+        let args = vec!["calc".to_string(), "--deg".to_string(), "sin(30)".to_string()];
+        let context = parse_args(args);
+        assert_eq!(context.angle_mode, AngleMode::Degrees);
+        assert_eq!(context.included_tokens, "sin(30)");
+    }
+
+    // Checks that parse_command correctly identifies the angle mode command
+    #[test]
+    fn test_input_reading_angle_mode() {
+        let input = "angle grad";
+        let command = parse_command(input.to_string()).unwrap();
+        assert_eq!(command, Command::AngleMode(AngleMode::Gradians));
+    }
+
+    // Checks that parse_command rejects an unknown angle mode
+    #[test]
+    fn test_input_reading_angle_mode_invalid() {
+        let input = "angle turns";
+        let command = parse_command(input.to_string());
+        assert_eq!(command, Err(InputError::InvalidArgument("turns".to_string())));
+    }
+
+    // Checks that the programmer mode flag is parsed
+    #[test]
+    fn test_cli_arg_parsing_programmer() {
+        let args = vec!["calc".to_string(), "--programmer=bin,8,unsigned".to_string(), "~5".to_string()];
+        let context = parse_args(args);
+        assert_eq!(context.programmer, Some((Base::Binary, WordSize { bits: 8, signed: false })));
+        assert_eq!(context.included_tokens, "~5");
+    }
+
+    // Checks that parse_command correctly identifies the programmer command
+    #[test]
+    fn test_input_reading_programmer() {
+        let command = parse_command("programmer".to_string()).unwrap();
+        assert_eq!(command, Command::Programmer(Some((Base::Hexadecimal, WordSize { bits: 64, signed: true }))));
+        let command = parse_command("programmer oct 16 unsigned".to_string()).unwrap();
+        assert_eq!(command, Command::Programmer(Some((Base::Octal, WordSize { bits: 16, signed: false }))));
+        let command = parse_command("programmer off".to_string()).unwrap();
+        assert_eq!(command, Command::Programmer(None));
+    }
+
+    // Checks that parse_command rejects a word size that is not 8, 16, 32 or 64 bits
+    #[test]
+    fn test_input_reading_programmer_invalid() {
+        let input = "programmer hex 12";
+        let command = parse_command(input.to_string());
+        assert_eq!(command, Err(InputError::InvalidArgument("hex 12".to_string())));
+    }
+
+    // Checks that the locale flag is parsed
+    #[test]
+    fn test_cli_arg_parsing_locale() {
+        let args = vec!["calc".to_string(), "--locale=eu".to_string(), "1.234,5".to_string()];
+        let context = parse_args(args);
+        assert_eq!(context.locale, Locale::Eu);
+        assert_eq!(context.included_tokens, "1.234,5");
+    }
+
+    // Checks that parse_command correctly identifies the locale command
+    #[test]
+    fn test_input_reading_locale() {
+        let input = "locale us";
+        let command = parse_command(input.to_string()).unwrap();
+        assert_eq!(command, Command::Locale(Locale::Us));
+    }
+
     // Checks that parse_command returns an error for empty input
     #[test]
     fn test_input_reading_empty() {
@@ -0,0 +1,602 @@
+use crate::errors::EvaluationError;
+use crate::evaluator::{Function, Settings};
+use crate::lexer::TokenType;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+
+// Floats smaller than this, relative to the largest element, count as zero during elimination.
+// Exact numbers are only zero when they are exactly 0.
+const ZERO_TOLERANCE: f64 = 1e-12;
+
+// Eigenvalues are found from the characteristic polynomial, which can be solved directly up to
+// a cubic
+const MAX_EIGENVALUE_SIZE: usize = 3;
+
+// Eigenvalues whose imaginary part is this small, relative to their size, are real ones that
+// picked up rounding errors
+const REAL_TOLERANCE: f64 = 1e-10;
+
+// A grid of values, kept row by row. Matrices are written as lists of rows, like
+// `[[1, 2], [3, 4]]` or `[1, 2; 3, 4]`, and `*` multiplies them as matrices rather than element
+// by element. Elimination works on the values themselves, so matrices of exact numbers have
+// exact determinants, inverses and solutions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    pub rows: usize,
+    pub columns: usize,
+    elements: Vec<Value>,
+}
+
+// A matrix in reduced row echelon form, with the number of pivots it has and the determinant
+// of the columns they were found in
+struct Elimination {
+    matrix: Matrix,
+    rank: usize,
+    determinant: Value,
+}
+
+impl Matrix {
+    // The rows must all have the same length, see `Value::from_list`
+    pub fn new(rows: Vec<Vec<Value>>) -> Matrix {
+        let columns = match rows.first() {
+            Some(row) => row.len(),
+            None => 0,
+        };
+        Matrix { rows: rows.len(), columns, elements: rows.into_iter().flatten().collect() }
+    }
+
+    // A list as a single column, which is how a list is multiplied by a matrix on its left
+    pub fn from_column(values: Vec<Value>) -> Matrix {
+        Matrix { rows: values.len(), columns: 1, elements: values }
+    }
+
+    // A list as a single row, which is how a list is multiplied by a matrix on its right
+    pub fn from_row(values: Vec<Value>) -> Matrix {
+        Matrix { rows: 1, columns: values.len(), elements: values }
+    }
+
+    pub fn identity(size: usize) -> Matrix {
+        let mut elements: Vec<Value> = Vec::with_capacity(size * size);
+        for row in 0..size {
+            for column in 0..size {
+                elements.push(Value::Integer(BigInt::from((row == column) as u8)));
+            }
+        }
+        Matrix { rows: size, columns: size, elements }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> &Value {
+        &self.elements[row * self.columns + column]
+    }
+
+    fn set(&mut self, row: usize, column: usize, value: Value) {
+        self.elements[row * self.columns + column] = value;
+    }
+
+    pub fn elements(&self) -> &[Value] {
+        &self.elements
+    }
+
+    // Each row as a list, so that `A[0]` is the first row and `A[0][1]` an element of it
+    pub fn row_lists(&self) -> Vec<Value> {
+        self.elements.chunks(self.columns).map(|row| Value::List(row.to_vec())).collect()
+    }
+
+    // Written like `[2, 3]`, the same way as the shape of a list of lists
+    pub fn shape(&self) -> String {
+        format!("[{}, {}]", self.rows, self.columns)
+    }
+
+    // The matrix as a list of rows, with each element written by write_element
+    pub fn write(&self, write_element: impl Fn(&Value) -> String) -> String {
+        let rows: Vec<String> = self.elements
+            .chunks(self.columns)
+            .map(|row| format!("[{}]", row.iter().map(&write_element).collect::<Vec<String>>().join(", ")))
+            .collect();
+        format!("[{}]", rows.join(", "))
+    }
+
+    pub fn map(&self, mut f: impl FnMut(&Value) -> Result<Value, EvaluationError>) -> Result<Matrix, EvaluationError> {
+        let mut elements: Vec<Value> = Vec::with_capacity(self.elements.len());
+        for element in &self.elements {
+            match f(element) {
+                Ok(result) => elements.push(result),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Matrix { rows: self.rows, columns: self.columns, elements })
+    }
+
+    // Combines two matrices of the same shape element by element
+    pub fn zip(
+        &self,
+        other: &Matrix,
+        mut f: impl FnMut(&Value, &Value) -> Result<Value, EvaluationError>,
+    ) -> Result<Matrix, EvaluationError> {
+        if self.rows != other.rows || self.columns != other.columns {
+            return Err(EvaluationError::ShapeMismatch { expected: self.shape(), found: other.shape() });
+        }
+        let mut elements: Vec<Value> = Vec::with_capacity(self.elements.len());
+        for (a, b) in self.elements.iter().zip(&other.elements) {
+            match f(a, b) {
+                Ok(result) => elements.push(result),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Matrix { rows: self.rows, columns: self.columns, elements })
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut elements: Vec<Value> = Vec::with_capacity(self.elements.len());
+        for column in 0..self.columns {
+            for row in 0..self.rows {
+                elements.push(self.get(row, column).clone());
+            }
+        }
+        Matrix { rows: self.columns, columns: self.rows, elements }
+    }
+
+    // The matrix product, which needs as many rows in the other matrix as there are columns
+    // in this one
+    pub fn multiply(&self, other: &Matrix, settings: Settings) -> Result<Matrix, EvaluationError> {
+        if self.columns != other.rows {
+            return Err(EvaluationError::ShapeMismatch {
+                expected: format!("[{}, {}]", self.columns, other.columns),
+                found: other.shape(),
+            });
+        }
+        let mut elements: Vec<Value> = Vec::with_capacity(self.rows * other.columns);
+        for row in 0..self.rows {
+            for column in 0..other.columns {
+                // The sum starts from the first product rather than from 0, which could not be
+                // added to a quantity
+                let mut total: Option<Value> = None;
+                for k in 0..self.columns {
+                    let product = match arithmetic(self.get(row, k), TokenType::Multiplication, other.get(k, column), settings) {
+                        Ok(product) => product,
+                        Err(error) => return Err(error),
+                    };
+                    total = match total {
+                        Some(total) => match total.apply_binary(&TokenType::Addition, product, settings) {
+                            Ok(total) => Some(total),
+                            Err(error) => return Err(error),
+                        },
+                        None => Some(product),
+                    };
+                }
+                elements.push(total.unwrap_or(Value::Integer(BigInt::zero())));
+            }
+        }
+        Ok(Matrix { rows: self.rows, columns: other.columns, elements })
+    }
+
+    // Whole powers by repeated squaring, where negative powers are powers of the inverse and
+    // `A^0` is the identity
+    pub fn power(&self, exponent: &Value, settings: Settings) -> Result<Matrix, EvaluationError> {
+        match self.check_square() {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        let exponent = match exponent.as_integer() {
+            Some(exponent) => exponent,
+            None => return Err(EvaluationError::TypeMismatch {
+                expected: "integer".to_string(),
+                found: exponent.type_name().to_string(),
+            }),
+        };
+        let mut remaining = match exponent.abs().to_u64() {
+            Some(remaining) => remaining,
+            None => return Err(EvaluationError::DomainError {
+                function: "^".to_string(),
+                value: exponent.to_f64().unwrap_or(f64::NAN),
+            }),
+        };
+        let mut base = if exponent.is_negative() {
+            match self.inverse(settings) {
+                Ok(inverse) => inverse,
+                Err(error) => return Err(error),
+            }
+        } else {
+            self.clone()
+        };
+
+        let mut result = Matrix::identity(self.rows);
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = match result.multiply(&base, settings) {
+                    Ok(result) => result,
+                    Err(error) => return Err(error),
+                };
+            }
+            remaining /= 2;
+            if remaining > 0 {
+                base = match base.multiply(&base, settings) {
+                    Ok(base) => base,
+                    Err(error) => return Err(error),
+                };
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn determinant(&self, settings: Settings) -> Result<Value, EvaluationError> {
+        match self.check_square() {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        match self.clone().row_reduce(self.columns, settings) {
+            Ok(elimination) if elimination.rank < self.rows => Ok(Value::Integer(BigInt::zero())),
+            Ok(elimination) => Ok(elimination.determinant),
+            Err(error) => Err(error),
+        }
+    }
+
+    // Gauss-Jordan elimination of the matrix next to the identity, which turns the identity
+    // into the inverse
+    pub fn inverse(&self, settings: Settings) -> Result<Matrix, EvaluationError> {
+        match self.check_square() {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        return self.solve(&Matrix::identity(self.rows), settings);
+    }
+
+    // The number of linearly independent rows
+    pub fn rank(&self, settings: Settings) -> Result<usize, EvaluationError> {
+        match self.clone().row_reduce(self.columns, settings) {
+            Ok(elimination) => Ok(elimination.rank),
+            Err(error) => Err(error),
+        }
+    }
+
+    // The x that solves `A x = b` for this matrix A, where each column of b is a separate
+    // right-hand side
+    pub fn solve(&self, b: &Matrix, settings: Settings) -> Result<Matrix, EvaluationError> {
+        match self.check_square() {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        if b.rows != self.rows {
+            return Err(EvaluationError::ShapeMismatch {
+                expected: format!("[{}, {}]", self.rows, b.columns),
+                found: b.shape(),
+            });
+        }
+
+        let mut augmented: Vec<Value> = Vec::with_capacity(self.rows * (self.columns + b.columns));
+        for (row, right) in self.elements.chunks(self.columns).zip(b.elements.chunks(b.columns)) {
+            augmented.extend_from_slice(row);
+            augmented.extend_from_slice(right);
+        }
+        let augmented = Matrix { rows: self.rows, columns: self.columns + b.columns, elements: augmented };
+        let reduced = match augmented.row_reduce(self.columns, settings) {
+            Ok(elimination) if elimination.rank < self.rows => return Err(EvaluationError::SingularMatrix),
+            Ok(elimination) => elimination.matrix,
+            Err(error) => return Err(error),
+        };
+
+        let mut elements: Vec<Value> = Vec::with_capacity(b.elements.len());
+        for row in reduced.elements.chunks(reduced.columns) {
+            elements.extend_from_slice(&row[self.columns..]);
+        }
+        Ok(Matrix { rows: b.rows, columns: b.columns, elements })
+    }
+
+    // The roots of the characteristic polynomial, largest first, for matrices of up to 3 by 3.
+    // Those of a 2 by 2 matrix are exact where the square root in them is, and those of a 3 by 3
+    // matrix are found with floats, except for integer ones of an exact matrix.
+    pub fn eigenvalues(&self, settings: Settings) -> Result<Vec<Value>, EvaluationError> {
+        match self.check_square() {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        if self.rows > MAX_EIGENVALUE_SIZE {
+            return Err(EvaluationError::ShapeMismatch {
+                expected: format!("at most [{0}, {0}]", MAX_EIGENVALUE_SIZE),
+                found: self.shape(),
+            });
+        }
+        if self.rows == 1 {
+            return Ok(vec![self.get(0, 0).clone()]);
+        }
+
+        let mut trace = self.get(0, 0).clone();
+        for i in 1..self.rows {
+            trace = match trace.apply_binary(&TokenType::Addition, self.get(i, i).clone(), settings) {
+                Ok(trace) => trace,
+                Err(error) => return Err(error),
+            };
+        }
+        let determinant = match self.determinant(settings) {
+            Ok(determinant) => determinant,
+            Err(error) => return Err(error),
+        };
+
+        if self.rows == 2 {
+            return quadratic_roots(&trace, &determinant, settings);
+        }
+
+        // λ³ - tr λ² + m λ - det, where m is the sum of the 2 by 2 minors on the diagonal
+        let mut minors = Value::Integer(BigInt::zero());
+        for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+            let minor = match (
+                arithmetic(self.get(i, i), TokenType::Multiplication, self.get(j, j), settings),
+                arithmetic(self.get(i, j), TokenType::Multiplication, self.get(j, i), settings),
+            ) {
+                (Ok(diagonal), Ok(other)) => diagonal.apply_binary(&TokenType::Subtraction, other, settings),
+                (Err(error), _) | (_, Err(error)) => return Err(error),
+            };
+            minors = match minor {
+                Ok(minor) => match minors.apply_binary(&TokenType::Addition, minor, settings) {
+                    Ok(minors) => minors,
+                    Err(error) => return Err(error),
+                },
+                Err(error) => return Err(error),
+            };
+        }
+        let coefficients = [
+            trace.apply_unary(&TokenType::Negation),
+            Ok(minors),
+            determinant.apply_unary(&TokenType::Negation),
+        ];
+        let mut exact: Vec<BigRational> = Vec::with_capacity(3);
+        let mut complex: Vec<Complex64> = Vec::with_capacity(3);
+        for coefficient in coefficients {
+            let coefficient = match coefficient {
+                Ok(coefficient) => coefficient,
+                Err(error) => return Err(error),
+            };
+            if let Some(value) = coefficient.as_rational() {
+                exact.push(value);
+            }
+            match coefficient.as_complex() {
+                Some(value) => complex.push(value),
+                None => return Err(EvaluationError::TypeMismatch {
+                    expected: "number".to_string(),
+                    found: coefficient.type_name().to_string(),
+                }),
+            }
+        }
+
+        let mut roots = cubic_roots(complex[0], complex[1], complex[2]);
+        roots.sort_by(|a, b| b.re.total_cmp(&a.re).then(b.im.total_cmp(&a.im)));
+        let mut eigenvalues: Vec<Value> = Vec::with_capacity(3);
+        for root in roots {
+            if root.im.abs() > REAL_TOLERANCE * root.norm().max(1.0) {
+                eigenvalues.push(Value::from_complex(root));
+                continue;
+            }
+            // An exact matrix with an integer eigenvalue gives exactly that integer
+            let nearest = root.re.round();
+            match (BigInt::from_f64(nearest), exact.as_slice()) {
+                (Some(candidate), [a, b, c]) if is_cubic_root(&candidate, a, b, c) => {
+                    eigenvalues.push(Value::Integer(candidate));
+                }
+                _ => eigenvalues.push(Value::Number(root.re)),
+            }
+        }
+        Ok(eigenvalues)
+    }
+
+    fn check_square(&self) -> Option<EvaluationError> {
+        if self.rows == self.columns {
+            return None;
+        }
+        Some(EvaluationError::ShapeMismatch {
+            expected: format!("[{0}, {0}]", self.rows),
+            found: self.shape(),
+        })
+    }
+
+    // Gauss-Jordan elimination, looking for pivots in the first pivot_columns columns. The largest
+    // element left in a column is its pivot, which keeps the rounding of floats small.
+    fn row_reduce(mut self, pivot_columns: usize, settings: Settings) -> Result<Elimination, EvaluationError> {
+        let mut largest: f64 = 0.0;
+        for element in &self.elements {
+            match magnitude(element) {
+                Some(size) => largest = largest.max(size),
+                None => return Err(EvaluationError::TypeMismatch {
+                    expected: "number".to_string(),
+                    found: element.type_name().to_string(),
+                }),
+            }
+        }
+        let tolerance = largest * ZERO_TOLERANCE;
+
+        let mut determinant = Value::Integer(BigInt::one());
+        let mut rank = 0;
+        for column in 0..pivot_columns {
+            if rank == self.rows {
+                break;
+            }
+            let mut pivot: Option<(usize, f64)> = None;
+            for row in rank..self.rows {
+                let element = self.get(row, column);
+                if is_negligible(element, tolerance) {
+                    continue;
+                }
+                let size = magnitude(element).unwrap_or(0.0);
+                match pivot {
+                    Some((_, best)) if best >= size => (),
+                    _ => pivot = Some((row, size)),
+                }
+            }
+            let pivot_row = match pivot {
+                Some((row, _)) => row,
+                None => continue,
+            };
+
+            // Swapping two rows changes the sign of the determinant
+            if pivot_row != rank {
+                for k in 0..self.columns {
+                    self.elements.swap(pivot_row * self.columns + k, rank * self.columns + k);
+                }
+                determinant = match determinant.apply_unary(&TokenType::Negation) {
+                    Ok(determinant) => determinant,
+                    Err(error) => return Err(error),
+                };
+            }
+            let pivot = self.get(rank, column).clone();
+            determinant = match arithmetic(&determinant, TokenType::Multiplication, &pivot, settings) {
+                Ok(determinant) => determinant,
+                Err(error) => return Err(error),
+            };
+            for k in column..self.columns {
+                match arithmetic(self.get(rank, k), TokenType::Division, &pivot, settings) {
+                    Ok(value) => self.set(rank, k, value),
+                    Err(error) => return Err(error),
+                }
+            }
+
+            for row in 0..self.rows {
+                let factor = self.get(row, column).clone();
+                if row == rank || is_negligible(&factor, 0.0) {
+                    continue;
+                }
+                for k in column..self.columns {
+                    let value = match arithmetic(&factor, TokenType::Multiplication, self.get(rank, k), settings) {
+                        Ok(product) => arithmetic(self.get(row, k), TokenType::Subtraction, &product, settings),
+                        Err(error) => return Err(error),
+                    };
+                    match value {
+                        Ok(value) => self.set(row, k, value),
+                        Err(error) => return Err(error),
+                    }
+                }
+                // Cancelled exactly, even where floats would leave a rounding error behind
+                self.set(row, column, Value::Integer(BigInt::zero()));
+            }
+            rank += 1;
+        }
+        Ok(Elimination { matrix: self, rank, determinant })
+    }
+}
+
+fn arithmetic(a: &Value, operator: TokenType, b: &Value, settings: Settings) -> Result<Value, EvaluationError> {
+    a.clone().apply_binary(&operator, b.clone(), settings)
+}
+
+// The size of a number, or None for anything elimination cannot work with
+fn magnitude(value: &Value) -> Option<f64> {
+    match value {
+        Value::Boolean(_) => None,
+        _ => value.as_complex().map(|value| value.norm()),
+    }
+}
+
+fn is_negligible(value: &Value, tolerance: f64) -> bool {
+    match value.as_rational() {
+        Some(value) => value.is_zero(),
+        None => magnitude(value).unwrap_or(0.0) <= tolerance,
+    }
+}
+
+// The roots of λ² - tr λ + det, which are (tr ± sqrt(tr² - 4 det)) / 2
+fn quadratic_roots(trace: &Value, determinant: &Value, settings: Settings) -> Result<Vec<Value>, EvaluationError> {
+    let four_determinant = arithmetic(&Value::Integer(BigInt::from(4)), TokenType::Multiplication, determinant, settings);
+    let discriminant = match (arithmetic(trace, TokenType::Multiplication, trace, settings), four_determinant) {
+        (Ok(square), Ok(four_determinant)) => square.apply_binary(&TokenType::Subtraction, four_determinant, settings),
+        (Err(error), _) | (_, Err(error)) => return Err(error),
+    };
+    let root = match discriminant {
+        Ok(discriminant) => Function::Sqrt.call(&[discriminant], settings),
+        Err(error) => return Err(error),
+    };
+    let root = match root {
+        Ok(root) => root,
+        Err(error) => return Err(error),
+    };
+
+    let mut roots: Vec<Value> = Vec::with_capacity(2);
+    for operator in [TokenType::Addition, TokenType::Subtraction] {
+        let root = match arithmetic(trace, operator, &root, settings) {
+            Ok(sum) => arithmetic(&sum, TokenType::Division, &Value::Integer(BigInt::from(2)), settings),
+            Err(error) => return Err(error),
+        };
+        match root {
+            Ok(root) => roots.push(root),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(roots)
+}
+
+// The roots of λ³ + aλ² + bλ + c by Cardano's formula, each refined with a few steps of
+// Newton's method to win back the precision lost in the cube roots
+fn cubic_roots(a: Complex64, b: Complex64, c: Complex64) -> Vec<Complex64> {
+    // Substituting λ = t - a/3 leaves t³ + pt + q
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let discriminant = (q * q / 4.0 + p * p * p / 27.0).sqrt();
+    // Of the two cube roots Cardano's formula allows, the larger one loses less to cancellation
+    let (first, second) = (-q / 2.0 + discriminant, -q / 2.0 - discriminant);
+    let u = if first.norm() >= second.norm() { first.cbrt() } else { second.cbrt() };
+    let rotation = Complex64::new(-0.5, 3.0_f64.sqrt() / 2.0);
+
+    let polynomial = |x: Complex64| ((x + a) * x + b) * x + c;
+    let derivative = |x: Complex64| (3.0 * x + 2.0 * a) * x + b;
+    let mut roots: Vec<Complex64> = Vec::with_capacity(3);
+    let mut w = u;
+    for _ in 0..3 {
+        let t = if w.is_zero() { Complex64::zero() } else { w - p / (3.0 * w) };
+        let mut root = t - a / 3.0;
+        for _ in 0..3 {
+            let step = polynomial(root) / derivative(root);
+            if !step.is_finite() {
+                break;
+            }
+            root -= step;
+        }
+        roots.push(root);
+        w *= rotation;
+    }
+    roots
+}
+
+fn is_cubic_root(x: &BigInt, a: &BigRational, b: &BigRational, c: &BigRational) -> bool {
+    let x = BigRational::from_integer(x.clone());
+    (((&x + a) * &x + b) * &x + c).is_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::AngleMode;
+
+    const SETTINGS: Settings = Settings { angle_mode: AngleMode::Radians, precision: None, word_size: None };
+
+    fn matrix(rows: &[&[i64]]) -> Matrix {
+        Matrix::new(rows.iter().map(|row| row.iter().map(|&value| Value::Integer(BigInt::from(value))).collect()).collect())
+    }
+
+    #[test]
+    fn test_elimination_stays_exact() {
+        let a = matrix(&[&[2, 1, 1], &[1, 3, 2], &[1, 0, 0]]);
+        assert_eq!(a.determinant(SETTINGS), Ok(Value::Integer(BigInt::from(-1))));
+        let inverse = a.inverse(SETTINGS).unwrap();
+        assert_eq!(a.multiply(&inverse, SETTINGS), Ok(Matrix::identity(3)));
+        assert_eq!(matrix(&[&[1, 2], &[2, 4]]).rank(SETTINGS), Ok(1));
+    }
+
+    #[test]
+    fn test_solve_shape_mismatch() {
+        let result = matrix(&[&[1, 2], &[3, 4]]).solve(&matrix(&[&[1], &[2], &[3]]), SETTINGS);
+        assert_eq!(result, Err(EvaluationError::ShapeMismatch {
+            expected: "[2, 1]".to_string(),
+            found: "[3, 1]".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_cubic_roots() {
+        // (λ - 1)(λ - 2)(λ - 3) = λ³ - 6λ² + 11λ - 6
+        let mut roots = cubic_roots(Complex64::new(-6.0, 0.0), Complex64::new(11.0, 0.0), Complex64::new(-6.0, 0.0));
+        roots.sort_by(|a, b| a.re.total_cmp(&b.re));
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((root - expected).norm() < 1e-12);
+        }
+    }
+}
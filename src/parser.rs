@@ -1,27 +1,103 @@
-use crate::lexer::{Token, TokenType};
+use crate::lexer::{split_radix, Span, Token, TokenType};
 use crate::errors::{ParseError};
+use num_bigint::BigInt;
+use num_rational::BigRational;
 
-#[derive(Debug, PartialEq)]
+// Parsing and evaluating recurse once per level of nesting, and continue on a new stack segment
+// when less than this much is left
+pub(crate) const STACK_RED_ZONE: usize = 64 * 1024;
+pub(crate) const STACK_SEGMENT_SIZE: usize = 1024 * 1024;
+
+// The nodes that can fail to evaluate carry the span of the input they were parsed from
+#[derive(Debug, PartialEq, Clone)]
 pub enum AstNode {
-    Number(f64),
+    Number(BigRational),
+    Boolean(bool),
+    ImaginaryUnit,
+    Unit(String, Span),
     UnaryOp {
         operator: TokenType,
         operand: Box<AstNode>,
+        span: Span,
     },
     BinaryOp {
         operator: TokenType,
         operand_1: Box<AstNode>,
         operand_2: Box<AstNode>,
+        span: Span,
+    },
+    PostfixOp {
+        operator: TokenType,
+        operand: Box<AstNode>,
+        span: Span,
     },
     Function {
         function: TokenType,
-        args: Box<AstNode>,
+        args: Vec<AstNode>,
+        span: Span,
+    },
+    Conditional {
+        condition: Box<AstNode>,
+        if_true: Box<AstNode>,
+        if_false: Box<AstNode>,
     },
     Assignment {
         name: String,
         value: Box<AstNode>,
+        span: Span,
+    },
+    FunctionDefinition {
+        name: String,
+        params: Vec<String>,
+        body: Box<AstNode>,
+        span: Span,
+    },
+    // A call on an identifier, which is implicit multiplication if the identifier is a variable
+    Call {
+        name: String,
+        args: Vec<AstNode>,
+        span: Span,
     },
-    Variable(String),
+    // `x -> x^2` or `(x, y) -> x * y`, an anonymous function that evaluates to a value
+    Lambda {
+        params: Vec<String>,
+        body: Box<AstNode>,
+        span: Span,
+    },
+    // A call on what an expression gives, like `(x -> x + 1)(2)` or `f(3)(2)`, which is
+    // implicit multiplication if it does not give a function
+    Apply {
+        callee: Box<AstNode>,
+        args: Vec<AstNode>,
+        span: Span,
+    },
+    List {
+        elements: Vec<AstNode>,
+        span: Span,
+    },
+    // `[1, 2; 3, 4]`, a matrix written row by row
+    Matrix {
+        rows: Vec<Vec<AstNode>>,
+        span: Span,
+    },
+    // `v[i]`
+    Index {
+        operand: Box<AstNode>,
+        index: Box<AstNode>,
+        span: Span,
+    },
+    // `v[a:b]`, where either end may be left out
+    Slice {
+        operand: Box<AstNode>,
+        start: Option<Box<AstNode>>,
+        end: Option<Box<AstNode>>,
+        span: Span,
+    },
+    Variable(String, Span),
+    Sequence(Vec<AstNode>),
+    // Stands in for a part of the input that could not be parsed, so the rest of it can still be
+    // checked. A tree with one of these is never returned by construct_ast.
+    Invalid,
 }
 
 trait Operator {
@@ -29,18 +105,39 @@ trait Operator {
     fn is_right_associative(&self) -> bool {
         return false;
     }
+    fn is_postfix(&self) -> bool {
+        return false;
+    }
 }
 
 impl Operator for TokenType {
     fn get_precedence(&self) -> u8 {
         match self {
-            TokenType::Negation => return 4,
-            TokenType::Keyword(_) => return 4,
-            TokenType::Exponentiation => return 3,
-            TokenType::Multiplication => return 2,
-            TokenType::Division => return 2,
-            TokenType::Addition => return 1,
-            TokenType::Subtraction => return 1,
+            TokenType::Negation => return 14,
+            TokenType::BitwiseNot => return 14,
+            TokenType::Keyword(_) => return 14,
+            TokenType::Exponentiation => return 13,
+            TokenType::Multiplication => return 11,
+            TokenType::Division => return 11,
+            TokenType::Addition => return 10,
+            TokenType::Subtraction => return 10,
+            // Unlike in C, the bitwise operators bind tighter than comparisons, so
+            // `x & 1 == 0` is `(x & 1) == 0`
+            TokenType::ShiftLeft => return 9,
+            TokenType::ShiftRight => return 9,
+            TokenType::BitwiseAnd => return 8,
+            TokenType::BitwiseXor => return 7,
+            TokenType::BitwiseOr => return 6,
+            TokenType::LessThan => return 5,
+            TokenType::LessThanOrEqual => return 5,
+            TokenType::GreaterThan => return 5,
+            TokenType::GreaterThanOrEqual => return 5,
+            TokenType::Equality => return 4,
+            TokenType::NotEqual => return 4,
+            TokenType::And => return 3,
+            TokenType::Or => return 2,
+            // `5 km/h to m/s` converts the whole expression on the left
+            TokenType::Conversion => return 1,
             _ => return 0,
         }
     }
@@ -51,15 +148,117 @@ impl Operator for TokenType {
             _ => return false,
         }
     }
+
+    fn is_postfix(&self) -> bool {
+        match self {
+            TokenType::Exclamation | TokenType::DoubleExclamation | TokenType::AngleUnit(_) => return true,
+            _ => return false,
+        }
+    }
+}
+
+const UNIT_PRECEDENCE: u8 = 12;
+
+// The span of the tokens from start up to, but not including, end
+fn span_of(tokens: &[Token], start: usize, end: usize) -> Span {
+    return tokens[start].span.to(tokens[end - 1].span);
+}
+
+// The span of the token at a position, or an empty span at the end of the input if there is
+// no token there
+fn span_at(tokens: &[Token], pos: usize) -> Span {
+    match tokens.get(pos) {
+        Some(token) => return token.span,
+        None => match tokens.last() {
+            Some(token) => return Span::new(token.span.end, token.span.end),
+            None => return Span::default(),
+        },
+    }
+}
+
+// What was expected where an operand is missing, described by the token before it
+fn expected_operand(tokens: &[Token], pos: usize) -> String {
+    let prev = match pos.checked_sub(1) {
+        Some(prev) => tokens.get(prev),
+        None => None,
+    };
+    match prev {
+        Some(prev) if prev.token_type.get_precedence() > 0 || prev.token_type == TokenType::Not => {
+            return format!("an operand after `{}`", prev.lexeme);
+        }
+        Some(prev) => return format!("an expression after `{}`", prev.lexeme),
+        None => return "an expression".to_string(),
+    }
 }
 
-fn parse_expression(tokens: &Vec<Token>, pos: usize, min_precedence: u8) -> Result<(AstNode, usize), ParseError> {
-    let (mut left, mut pos) = match parse_primary(tokens, pos) {
+// Skips what could not be parsed, up to the next of the given tokens that is not inside
+// parentheses or brackets, and returns its position. A `;` always stops it, as it ends the
+// statement, unless it separates the rows of a matrix.
+fn synchronise(tokens: &[Token], pos: usize, ends: &[TokenType]) -> usize {
+    // The parentheses and brackets that are open, innermost last
+    let mut open: Vec<&TokenType> = vec![];
+    let mut pos = pos;
+    while pos < tokens.len() {
+        match &tokens[pos].token_type {
+            TokenType::Semicolon if open.last() != Some(&&TokenType::LeftBracket) => return pos,
+            token_type if open.is_empty() && ends.contains(token_type) => return pos,
+            token_type @ (TokenType::LeftParenthesis | TokenType::LeftBracket) => open.push(token_type),
+            TokenType::RightParenthesis | TokenType::RightBracket => {
+                open.pop();
+            }
+            _ => (),
+        }
+        pos += 1;
+    }
+    return pos;
+}
+
+// Errors that can be recovered from are added to `errors`, with the part that failed left as
+// AstNode::Invalid. The rest are returned, for a caller that can recover from them.
+fn parse_expression(
+    tokens: &Vec<Token>,
+    pos: usize,
+    min_precedence: u8,
+    errors: &mut Vec<ParseError>,
+) -> Result<(AstNode, usize), ParseError> {
+    // Every level of nesting passes through here or parse_postfix, so deeply nested input moves
+    // on to a new stack segment instead of overflowing
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || parse_operators(tokens, pos, min_precedence, errors))
+}
+
+fn parse_operators(
+    tokens: &Vec<Token>,
+    pos: usize,
+    min_precedence: u8,
+    errors: &mut Vec<ParseError>,
+) -> Result<(AstNode, usize), ParseError> {
+    let start = pos;
+    let (mut left, mut pos) = match parse_postfix(tokens, pos, errors) {
         Ok(result) => result,
         Err(error) => return Err(error),
     };
 
     while pos < tokens.len() {
+        // A unit binds to what is before it tighter than `*` and `/`, but looser than `^`, so
+        // `100 km / 2 h` divides by two hours and `r^2 h` is `(r^2) h`
+        if tokens[pos].token_type == TokenType::Unit && UNIT_PRECEDENCE >= min_precedence {
+            let (unit, new_position) = parse_unit(tokens, pos);
+            left = AstNode::BinaryOp {
+                operator: TokenType::Multiplication,
+                operand_1: Box::new(left),
+                operand_2: Box::new(unit),
+                span: span_of(tokens, start, new_position),
+            };
+            pos = new_position;
+            continue;
+        }
+
+        // A lambda's parameters are read by parse_primary, so anything else before an arrow
+        // is not a parameter list
+        if tokens[pos].token_type == TokenType::Arrow {
+            return Err(ParseError::InvalidParameterList(span_of(tokens, start, pos)));
+        }
+
         let operator = &tokens[pos];
         let precedence = operator.token_type.get_precedence();
         if precedence < min_precedence || precedence == 0 { break; }
@@ -70,7 +269,7 @@ fn parse_expression(tokens: &Vec<Token>, pos: usize, min_precedence: u8) -> Resu
             precedence + 1 
         };
 
-        let (right, new_position) = match parse_expression(tokens, pos + 1, next_min_precedence) {
+        let (right, new_position) = match parse_expression(tokens, pos + 1, next_min_precedence, errors) {
             Ok(result) => result,
             Err(error) => return Err(error),
         };
@@ -79,64 +278,399 @@ fn parse_expression(tokens: &Vec<Token>, pos: usize, min_precedence: u8) -> Resu
             operator: operator.token_type.clone(),
             operand_1: Box::new(left),
             operand_2: Box::new(right),
+            span: span_of(tokens, start, new_position),
+        };
+
+        pos = new_position;
+    }
+
+    // The conditional operator binds loosest of all, and is right associative: `a ? b : c ? d : e`
+    if min_precedence == 0 && pos < tokens.len() && tokens[pos].token_type == TokenType::Question {
+        let (if_true, new_position) = match parse_expression(tokens, pos + 1, 0, errors) {
+            Ok(result) => result,
+            Err(error) => return Err(error),
         };
 
+        if new_position >= tokens.len() || tokens[new_position].token_type != TokenType::Colon {
+            return Err(ParseError::MissingColon(span_at(tokens, new_position)));
+        }
+
+        let (if_false, new_position) = match parse_expression(tokens, new_position + 1, 0, errors) {
+            Ok(result) => result,
+            Err(error) => return Err(error),
+        };
+
+        left = AstNode::Conditional {
+            condition: Box::new(left),
+            if_true: Box::new(if_true),
+            if_false: Box::new(if_false),
+        };
         pos = new_position;
     }
 
     Ok((left, pos))
 }
 
-fn parse_primary(tokens: &Vec<Token>, pos: usize) -> Result<(AstNode, usize), ParseError> {
+// Postfix operators and indexing bind tighter than any prefix or binary operator, so `-3!` is
+// `-(3!)` and `-v[0]` is `-(v[0])`
+fn parse_postfix(tokens: &Vec<Token>, pos: usize, errors: &mut Vec<ParseError>) -> Result<(AstNode, usize), ParseError> {
+    let start = pos;
+    // A prefix operator's operand is parsed from here, without passing through parse_expression
+    let primary = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || parse_primary(tokens, pos, errors));
+    let (mut operand, mut pos) = match primary {
+        Ok(result) => result,
+        Err(error) => return Err(error),
+    };
+
+    while pos < tokens.len() {
+        // An angle has one unit, so `30°°` is a mistake rather than a conversion
+        if matches!(tokens[pos].token_type, TokenType::AngleUnit(_))
+            && matches!(operand, AstNode::PostfixOp {operator: TokenType::AngleUnit(_), ..})
+        {
+            return Err(ParseError::UnexpectedToken {
+                expected: "an operator after the angle".to_string(),
+                found: tokens[pos].lexeme.clone(),
+                span: tokens[pos].span,
+            });
+        }
+        if tokens[pos].token_type.is_postfix() {
+            operand = AstNode::PostfixOp {
+                operator: tokens[pos].token_type.clone(),
+                operand: Box::new(operand),
+                span: span_of(tokens, start, pos + 1),
+            };
+            pos += 1;
+        } else if tokens[pos].token_type == TokenType::LeftBracket {
+            (operand, pos) = match parse_index(tokens, start, operand, pos, errors) {
+                Ok(result) => result,
+                Err(error) => return Err(error),
+            };
+        } else if may_give_function(&operand, tokens[start].token_type == TokenType::LeftParenthesis)
+            && tokens[pos].token_type == TokenType::Multiplication
+            && tokens[pos].is_implicit()
+            && pos + 1 < tokens.len()
+            && tokens[pos + 1].token_type == TokenType::LeftParenthesis
+        {
+            // Arguments after something that may give a function call it, as the lexer can
+            // only tell that it was followed by a parenthesis
+            let (args, new_position) = match parse_arguments(tokens, pos + 1, errors) {
+                Ok(result) => result,
+                Err(error) => return Err(error),
+            };
+            operand = AstNode::Apply {
+                callee: Box::new(operand),
+                args,
+                span: span_of(tokens, start, new_position),
+            };
+            pos = new_position;
+        } else {
+            break;
+        }
+    }
+
+    Ok((operand, pos))
+}
+
+// Calls and lambdas may give functions, and so may a variable or a conditional in parentheses,
+// as in `(f)(2)`. Anything else followed by arguments in parentheses is multiplied by them, so
+// `(a + b)(c + d)` keeps the precedence of implicit multiplication.
+fn may_give_function(operand: &AstNode, parenthesised: bool) -> bool {
+    match operand {
+        AstNode::Call {..} | AstNode::Apply {..} | AstNode::Lambda {..} => true,
+        AstNode::Variable(..) | AstNode::Conditional {..} => parenthesised,
+        _ => false,
+    }
+}
+
+// Reads an index `[i]` or a slice `[a:b]` after an operand, which starts at start
+fn parse_index(
+    tokens: &Vec<Token>,
+    start: usize,
+    operand: AstNode,
+    pos: usize,
+    errors: &mut Vec<ParseError>,
+) -> Result<(AstNode, usize), ParseError> {
+    let opening = pos;
+    let mut pos = pos + 1;
+    let mut index: Option<Box<AstNode>> = None;
+    if pos < tokens.len() && !matches!(tokens[pos].token_type, TokenType::Colon | TokenType::RightBracket) {
+        let (expression, new_position) = match parse_expression(tokens, pos, 0, errors) {
+            Ok(result) => result,
+            Err(error) => return Err(error),
+        };
+        index = Some(Box::new(expression));
+        pos = new_position;
+    }
+
+    let slice = pos < tokens.len() && tokens[pos].token_type == TokenType::Colon;
+    let mut end: Option<Box<AstNode>> = None;
+    if slice {
+        pos += 1;
+        if pos < tokens.len() && tokens[pos].token_type != TokenType::RightBracket {
+            let (expression, new_position) = match parse_expression(tokens, pos, 0, errors) {
+                Ok(result) => result,
+                Err(error) => return Err(error),
+            };
+            end = Some(Box::new(expression));
+            pos = new_position;
+        }
+    }
+
+    if pos >= tokens.len() || tokens[pos].token_type != TokenType::RightBracket {
+        return Err(ParseError::MissingClosingBracket {
+            opening: tokens[opening].span,
+            found: span_at(tokens, pos),
+        });
+    }
+    let span = span_of(tokens, start, pos + 1);
+    if slice {
+        return Ok((AstNode::Slice { operand: Box::new(operand), start: index, end, span }, pos + 1));
+    }
+    match index {
+        Some(index) => return Ok((AstNode::Index { operand: Box::new(operand), index, span }, pos + 1)),
+        None => return Err(ParseError::UnexpectedToken {
+            expected: "an index".to_string(),
+            found: tokens[pos].lexeme.clone(),
+            span: tokens[pos].span,
+        }),
+    }
+}
+
+// Reads a unit with an optional whole exponent, as in `s^-1`. Any other exponent is left
+// for parse_expression, and applies to the whole quantity.
+fn parse_unit(tokens: &[Token], pos: usize) -> (AstNode, usize) {
+    let unit = AstNode::Unit(tokens[pos].lexeme.clone(), tokens[pos].span);
+    let mut exponent_position = pos + 2;
+    let negative = exponent_position < tokens.len() && tokens[exponent_position].token_type == TokenType::Negation;
+    if negative {
+        exponent_position += 1;
+    }
+    if pos + 1 >= tokens.len()
+        || tokens[pos + 1].token_type != TokenType::Exponentiation
+        || exponent_position >= tokens.len()
+        || tokens[exponent_position].token_type != TokenType::Number
+    {
+        return (unit, pos + 1);
+    }
+    let mut exponent = match parse_decimal(&tokens[exponent_position].lexeme.replace('_', "")) {
+        Some(exponent) => AstNode::Number(exponent),
+        None => return (unit, pos + 1),
+    };
+    if negative {
+        exponent = AstNode::UnaryOp {
+            operator: TokenType::Negation,
+            operand: Box::new(exponent),
+            span: span_of(tokens, exponent_position - 1, exponent_position + 1),
+        };
+    }
+    (
+        AstNode::BinaryOp {
+            operator: TokenType::Exponentiation,
+            operand_1: Box::new(unit),
+            operand_2: Box::new(exponent),
+            span: span_of(tokens, pos, exponent_position + 1),
+        },
+        exponent_position + 1,
+    )
+}
+
+// Exponents further from zero than this would make exact numbers too large to work with
+const MAX_EXPONENT: u32 = 100_000;
+
+// Reads a decimal literal as an exact fraction, so `0.1` is exactly 1/10 and `1.5e-9` is
+// exactly 15/10^10
+fn parse_decimal(lexeme: &str) -> Option<BigRational> {
+    let (mantissa, exponent) = match lexeme.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => match exponent.parse::<i32>() {
+            Ok(exponent) if exponent.unsigned_abs() <= MAX_EXPONENT => (mantissa, exponent),
+            _ => return None,
+        },
+        None => (lexeme, 0),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (mantissa, ""),
+    };
+    let numerator = match format!("{}{}", whole, fraction).parse::<BigInt>() {
+        Ok(numerator) => numerator,
+        Err(_) => return None,
+    };
+    let denominator = BigInt::from(10).pow(fraction.len() as u32);
+    let scale = BigRational::from_integer(BigInt::from(10).pow(exponent.unsigned_abs()));
+    if exponent < 0 {
+        return Some(BigRational::new(numerator, denominator) / scale);
+    }
+    return Some(BigRational::new(numerator, denominator) * scale);
+}
+
+fn parse_primary(tokens: &Vec<Token>, pos: usize, errors: &mut Vec<ParseError>) -> Result<(AstNode, usize), ParseError> {
     if pos >= tokens.len() {
-        return Err(ParseError::UnexpectedEndOfInput);
+        return Err(ParseError::UnexpectedEndOfInput {
+            expected: expected_operand(tokens, pos),
+            span: span_at(tokens, pos),
+        });
     }
 
     match &tokens[pos].token_type {
         TokenType::Number => {
-            let number = match tokens[pos].lexeme.parse::<f64>() {
-                Ok(num) => num,
-                Err(_) => return Err(ParseError::UnexpectedToken(tokens[pos].lexeme.clone())),
+            // Digits may be grouped with underscores, as in `1_000_000`
+            let lexeme = tokens[pos].lexeme.replace('_', "");
+            let number = match split_radix(&lexeme) {
+                Some((radix, digits)) => BigInt::parse_bytes(digits.as_bytes(), radix).map(BigRational::from_integer),
+                None => parse_decimal(&lexeme),
+            };
+            let number = match number {
+                Some(num) => num,
+                None => return Err(ParseError::InvalidNumber(tokens[pos].lexeme.clone(), tokens[pos].span)),
             };
             return Ok((AstNode::Number(number), pos + 1));
         }
         
+        TokenType::Boolean => {
+            return Ok((AstNode::Boolean(tokens[pos].lexeme == "true"), pos + 1));
+        }
+
+        TokenType::ImaginaryUnit => {
+            // `i = 3` is parsed as an assignment so that it is reported as assigning to a constant
+            if pos + 1 < tokens.len() && tokens[pos + 1].token_type == TokenType::Equals {
+                return parse_assignment(tokens, pos, "i".to_string(), errors);
+            }
+            return Ok((AstNode::ImaginaryUnit, pos + 1));
+        }
+
+        TokenType::Unit => {
+            return Ok((AstNode::Unit(tokens[pos].lexeme.clone(), tokens[pos].span), pos + 1));
+        }
+
+        // Anything that fails inside the parentheses is skipped up to the closing one
         TokenType::LeftParenthesis => {
-            let (expression, new_position) = match parse_expression(tokens, pos + 1, 0) {
+            if let Some((params, arrow)) = lambda_parameters(tokens, pos) {
+                return parse_lambda(tokens, pos, params, arrow, errors);
+            }
+
+            let (expression, mut end) = match parse_expression(tokens, pos + 1, 0, errors) {
                 Ok(result) => result,
-                Err(error) => return Err(error),
+                Err(error) => {
+                    errors.push(error);
+                    (AstNode::Invalid, synchronise(tokens, pos + 1, &[TokenType::RightParenthesis]))
+                }
             };
-            
-            if new_position >= tokens.len() || tokens[new_position].token_type != TokenType::RightParenthesis {
-                return Err(ParseError::MissingClosingParenthesis);
+
+            match tokens.get(end).map(|token| &token.token_type) {
+                Some(TokenType::RightParenthesis) | Some(TokenType::Semicolon) | None => (),
+                Some(_) => {
+                    errors.push(ParseError::UnexpectedToken {
+                        expected: "`)`".to_string(),
+                        found: tokens[end].lexeme.clone(),
+                        span: tokens[end].span,
+                    });
+                    end = synchronise(tokens, end, &[TokenType::RightParenthesis]);
+                }
+            }
+            if end >= tokens.len() || tokens[end].token_type != TokenType::RightParenthesis {
+                return Err(ParseError::MissingClosingParenthesis {
+                    opening: tokens[pos].span,
+                    found: span_at(tokens, end),
+                });
             }
 
-            Ok((expression, new_position + 1))
+            Ok((expression, end + 1))
         },
         
-        TokenType::Negation => {
-            let (operand, new_position) = match parse_primary(tokens, pos + 1) {
+        // `[1, 2]` is a list, and `[1, 2; 3, 4]` a matrix with a row on each side of the `;`
+        TokenType::LeftBracket => {
+            if pos + 1 < tokens.len() && tokens[pos + 1].token_type == TokenType::RightBracket {
+                return Ok((AstNode::List { elements: vec![], span: span_of(tokens, pos, pos + 2) }, pos + 2));
+            }
+            let closing = [TokenType::RightBracket, TokenType::Semicolon];
+            let mut rows: Vec<Vec<AstNode>> = vec![];
+            let mut row_start = pos + 1;
+            loop {
+                let (row, end) = match parse_separated(tokens, pos, row_start, &closing, errors) {
+                    Ok(result) => result,
+                    Err(error) => return Err(error),
+                };
+                rows.push(row);
+                if tokens[end].token_type == TokenType::RightBracket {
+                    let span = span_of(tokens, pos, end + 1);
+                    if rows.len() == 1 {
+                        return Ok((AstNode::List { elements: rows.remove(0), span }, end + 1));
+                    }
+                    return Ok((AstNode::Matrix { rows, span }, end + 1));
+                }
+                row_start = end + 1;
+            }
+        },
+
+        TokenType::Negation | TokenType::BitwiseNot => {
+            let (operand, new_position) = match parse_postfix(tokens, pos + 1, errors) {
+                Ok(result) => result,
+                Err(error) => return Err(error),
+            };
+            Ok((
+                AstNode::UnaryOp {
+                    operator: tokens[pos].token_type.clone(),
+                    operand: Box::new(operand),
+                    span: span_of(tokens, pos, new_position),
+                },
+                new_position,
+            ))
+        },
+
+        // `not` binds looser than comparisons, so `not a == b` is `not (a == b)`
+        TokenType::Not => {
+            let (operand, new_position) = match parse_expression(tokens, pos + 1, TokenType::Equality.get_precedence(), errors) {
                 Ok(result) => result,
                 Err(error) => return Err(error),
             };
             Ok((
                 AstNode::UnaryOp {
-                    operator: TokenType::Negation,
+                    operator: TokenType::Not,
                     operand: Box::new(operand),
+                    span: span_of(tokens, pos, new_position),
                 },
                 new_position,
             ))
         },
 
         TokenType::Keyword(function) => {
-            let (value, new_position) = match parse_primary(tokens, pos + 1) {
+            if pos + 1 < tokens.len() && tokens[pos + 1].token_type == TokenType::LeftParenthesis {
+                let (args, new_position) = match parse_arguments(tokens, pos + 1, errors) {
+                    Ok(result) => result,
+                    Err(error) => return Err(error),
+                };
+                return Ok((
+                    AstNode::Function {
+                        function: TokenType::Keyword(function.clone()),
+                        args,
+                        span: span_of(tokens, pos, new_position),
+                    },
+                    new_position,
+                ));
+            }
+
+            // A function without arguments, as in `map(sin, v)`, is passed as a lambda that calls it
+            let ends_operand = |token: &Token| matches!(
+                token.token_type,
+                TokenType::Comma | TokenType::RightParenthesis | TokenType::RightBracket | TokenType::Semicolon
+            );
+            if tokens.get(pos + 1).is_none_or(ends_operand) {
+                let span = tokens[pos].span;
+                let params: Vec<String> = ["x", "y"][..function.arity().0].iter().map(|param| param.to_string()).collect();
+                let args = params.iter().map(|param| AstNode::Variable(param.clone(), span)).collect();
+                let body = AstNode::Function { function: TokenType::Keyword(function.clone()), args, span };
+                return Ok((AstNode::Lambda { params, body: Box::new(body), span }, pos + 1));
+            }
+
+            let (value, new_position) = match parse_postfix(tokens, pos + 1, errors) {
                 Ok(result) => result,
                 Err(error) => return Err(error),
             };
             Ok((
                     AstNode::Function {
                         function: TokenType::Keyword(function.clone()),
-                        args: Box::new(value),
+                        args: vec![value],
+                        span: span_of(tokens, pos, new_position),
                     },
                     new_position,
             ))
@@ -145,40 +679,262 @@ fn parse_primary(tokens: &Vec<Token>, pos: usize) -> Result<(AstNode, usize), Pa
 
         TokenType::Identifier => {
             let name = tokens[pos].lexeme.clone();
+            if pos + 1 < tokens.len() && tokens[pos + 1].token_type == TokenType::Arrow {
+                return parse_lambda(tokens, pos, vec![name], pos + 1, errors);
+            }
             if pos + 1 < tokens.len() && tokens[pos + 1].token_type == TokenType::Equals {
-                let (value, new_position) = match parse_expression(tokens, pos + 2, 0) {
+                parse_assignment(tokens, pos, name, errors)
+            } else if pos + 1 < tokens.len() && tokens[pos + 1].token_type == TokenType::LeftParenthesis {
+                let (args, new_position) = match parse_arguments(tokens, pos + 1, errors) {
+                    Ok(result) => result,
+                    Err(error) => return Err(error),
+                };
+
+                if new_position >= tokens.len() || tokens[new_position].token_type != TokenType::Equals {
+                    let span = span_of(tokens, pos, new_position);
+                    return Ok((AstNode::Call { name, args, span }, new_position));
+                }
+
+                // `f(x, y) = ...` defines a function, so every argument must be a plain parameter name
+                let mut params: Vec<String> = Vec::with_capacity(args.len());
+                for arg in args {
+                    match arg {
+                        AstNode::Variable(param, _) if params.contains(&param) => {
+                            return Err(ParseError::InvalidParameterList(span_of(tokens, pos + 1, new_position)));
+                        }
+                        AstNode::Variable(param, _) => params.push(param),
+                        // An argument that could not be parsed has already been reported
+                        AstNode::Invalid => (),
+                        _ => return Err(ParseError::InvalidParameterList(span_of(tokens, pos + 1, new_position))),
+                    }
+                }
+
+                let (body, new_position) = match parse_expression(tokens, new_position + 1, 0, errors) {
                     Ok(result) => result,
                     Err(error) => return Err(error),
                 };
                 Ok((
-                    AstNode::Assignment {
+                    AstNode::FunctionDefinition {
                         name,
-                        value: Box::new(value),
+                        params,
+                        body: Box::new(body),
+                        span: span_of(tokens, pos, new_position),
                     },
                     new_position,
                 ))
             } else {
-                Ok((AstNode::Variable(name), pos + 1))
+                Ok((AstNode::Variable(name, tokens[pos].span), pos + 1))
             }
         },
         
         _ => {
-            return Err(ParseError::UnexpectedToken(tokens[pos].lexeme.clone()));
+            return Err(ParseError::UnexpectedToken {
+                expected: expected_operand(tokens, pos),
+                found: tokens[pos].lexeme.clone(),
+                span: tokens[pos].span,
+            });
         },
     }
 }
 
-pub fn construct_ast(tokens: &Vec<Token>) -> Result<AstNode, ParseError> {
-    let (ast, pos) = match parse_expression(tokens, 0, 0) {
+// The parameters of a lambda written `(x, y) -> ...` and the position of its arrow, or None if
+// the parenthesis at pos does not start one
+fn lambda_parameters(tokens: &[Token], pos: usize) -> Option<(Vec<String>, usize)> {
+    let mut params: Vec<String> = vec![];
+    let mut pos = pos + 1;
+    let empty = match tokens.get(pos) {
+        Some(token) => token.token_type == TokenType::RightParenthesis,
+        None => return None,
+    };
+    if !empty {
+        loop {
+            match tokens.get(pos) {
+                Some(token) if token.token_type == TokenType::Identifier => params.push(token.lexeme.clone()),
+                _ => return None,
+            }
+            match tokens.get(pos + 1).map(|token| &token.token_type) {
+                Some(TokenType::Comma) => pos += 2,
+                Some(TokenType::RightParenthesis) => {
+                    pos += 1;
+                    break;
+                }
+                _ => return None,
+            }
+        }
+    }
+    match tokens.get(pos + 1) {
+        Some(token) if token.token_type == TokenType::Arrow => return Some((params, pos + 1)),
+        _ => return None,
+    }
+}
+
+// Parses `name = value` where `start` is the position of the name
+fn parse_assignment(
+    tokens: &Vec<Token>,
+    start: usize,
+    name: String,
+    errors: &mut Vec<ParseError>,
+) -> Result<(AstNode, usize), ParseError> {
+    let (value, new_position) = match parse_expression(tokens, start + 2, 0, errors) {
+        Ok(result) => result,
+        Err(error) => return Err(error),
+    };
+    Ok((
+        AstNode::Assignment {
+            name,
+            value: Box::new(value),
+            span: span_of(tokens, start, new_position),
+        },
+        new_position,
+    ))
+}
+
+// The body of a lambda reaches as far as it can, like the right side of an assignment, so
+// `x -> x^2 + 1` is the whole expression and `sum(x -> x^2, 1, 10)` stops at the comma
+fn parse_lambda(
+    tokens: &Vec<Token>,
+    start: usize,
+    params: Vec<String>,
+    arrow: usize,
+    errors: &mut Vec<ParseError>,
+) -> Result<(AstNode, usize), ParseError> {
+    for (i, param) in params.iter().enumerate() {
+        if params[..i].contains(param) {
+            return Err(ParseError::InvalidParameterList(span_of(tokens, start, arrow)));
+        }
+    }
+    let (body, new_position) = match parse_expression(tokens, arrow + 1, 0, errors) {
         Ok(result) => result,
         Err(error) => return Err(error),
     };
+    Ok((
+        AstNode::Lambda {
+            params,
+            body: Box::new(body),
+            span: span_of(tokens, start, new_position),
+        },
+        new_position,
+    ))
+}
+
+// Parses a parenthesised, comma-separated argument list starting at the opening parenthesis.
+fn parse_arguments(tokens: &Vec<Token>, pos: usize, errors: &mut Vec<ParseError>) -> Result<(Vec<AstNode>, usize), ParseError> {
+    // `f()` calls a function that takes no arguments, like the lambda `() -> 42`
+    if pos + 1 < tokens.len() && tokens[pos + 1].token_type == TokenType::RightParenthesis {
+        return Ok((vec![], pos + 2));
+    }
+    return match parse_separated(tokens, pos, pos + 1, &[TokenType::RightParenthesis], errors) {
+        Ok((args, end)) => Ok((args, end + 1)),
+        Err(error) => Err(error),
+    };
+}
+
+// Parses comma-separated expressions from start up to one of the closing tokens, and returns
+// them with the position of that token. The closing token is a `)` for arguments, and a `]` or
+// a `;` for the rows of a list or matrix, which were opened by the token at opening. An
+// expression that fails is skipped up to the next `,` or closing token, so the others are still
+// checked.
+fn parse_separated(
+    tokens: &Vec<Token>,
+    opening: usize,
+    start: usize,
+    closing: &[TokenType],
+    errors: &mut Vec<ParseError>,
+) -> Result<(Vec<AstNode>, usize), ParseError> {
+    let mut args: Vec<AstNode> = vec![];
+    let mut pos = start;
+    let mut ends = vec![TokenType::Comma];
+    ends.extend_from_slice(closing);
+    let brackets = closing.contains(&TokenType::RightBracket);
+
+    loop {
+        let mut end = match parse_expression(tokens, pos, 0, errors) {
+            Ok((arg, new_position)) => {
+                args.push(arg);
+                new_position
+            }
+            Err(error) => {
+                errors.push(error);
+                args.push(AstNode::Invalid);
+                synchronise(tokens, pos, &ends)
+            }
+        };
+
+        match tokens.get(end).map(|token| &token.token_type) {
+            Some(token_type) if ends.contains(token_type) => (),
+            Some(TokenType::Semicolon) | None => (),
+            Some(_) => {
+                errors.push(ParseError::UnexpectedToken {
+                    expected: (if brackets { "`,`, `;` or `]`" } else { "`,` or `)`" }).to_string(),
+                    found: tokens[end].lexeme.clone(),
+                    span: tokens[end].span,
+                });
+                end = synchronise(tokens, end, &ends);
+            }
+        }
+        match tokens.get(end).map(|token| &token.token_type) {
+            Some(TokenType::Comma) => pos = end + 1,
+            Some(token_type) if closing.contains(token_type) => return Ok((args, end)),
+            _ if brackets => return Err(ParseError::MissingClosingBracket {
+                opening: tokens[opening].span,
+                found: span_at(tokens, end),
+            }),
+            _ => return Err(ParseError::MissingClosingParenthesis {
+                opening: tokens[opening].span,
+                found: span_at(tokens, end),
+            }),
+        }
+    }
+}
+
+// Statements are separated by semicolons, and a single statement is returned on its own. A
+// statement that fails is skipped up to the next `;`, so every statement is checked and all of
+// their errors are returned together.
+pub fn construct_ast(tokens: &Vec<Token>) -> Result<AstNode, Vec<ParseError>> {
+    let mut statements: Vec<AstNode> = vec![];
+    let mut errors: Vec<ParseError> = vec![];
+    let mut pos = 0;
+
+    loop {
+        let mut end = match parse_expression(tokens, pos, 0, &mut errors) {
+            Ok((statement, new_position)) => {
+                statements.push(statement);
+                new_position
+            }
+            Err(error) => {
+                errors.push(error);
+                synchronise(tokens, pos, &[])
+            }
+        };
 
-    if pos < tokens.len() {
-        return Err(ParseError::UnexpectedTokensAtEnd);
+        if end < tokens.len() && tokens[end].token_type != TokenType::Semicolon {
+            let next = synchronise(tokens, end, &[]);
+            let found: Vec<&str> = tokens[end..next]
+                .iter()
+                .filter(|token| !token.is_implicit())
+                .map(|token| token.lexeme.as_str())
+                .collect();
+            errors.push(ParseError::UnexpectedTokensAtEnd {
+                found: found.join(" "),
+                span: span_of(tokens, end, next),
+            });
+            end = next;
+        }
+
+        // A trailing semicolon ends the sequence
+        pos = end + 1;
+        if pos >= tokens.len() {
+            break;
+        }
     }
 
-    Ok(ast)
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    if statements.len() == 1 {
+        return Ok(statements.remove(0));
+    }
+    Ok(AstNode::Sequence(statements))
 }
 
 #[cfg(test)]
@@ -187,51 +943,62 @@ mod tests {
     use crate::evaluator::Function;
 
     fn num(n: &str) -> Token {
-        Token { token_type: TokenType::Number, lexeme: n.to_string() }
+        Token { token_type: TokenType::Number, lexeme: n.to_string(), span: Span::default() }
     }
     fn op(token_type: TokenType, lexeme: &str) -> Token {
         Token {
             token_type,
             lexeme: lexeme.to_string(),
+            span: Span::default(),
         }
     }
+    // Gives the tokens the spans they would have if they were written with a space between each
+    fn spaced(mut tokens: Vec<Token>) -> Vec<Token> {
+        let mut position = 0;
+        for token in tokens.iter_mut() {
+            let length = token.lexeme.chars().count();
+            token.span = Span::new(position, position + length);
+            position += length + 1;
+        }
+        return tokens;
+    }
 
     // Parses a number in parentheses
     #[test]
     fn test_parse_primary_number() {
-        let tokens = vec![num("42")];
-        let (ast, pos) = match parse_primary(&tokens, 0) {
+        let tokens = spaced(vec![num("42")]);
+        let (ast, pos) = match parse_primary(&tokens, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
-        assert_eq!(ast, AstNode::Number(42.0));
+        assert_eq!(ast, AstNode::Number(BigRational::from_integer(42.into())));
         assert_eq!(pos, 1);
     }
 
     // 
     #[test]
     fn test_parse_primary_parenthesis() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::LeftParenthesis, "("),
             num("7"),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let (ast, pos) = match parse_primary(&tokens, 0) {
+        ]);
+        let (ast, pos) = match parse_primary(&tokens, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
-        assert_eq!(ast, AstNode::Number(7.0));
+        assert_eq!(ast, AstNode::Number(BigRational::from_integer(7.into())));
         assert_eq!(pos, 3);
     }
 
     #[test]
     fn test_parse_expression_addition() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             num("1"),
             op(TokenType::Addition, "+"),
             num("2"),
-        ];
-        let (ast, pos) = match parse_expression(&tokens, 0, 0) {
+        ]);
+        let (ast, pos) = match parse_expression(&tokens, 0, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
@@ -239,8 +1006,9 @@ mod tests {
             ast,
             AstNode::BinaryOp {
                 operator: TokenType::Addition,
-                operand_1: Box::new(AstNode::Number(1.0)),
-                operand_2: Box::new(AstNode::Number(2.0)),
+                operand_1: Box::new(AstNode::Number(BigRational::from_integer(1.into()))),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                span: Span::new(0, 5),
             }
         );
         assert_eq!(pos, 3);
@@ -248,14 +1016,14 @@ mod tests {
 
     #[test]
     fn test_parse_expression_precedence() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             num("1"),
             op(TokenType::Addition, "+"),
             num("2"),
             op(TokenType::Multiplication, "*"),
             num("3"),
-        ];
-        let (ast, pos) = match parse_expression(&tokens, 0, 0) {
+        ]);
+        let (ast, pos) = match parse_expression(&tokens, 0, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
@@ -263,12 +1031,14 @@ mod tests {
             ast,
             AstNode::BinaryOp {
                 operator: TokenType::Addition,
-                operand_1: Box::new(AstNode::Number(1.0)),
+                operand_1: Box::new(AstNode::Number(BigRational::from_integer(1.into()))),
                 operand_2: Box::new(AstNode::BinaryOp {
                     operator: TokenType::Multiplication,
-                    operand_1: Box::new(AstNode::Number(2.0)),
-                    operand_2: Box::new(AstNode::Number(3.0)),
+                    operand_1: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                    operand_2: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+                    span: Span::new(4, 9),
                 }),
+                span: Span::new(0, 9),
             }
         );
         assert_eq!(pos, 5);
@@ -276,13 +1046,13 @@ mod tests {
 
     #[test]
     fn test_parse_simple_function() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::Keyword(Function::Sin), "Sin"),
             op(TokenType::LeftParenthesis, "("),
             num("0"),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let (ast, pos) = match parse_primary(&tokens, 0) {
+        ]);
+        let (ast, pos) = match parse_primary(&tokens, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
@@ -290,7 +1060,8 @@ mod tests {
             ast,
             AstNode::Function {
                 function: TokenType::Keyword(Function::Sin),
-                args: Box::new(AstNode::Number(0.0)),
+                args: vec![AstNode::Number(BigRational::from_integer(0.into()))],
+                span: Span::new(0, 9),
             }
         );
         assert_eq!(pos, 4);
@@ -298,15 +1069,15 @@ mod tests {
 
     #[test]
     fn test_parse_function_containing_other_operation() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::Keyword(Function::Sin), "Sin"),
             op(TokenType::LeftParenthesis, "("),
             num("0"),
             op(TokenType::Addition, "+"),
             num("1"),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let (ast, pos) = match parse_primary(&tokens, 0) {
+        ]);
+        let (ast, pos) = match parse_primary(&tokens, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
@@ -314,11 +1085,13 @@ mod tests {
             ast,
             AstNode::Function {
                 function: TokenType::Keyword(Function::Sin),
-                args: Box::new(AstNode::BinaryOp {
+                args: vec![AstNode::BinaryOp {
                     operator: TokenType::Addition,
-                    operand_1: Box::new(AstNode::Number(0.0)),
-                    operand_2: Box::new(AstNode::Number(1.0)),
-                }),
+                    operand_1: Box::new(AstNode::Number(BigRational::from_integer(0.into()))),
+                    operand_2: Box::new(AstNode::Number(BigRational::from_integer(1.into()))),
+                    span: Span::new(6, 11),
+                }],
+                span: Span::new(0, 13),
             }
         );
         assert_eq!(pos, 6);
@@ -326,27 +1099,44 @@ mod tests {
 
     #[test]
     fn test_parse_function_containing_incomplete_expression() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::Keyword(Function::Sin), "Sin"),
             op(TokenType::LeftParenthesis, "("),
             num("0"),
             op(TokenType::Addition, "+"),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let result = parse_primary(&tokens, 0);
-        assert!(result.is_err());
+        ]);
+        let mut errors: Vec<ParseError> = vec![];
+        let (ast, pos) = match parse_primary(&tokens, 0, &mut errors) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        assert_eq!(
+            ast,
+            AstNode::Function {
+                function: TokenType::Keyword(Function::Sin),
+                args: vec![AstNode::Invalid],
+                span: Span::new(0, 11),
+            }
+        );
+        assert_eq!(pos, 5);
+        assert_eq!(errors, vec![ParseError::UnexpectedToken {
+            expected: "an operand after `+`".to_string(),
+            found: ")".to_string(),
+            span: Span::new(10, 11),
+        }]);
     }
 
     #[test]
     fn test_parse_expression_with_function_and_unary_negation() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::Negation, "-"),
             op(TokenType::Keyword(Function::Sin), "Sin"),
             op(TokenType::LeftParenthesis, "("),
             num("1"),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let (ast, pos) = match parse_primary(&tokens, 0) {
+        ]);
+        let (ast, pos) = match parse_primary(&tokens, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
@@ -356,8 +1146,10 @@ mod tests {
                 operator: TokenType::Negation,
                 operand: Box::new(AstNode::Function {
                     function: TokenType::Keyword(Function::Sin),
-                    args: Box::new(AstNode::Number(1.0)),
+                    args: vec![AstNode::Number(BigRational::from_integer(1.into()))],
+                    span: Span::new(2, 11),
                 }),
+                span: Span::new(0, 11),
             }
         );
         assert_eq!(pos, 5);
@@ -365,7 +1157,7 @@ mod tests {
 
     #[test]
     fn test_parse_nested_functions() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::Keyword(Function::Sin), "Sin"),
             op(TokenType::LeftParenthesis, "("),
             op(TokenType::Keyword(Function::Cos), "Cos"),
@@ -373,8 +1165,8 @@ mod tests {
             num("0"),
             op(TokenType::RightParenthesis, ")"),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let (ast, pos) = match parse_primary(&tokens, 0) {
+        ]);
+        let (ast, pos) = match parse_primary(&tokens, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
@@ -382,33 +1174,46 @@ mod tests {
             ast,
             AstNode::Function {
                 function: TokenType::Keyword(Function::Sin),
-                args: Box::new(AstNode::Function {
+                args: vec![AstNode::Function {
                     function: TokenType::Keyword(Function::Cos),
-                    args: Box::new(AstNode::Number(0.0)),
-                }),
+                    args: vec![AstNode::Number(BigRational::from_integer(0.into()))],
+                    span: Span::new(6, 15),
+                }],
+                span: Span::new(0, 17),
             }
         );
         assert_eq!(pos, 7);
     }
 
-    #[should_panic(expected = "ParseError: UnexpectedToken(\")\"")]
     #[test]
     fn test_parse_function_with_empty_parentheses() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::Keyword(Function::Sin), "Sin"),
             op(TokenType::LeftParenthesis, "("),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let (_ast, _pos) = match parse_primary(&tokens, 0) {
+        ]);
+        // The arguments are checked against the function when it is called, as a lambda may
+        // take none
+        let mut errors: Vec<ParseError> = vec![];
+        let (node, pos) = match parse_primary(&tokens, 0, &mut errors) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            node,
+            AstNode::Function {
+                function: TokenType::Keyword(Function::Sin),
+                args: vec![],
+                span: Span::new(0, 7),
+            }
+        );
+        assert_eq!(pos, 3);
     }
 
-    #[should_panic(expected = "ParseError: UnexpectedToken(\"+\")")]
     #[test]
     fn test_parse_function_with_unexpected_token() {
-        let tokens = vec![
+        let tokens = spaced(vec![
             op(TokenType::Keyword(Function::Sin), "Sin"),
             op(TokenType::LeftParenthesis, "("),
             num("0"),
@@ -416,17 +1221,137 @@ mod tests {
             op(TokenType::Addition, "+"),
             num("1"),
             op(TokenType::RightParenthesis, ")"),
-        ];
-        let (_ast, _pos) = match parse_primary(&tokens, 0) {
+        ]);
+        let mut errors: Vec<ParseError> = vec![];
+        match parse_primary(&tokens, 0, &mut errors) {
+            Ok(_) => (),
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        assert_eq!(errors, vec![ParseError::UnexpectedToken {
+            expected: "an operand after `+`".to_string(),
+            found: "+".to_string(),
+            span: Span::new(10, 11),
+        }]);
+    }
+
+    #[test]
+    fn test_parse_postfix_factorial() {
+        let tokens = spaced(vec![
+            op(TokenType::Negation, "-"),
+            num("3"),
+            op(TokenType::Exclamation, "!"),
+        ]);
+        let (ast, pos) = match parse_expression(&tokens, 0, 0, &mut vec![]) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        assert_eq!(
+            ast,
+            AstNode::UnaryOp {
+                operator: TokenType::Negation,
+                operand: Box::new(AstNode::PostfixOp {
+                    operator: TokenType::Exclamation,
+                    operand: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+                    span: Span::new(2, 5),
+                }),
+                span: Span::new(0, 5),
+            }
+        );
+        assert_eq!(pos, 3);
+    }
+
+    #[test]
+    fn test_parse_postfix_before_exponentiation() {
+        let tokens = spaced(vec![
+            num("3"),
+            op(TokenType::Exclamation, "!"),
+            op(TokenType::Exponentiation, "^"),
+            num("2"),
+        ]);
+        let (ast, pos) = match parse_expression(&tokens, 0, 0, &mut vec![]) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        assert_eq!(
+            ast,
+            AstNode::BinaryOp {
+                operator: TokenType::Exponentiation,
+                operand_1: Box::new(AstNode::PostfixOp {
+                    operator: TokenType::Exclamation,
+                    operand: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+                    span: Span::new(0, 3),
+                }),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                span: Span::new(0, 7),
+            }
+        );
+        assert_eq!(pos, 4);
+    }
+
+    #[test]
+    fn test_parse_conditional() {
+        let tokens = spaced(vec![
+            num("1"),
+            op(TokenType::LessThan, "<"),
+            num("2"),
+            op(TokenType::Question, "?"),
+            num("3"),
+            op(TokenType::Colon, ":"),
+            num("4"),
+            op(TokenType::Addition, "+"),
+            num("5"),
+        ]);
+        let (ast, pos) = match parse_expression(&tokens, 0, 0, &mut vec![]) {
             Ok(result) => result,
             Err(error) => panic!("ParseError: {:?}", error),
         };
+        assert_eq!(
+            ast,
+            AstNode::Conditional {
+                condition: Box::new(AstNode::BinaryOp {
+                    operator: TokenType::LessThan,
+                    operand_1: Box::new(AstNode::Number(BigRational::from_integer(1.into()))),
+                    operand_2: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                    span: Span::new(0, 5),
+                }),
+                if_true: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+                if_false: Box::new(AstNode::BinaryOp {
+                    operator: TokenType::Addition,
+                    operand_1: Box::new(AstNode::Number(BigRational::from_integer(4.into()))),
+                    operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+                    span: Span::new(12, 17),
+                }),
+            }
+        );
+        assert_eq!(pos, 9);
+    }
+
+    #[test]
+    fn test_parse_conditional_missing_colon() {
+        let tokens = spaced(vec![
+            num("1"),
+            op(TokenType::Question, "?"),
+            num("2"),
+        ]);
+        let result = parse_expression(&tokens, 0, 0, &mut vec![]);
+        assert_eq!(result, Err(ParseError::MissingColon(Span::new(5, 5))));
     }
 
     #[test]
     fn test_parse_primary_error() {
-        let tokens = vec![];
-        let result = parse_primary(&tokens, 0);
+        let tokens = spaced(vec![]);
+        let result = parse_primary(&tokens, 0, &mut vec![]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_decimal_exact() {
+        assert_eq!(parse_decimal("0.1"), Some(BigRational::new(1.into(), 10.into())));
+        assert_eq!(parse_decimal("2.50"), Some(BigRational::new(5.into(), 2.into())));
+        assert_eq!(parse_decimal("7"), Some(BigRational::from_integer(7.into())));
+        assert_eq!(parse_decimal("."), None);
+        assert_eq!(parse_decimal("1.5e-9"), Some(BigRational::new(15.into(), 10_000_000_000u64.into())));
+        assert_eq!(parse_decimal("6.022E23"), Some(BigRational::from_integer(BigInt::from(6022) * BigInt::from(10).pow(20))));
+        assert_eq!(parse_decimal("1e999999"), None);
+    }
 }
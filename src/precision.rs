@@ -0,0 +1,450 @@
+// Arbitrary-precision versions of the operations that would otherwise fall back to floats.
+//
+// Every function takes the number of significant digits wanted, works with GUARD_DIGITS more
+// than that to absorb rounding, and rounds its result at the end. The series themselves run
+// on fixed point integers, which hold a value multiplied by `one` (a power of ten).
+use bigdecimal::{BigDecimal, Context, RoundingMode};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::num::NonZeroU64;
+
+pub const GUARD_DIGITS: u64 = 10;
+
+// Rounds half to even on both sides of zero, which `BigDecimal::with_prec` does not
+pub trait Rounding {
+    fn round_to(&self, digits: u64) -> BigDecimal;
+}
+
+impl Rounding for BigDecimal {
+    fn round_to(&self, digits: u64) -> BigDecimal {
+        let precision = NonZeroU64::new(digits.max(1)).unwrap_or(NonZeroU64::MIN);
+        return self.with_precision_round(precision, RoundingMode::HalfEven);
+    }
+}
+
+// Reads a fraction as a decimal, rounded to the given number of significant digits
+pub fn from_rational(value: &BigRational, digits: u64) -> BigDecimal {
+    let numerator = BigDecimal::new(value.numer().clone(), 0);
+    let denominator = BigDecimal::new(value.denom().clone(), 0);
+    return divide(&numerator, &denominator, digits);
+}
+
+pub fn divide(a: &BigDecimal, b: &BigDecimal, digits: u64) -> BigDecimal {
+    let (a_digits, a_scale) = a.as_bigint_and_exponent();
+    let (b_digits, b_scale) = b.as_bigint_and_exponent();
+    // Enough extra places in the numerator for the quotient to have all the digits wanted
+    let places = (digits + GUARD_DIGITS + count_digits(&b_digits)).saturating_sub(count_digits(&a_digits));
+    let quotient = a_digits * BigInt::from(10).pow(places as u32) / b_digits;
+    return BigDecimal::new(quotient, a_scale - b_scale + places as i64).round_to(digits);
+}
+
+pub fn pi(digits: u64) -> BigDecimal {
+    let scale = digits + GUARD_DIGITS;
+    return from_fixed(pi_fixed(&ten_to(scale)), scale).round_to(digits);
+}
+
+// x^y, by repeated squaring for whole y, and otherwise as e^(y ln x) for a positive x
+pub fn power(x: &BigDecimal, y: &BigDecimal, digits: u64) -> BigDecimal {
+    if x.is_zero() {
+        return BigDecimal::zero();
+    }
+    if y.is_integer()
+        && let Some(exponent) = y.to_i64()
+    {
+        return x.powi_with_context(exponent, &context(digits + GUARD_DIGITS)).round_to(digits);
+    }
+    let logarithm = ln(x, digits + magnitude(x) + magnitude(y));
+    return exp(&(y * logarithm), digits);
+}
+
+pub fn sqrt(x: &BigDecimal, digits: u64) -> BigDecimal {
+    match x.sqrt_with_context(&context(digits + GUARD_DIGITS)) {
+        Some(root) => root.round_to(digits),
+        None => BigDecimal::zero(),
+    }
+}
+
+pub fn cbrt(x: &BigDecimal, digits: u64) -> BigDecimal {
+    return x.cbrt_with_context(&context(digits + GUARD_DIGITS)).round_to(digits);
+}
+
+pub fn exp(x: &BigDecimal, digits: u64) -> BigDecimal {
+    // e^-x is computed as 1/e^x, keeping tiny results from vanishing in the fixed point
+    if x.is_negative() {
+        let positive = exp(&-x, digits + GUARD_DIGITS);
+        return divide(&BigDecimal::one(), &positive, digits);
+    }
+    let scale = digits + GUARD_DIGITS + magnitude(x);
+    let one = ten_to(scale);
+    return from_fixed(exp_fixed(&to_fixed(x, scale), &one), scale).round_to(digits);
+}
+
+// The natural logarithm of a positive x, written as m * 10^e so that ln x = ln m + e ln 10
+pub fn ln(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let (mantissa, exponent) = x.as_bigint_and_exponent();
+    let mantissa_digits = count_digits(&mantissa) as i64;
+    let power_of_ten = mantissa_digits - 1 - exponent;
+    let scale = digits + GUARD_DIGITS + count_digits(&BigInt::from(power_of_ten));
+
+    let one = ten_to(scale);
+    let m = to_fixed(&BigDecimal::new(mantissa, mantissa_digits - 1), scale);
+    let result = ln_fixed(&m, &one) + ln_fixed(&(BigInt::from(10) * &one), &one) * power_of_ten;
+    return from_fixed(result, scale).round_to(digits);
+}
+
+// Trigonometric functions take radians
+pub fn sin(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let scale = digits + GUARD_DIGITS + magnitude(x);
+    let one = ten_to(scale);
+    let (sine, _) = sin_cos_fixed(&to_fixed(x, scale), &one);
+    return from_fixed(sine, scale).round_to(digits);
+}
+
+pub fn cos(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let scale = digits + GUARD_DIGITS + magnitude(x);
+    let one = ten_to(scale);
+    let (_, cosine) = sin_cos_fixed(&to_fixed(x, scale), &one);
+    return from_fixed(cosine, scale).round_to(digits);
+}
+
+pub fn atan(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let scale = digits + GUARD_DIGITS + magnitude(x);
+    let one = ten_to(scale);
+    return from_fixed(atan_fixed(&to_fixed(x, scale), &one), scale).round_to(digits);
+}
+
+// The angle of the point (x, y), between -pi and pi
+pub fn atan2(y: &BigDecimal, x: &BigDecimal, digits: u64) -> BigDecimal {
+    let half_pi = pi(digits + GUARD_DIGITS).half();
+    if x.is_zero() {
+        if y.is_zero() {
+            return BigDecimal::zero();
+        }
+        let angle = if y.is_negative() { -half_pi } else { half_pi };
+        return angle.round_to(digits);
+    }
+
+    let angle = atan(&divide(y, x, digits + GUARD_DIGITS), digits + GUARD_DIGITS);
+    if x.is_positive() {
+        return angle.round_to(digits);
+    }
+    let whole_pi = pi(digits + GUARD_DIGITS);
+    if y.is_negative() {
+        return (angle - whole_pi).round_to(digits);
+    }
+    return (angle + whole_pi).round_to(digits);
+}
+
+// Inverse trigonometric functions return radians, and take an x in [-1, 1]
+pub fn asin(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let work = digits + GUARD_DIGITS + magnitude(x);
+    if x.abs().is_one() {
+        let half_pi = pi(work).half();
+        let angle = if x.is_negative() { -half_pi } else { half_pi };
+        return angle.round_to(digits);
+    }
+    let cosine = sqrt(&(BigDecimal::one() - x.square()), work);
+    return atan(&divide(x, &cosine, work), digits);
+}
+
+pub fn acos(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let work = digits + GUARD_DIGITS;
+    return (pi(work).half() - asin(x, work)).round_to(digits);
+}
+
+// Hyperbolic functions, from e^x and e^-x. Small arguments get more digits, since the two
+// nearly cancel out in sinh and tanh.
+pub fn sinh(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let work = digits + GUARD_DIGITS + magnitude(x);
+    let (positive, negative) = exp_pair(x, work);
+    return (positive - negative).half().round_to(digits);
+}
+
+pub fn cosh(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let work = digits + GUARD_DIGITS;
+    let (positive, negative) = exp_pair(x, work);
+    return (positive + negative).half().round_to(digits);
+}
+
+pub fn tanh(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let work = digits + GUARD_DIGITS + magnitude(x);
+    let (positive, negative) = exp_pair(x, work);
+    return divide(&(&positive - &negative), &(positive + negative), digits);
+}
+
+// asinh x = ln(x + sqrt(x^2 + 1)), using asinh -x = -asinh x to avoid cancelling
+pub fn asinh(x: &BigDecimal, digits: u64) -> BigDecimal {
+    if x.is_negative() {
+        return -asinh(&-x, digits);
+    }
+    let work = digits + GUARD_DIGITS + magnitude(x);
+    let root = sqrt(&(x.square() + BigDecimal::one()), work);
+    return ln(&(x + root), digits);
+}
+
+// acosh x = ln(x + sqrt(x^2 - 1)), for x >= 1
+pub fn acosh(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let work = digits + GUARD_DIGITS + magnitude(x);
+    let root = sqrt(&(x.square() - BigDecimal::one()), work);
+    return ln(&(x + root), digits);
+}
+
+// atanh x = ln((1 + x) / (1 - x)) / 2, for -1 < x < 1
+pub fn atanh(x: &BigDecimal, digits: u64) -> BigDecimal {
+    let work = digits + GUARD_DIGITS + magnitude(x);
+    let ratio = divide(&(BigDecimal::one() + x), &(BigDecimal::one() - x), work);
+    return ln(&ratio, work).half().round_to(digits);
+}
+
+fn exp_pair(x: &BigDecimal, digits: u64) -> (BigDecimal, BigDecimal) {
+    let positive = exp(x, digits);
+    let negative = divide(&BigDecimal::one(), &positive, digits);
+    return (positive, negative);
+}
+
+// Whether x lies within the last few of the given digits of a multiple of pi
+pub fn is_multiple_of_pi(x: &BigDecimal, digits: u64) -> bool {
+    let whole_pi = pi(digits + GUARD_DIGITS + magnitude(x));
+    let turns = divide(x, &whole_pi, digits + GUARD_DIGITS).round(0);
+    let remainder = x - turns * whole_pi;
+    let tolerance = BigDecimal::new(BigInt::one(), digits as i64 - 1);
+    return remainder.abs() < tolerance;
+}
+
+// Digits needed beyond the precision so that both very large and very small numbers keep
+// their significant digits in the fixed point
+fn magnitude(x: &BigDecimal) -> u64 {
+    if x.is_zero() {
+        return 0;
+    }
+    return x.order_of_magnitude().unsigned_abs() + 1;
+}
+
+fn context(digits: u64) -> Context {
+    let precision = NonZeroU64::new(digits.max(1)).unwrap_or(NonZeroU64::MIN);
+    return Context::new(precision, RoundingMode::HalfEven);
+}
+
+fn count_digits(value: &BigInt) -> u64 {
+    if value.is_zero() {
+        return 1;
+    }
+    return value.abs().to_string().len() as u64;
+}
+
+fn ten_to(power: u64) -> BigInt {
+    return BigInt::from(10).pow(power as u32);
+}
+
+fn to_fixed(x: &BigDecimal, scale: u64) -> BigInt {
+    let (digits, _) = x.with_scale_round(scale as i64, RoundingMode::HalfEven).into_bigint_and_exponent();
+    return digits;
+}
+
+fn from_fixed(x: BigInt, scale: u64) -> BigDecimal {
+    return BigDecimal::new(x, scale as i64);
+}
+
+// Machin's formula: pi = 16 atan(1/5) - 4 atan(1/239)
+fn pi_fixed(one: &BigInt) -> BigInt {
+    return atan_of_inverse(5, one) * 16 - atan_of_inverse(239, one) * 4;
+}
+
+// atan(1/n) = 1/n - 1/(3n^3) + 1/(5n^5) - ...
+fn atan_of_inverse(n: u32, one: &BigInt) -> BigInt {
+    let n_squared = BigInt::from(n * n);
+    let mut power = one / n;
+    let mut sum = power.clone();
+    let mut k: u32 = 1;
+    loop {
+        power /= &n_squared;
+        let term = &power / (2 * k + 1);
+        if term.is_zero() {
+            break;
+        }
+        if k % 2 == 1 {
+            sum -= term;
+        } else {
+            sum += term;
+        }
+        k += 1;
+    }
+    return sum;
+}
+
+// e^x, halving x until the Taylor series converges quickly and squaring the result back up
+fn exp_fixed(x: &BigInt, one: &BigInt) -> BigInt {
+    let mut reduced = x.clone();
+    let mut halvings = 0;
+    let limit = one / 16;
+    while reduced.abs() > limit {
+        reduced /= 2;
+        halvings += 1;
+    }
+
+    let mut sum = one.clone();
+    let mut term = one.clone();
+    let mut k: u32 = 1;
+    loop {
+        term = &term * &reduced / one / k;
+        if term.is_zero() {
+            break;
+        }
+        sum += &term;
+        k += 1;
+    }
+
+    for _ in 0..halvings {
+        sum = &sum * &sum / one;
+    }
+    return sum;
+}
+
+// ln x for a positive x, as k ln 2 + ln(x / 2^k) with x / 2^k close to 1
+fn ln_fixed(x: &BigInt, one: &BigInt) -> BigInt {
+    let mut reduced = x.clone();
+    let mut halvings: i64 = 0;
+    let upper = one * 3 / 2;
+    let lower = one * 3 / 4;
+    while reduced > upper {
+        reduced /= 2;
+        halvings += 1;
+    }
+    while reduced < lower {
+        reduced *= 2;
+        halvings -= 1;
+    }
+
+    let ln_two = atanh_series(&(one / 3), one) * 2;
+    let ratio = (&reduced - one) * one / (&reduced + one);
+    return atanh_series(&ratio, one) * 2 + ln_two * halvings;
+}
+
+// atanh y = y + y^3/3 + y^5/5 + ..., which converges for |y| < 1
+fn atanh_series(y: &BigInt, one: &BigInt) -> BigInt {
+    let y_squared = y * y / one;
+    let mut power = y.clone();
+    let mut sum = y.clone();
+    let mut k: u32 = 1;
+    loop {
+        power = &power * &y_squared / one;
+        let term = &power / (2 * k + 1);
+        if term.is_zero() {
+            break;
+        }
+        sum += term;
+        k += 1;
+    }
+    return sum;
+}
+
+// Both Taylor series at once, after bringing x into [-pi, pi]
+fn sin_cos_fixed(x: &BigInt, one: &BigInt) -> (BigInt, BigInt) {
+    let whole_pi = pi_fixed(one);
+    let two_pi = &whole_pi * 2;
+    let mut reduced: BigInt = x - (x / &two_pi) * &two_pi;
+    if reduced > whole_pi {
+        reduced -= &two_pi;
+    } else if reduced < -&whole_pi {
+        reduced += &two_pi;
+    }
+    let x_squared = &reduced * &reduced / one;
+
+    let mut sine = reduced.clone();
+    let mut term = reduced;
+    let mut k: u32 = 1;
+    loop {
+        term = -(&term * &x_squared / one) / ((2 * k) * (2 * k + 1));
+        if term.is_zero() {
+            break;
+        }
+        sine += &term;
+        k += 1;
+    }
+
+    let mut cosine = one.clone();
+    let mut term = one.clone();
+    let mut k: u32 = 1;
+    loop {
+        term = -(&term * &x_squared / one) / ((2 * k - 1) * (2 * k));
+        if term.is_zero() {
+            break;
+        }
+        cosine += &term;
+        k += 1;
+    }
+    return (sine, cosine);
+}
+
+// atan x, using atan x = pi/2 - atan(1/x) for large x and halving the angle twice with
+// atan x = 2 atan(x / (1 + sqrt(1 + x^2))) so that the series converges quickly
+fn atan_fixed(x: &BigInt, one: &BigInt) -> BigInt {
+    if x.is_negative() {
+        return -atan_fixed(&-x, one);
+    }
+    if x > one {
+        let half_pi = pi_fixed(one) / 2;
+        return half_pi - atan_fixed(&(one * one / x), one);
+    }
+
+    let mut reduced = x.clone();
+    for _ in 0..2 {
+        let root = (one * one + &reduced * &reduced).sqrt();
+        reduced = &reduced * one / (one + root);
+    }
+
+    let x_squared = &reduced * &reduced / one;
+    let mut power = reduced.clone();
+    let mut sum = reduced;
+    let mut k: u32 = 1;
+    loop {
+        power = &power * &x_squared / one;
+        let term = &power / (2 * k + 1);
+        if term.is_zero() {
+            break;
+        }
+        if k % 2 == 1 {
+            sum -= term;
+        } else {
+            sum += term;
+        }
+        k += 1;
+    }
+    return sum * 4;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_pi() {
+        assert_eq!(pi(30), decimal("3.14159265358979323846264338328"));
+    }
+
+    #[test]
+    fn test_exp_and_ln() {
+        assert_eq!(exp(&decimal("1"), 30), decimal("2.71828182845904523536028747135"));
+        assert_eq!(ln(&decimal("2"), 30), decimal("0.693147180559945309417232121458"));
+        assert_eq!(ln(&decimal("0.001"), 20), decimal("-6.9077552789821370521"));
+    }
+
+    #[test]
+    fn test_trigonometry() {
+        assert_eq!(sin(&decimal("1"), 30), decimal("0.841470984807896506652502321630"));
+        assert_eq!(cos(&decimal("-4"), 20), decimal("-0.65364362086361191464"));
+        assert_eq!(atan(&decimal("1"), 30), pi(31).half().half().round_to(30));
+    }
+
+    #[test]
+    fn test_divide() {
+        assert_eq!(divide(&decimal("1"), &decimal("3"), 10), decimal("0.3333333333"));
+        assert_eq!(divide(&decimal("-22"), &decimal("7"), 5), decimal("-3.1429"));
+    }
+}
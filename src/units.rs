@@ -0,0 +1,361 @@
+use crate::errors::EvaluationError;
+use crate::evaluator::{AngleMode, Settings};
+use crate::lexer::TokenType;
+use crate::value::{Value, MAX_EXACT_POWER_BITS};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::One;
+use std::fmt;
+
+// The powers of the seven SI base quantities: length, mass, time, electric current,
+// temperature, amount of substance and luminous intensity
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Dimension([i32; 7]);
+
+const BASE_SYMBOLS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0; 7]);
+
+    const fn new(length: i32, mass: i32, time: i32, current: i32) -> Dimension {
+        Dimension([length, mass, time, current, 0, 0, 0])
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Dimension::NONE
+    }
+
+    pub fn multiply(&self, other: &Dimension) -> Dimension {
+        let mut powers = self.0;
+        for (power, other) in powers.iter_mut().zip(other.0) {
+            *power += other;
+        }
+        Dimension(powers)
+    }
+
+    pub fn power(&self, exponent: i32) -> Dimension {
+        Dimension(self.0.map(|power| power * exponent))
+    }
+}
+
+// Written in SI base units, as in `kg m s^-2`
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_dimensionless() {
+            return write!(f, "a plain number");
+        }
+        let terms: Vec<(String, i32)> = BASE_SYMBOLS.iter()
+            .zip(self.0)
+            .filter(|(_, power)| *power != 0)
+            .map(|(symbol, power)| (symbol.to_string(), power))
+            .collect();
+        write!(f, "{}", format_terms(&terms, false))
+    }
+}
+
+struct UnitDefinition {
+    symbol: &'static str,
+    // The size of the unit in SI base units, as a numerator and denominator so that it is exact
+    factor: (i64, i64),
+    dimension: Dimension,
+    // Whether the unit takes metric prefixes, as in `km` or `ms`
+    prefixable: bool,
+}
+
+const LENGTH: Dimension = Dimension::new(1, 0, 0, 0);
+const MASS: Dimension = Dimension::new(0, 1, 0, 0);
+const TIME: Dimension = Dimension::new(0, 0, 1, 0);
+const VOLUME: Dimension = Dimension::new(3, 0, 0, 0);
+const SPEED: Dimension = Dimension::new(1, 0, -1, 0);
+const FORCE: Dimension = Dimension::new(1, 1, -2, 0);
+const PRESSURE: Dimension = Dimension::new(-1, 1, -2, 0);
+
+const UNITS: [UnitDefinition; 37] = [
+    // SI base units, with the gram in place of the kilogram so that it takes prefixes
+    UnitDefinition { symbol: "m", factor: (1, 1), dimension: LENGTH, prefixable: true },
+    UnitDefinition { symbol: "g", factor: (1, 1000), dimension: MASS, prefixable: true },
+    UnitDefinition { symbol: "s", factor: (1, 1), dimension: TIME, prefixable: true },
+    UnitDefinition { symbol: "A", factor: (1, 1), dimension: Dimension::new(0, 0, 0, 1), prefixable: true },
+    UnitDefinition { symbol: "K", factor: (1, 1), dimension: Dimension([0, 0, 0, 0, 1, 0, 0]), prefixable: true },
+    UnitDefinition { symbol: "mol", factor: (1, 1), dimension: Dimension([0, 0, 0, 0, 0, 1, 0]), prefixable: true },
+    UnitDefinition { symbol: "cd", factor: (1, 1), dimension: Dimension([0, 0, 0, 0, 0, 0, 1]), prefixable: true },
+
+    // SI derived units
+    UnitDefinition { symbol: "Hz", factor: (1, 1), dimension: Dimension::new(0, 0, -1, 0), prefixable: true },
+    UnitDefinition { symbol: "N", factor: (1, 1), dimension: FORCE, prefixable: true },
+    UnitDefinition { symbol: "Pa", factor: (1, 1), dimension: PRESSURE, prefixable: true },
+    UnitDefinition { symbol: "J", factor: (1, 1), dimension: Dimension::new(2, 1, -2, 0), prefixable: true },
+    UnitDefinition { symbol: "W", factor: (1, 1), dimension: Dimension::new(2, 1, -3, 0), prefixable: true },
+    UnitDefinition { symbol: "C", factor: (1, 1), dimension: Dimension::new(0, 0, 1, 1), prefixable: true },
+    UnitDefinition { symbol: "V", factor: (1, 1), dimension: Dimension::new(2, 1, -3, -1), prefixable: true },
+    UnitDefinition { symbol: "ohm", factor: (1, 1), dimension: Dimension::new(2, 1, -3, -2), prefixable: true },
+    UnitDefinition { symbol: "Ω", factor: (1, 1), dimension: Dimension::new(2, 1, -3, -2), prefixable: true },
+    UnitDefinition { symbol: "F", factor: (1, 1), dimension: Dimension::new(-2, -1, 4, 2), prefixable: true },
+    UnitDefinition { symbol: "Wb", factor: (1, 1), dimension: Dimension::new(2, 1, -2, -1), prefixable: true },
+    UnitDefinition { symbol: "T", factor: (1, 1), dimension: Dimension::new(0, 1, -2, -1), prefixable: true },
+    UnitDefinition { symbol: "H", factor: (1, 1), dimension: Dimension::new(2, 1, -2, -2), prefixable: true },
+    UnitDefinition { symbol: "L", factor: (1, 1000), dimension: VOLUME, prefixable: true },
+
+    // Other units in common use
+    UnitDefinition { symbol: "min", factor: (60, 1), dimension: TIME, prefixable: false },
+    UnitDefinition { symbol: "h", factor: (3600, 1), dimension: TIME, prefixable: false },
+    UnitDefinition { symbol: "day", factor: (86400, 1), dimension: TIME, prefixable: false },
+    UnitDefinition { symbol: "t", factor: (1000, 1), dimension: MASS, prefixable: false },
+    UnitDefinition { symbol: "bar", factor: (100_000, 1), dimension: PRESSURE, prefixable: false },
+
+    // Imperial and US customary units. Inches are `inch`, as `in` converts between units.
+    UnitDefinition { symbol: "inch", factor: (254, 10_000), dimension: LENGTH, prefixable: false },
+    UnitDefinition { symbol: "ft", factor: (3048, 10_000), dimension: LENGTH, prefixable: false },
+    UnitDefinition { symbol: "yd", factor: (9144, 10_000), dimension: LENGTH, prefixable: false },
+    UnitDefinition { symbol: "mi", factor: (1_609_344, 1000), dimension: LENGTH, prefixable: false },
+    UnitDefinition { symbol: "lb", factor: (45_359_237, 100_000_000), dimension: MASS, prefixable: false },
+    UnitDefinition { symbol: "oz", factor: (28_349_523_125, 1_000_000_000_000), dimension: MASS, prefixable: false },
+    UnitDefinition { symbol: "mph", factor: (44_704, 100_000), dimension: SPEED, prefixable: false },
+    UnitDefinition { symbol: "gal", factor: (3_785_411_784, 1_000_000_000_000), dimension: VOLUME, prefixable: false },
+    UnitDefinition { symbol: "lbf", factor: (44_482_216_152_605, 10_000_000_000_000), dimension: FORCE, prefixable: false },
+    // A pound-force per square inch
+    UnitDefinition { symbol: "psi", factor: (44_482_216_152_605, 6_451_600_000), dimension: PRESSURE, prefixable: false },
+    UnitDefinition { symbol: "knot", factor: (1852, 3600), dimension: SPEED, prefixable: false },
+];
+
+// Each prefix with the power of ten it scales by
+const PREFIXES: [(&str, i32); 20] = [
+    ("Y", 24), ("Z", 21), ("E", 18), ("P", 15), ("T", 12), ("G", 9), ("M", 6),
+    ("k", 3), ("h", 2), ("da", 1), ("d", -1), ("c", -2), ("m", -3), ("µ", -6),
+    ("u", -6), ("n", -9), ("p", -12), ("f", -15), ("a", -18), ("z", -21),
+];
+
+// Looks up a unit by its symbol, with or without a metric prefix. Symbols are matched
+// whole first, so `min` is a minute and `mi` a mile rather than prefixed units.
+fn find_unit(symbol: &str) -> Option<(BigRational, Dimension)> {
+    let factor = |unit: &UnitDefinition| BigRational::new(unit.factor.0.into(), unit.factor.1.into());
+    if let Some(unit) = UNITS.iter().find(|unit| unit.symbol == symbol) {
+        return Some((factor(unit), unit.dimension));
+    }
+    for (prefix, power) in PREFIXES {
+        let base = match symbol.strip_prefix(prefix) {
+            Some(base) => base,
+            None => continue,
+        };
+        if let Some(unit) = UNITS.iter().find(|unit| unit.symbol == base && unit.prefixable) {
+            let scale = BigRational::from_integer(BigInt::from(10)).pow(power);
+            return Some((scale * factor(unit), unit.dimension));
+        }
+    }
+    return None;
+}
+
+pub fn is_unit(symbol: &str) -> bool {
+    find_unit(symbol).is_some()
+}
+
+// A number with a physical dimension, written in a unit that is remembered for display as a list
+// of symbols raised to powers. The magnitude is a number of those units, which stays exact as
+// long as it is written exactly, since the units are exact multiples of the SI base units.
+#[derive(Debug, Clone)]
+pub struct Quantity {
+    magnitude: Value,
+    pub dimension: Dimension,
+    unit: Vec<(String, i32)>,
+    // The size of the unit in SI base units
+    factor: BigRational,
+}
+
+impl Quantity {
+    // One of the given unit, or None if there is no such unit
+    pub fn from_unit(symbol: &str) -> Option<Quantity> {
+        let (factor, dimension) = match find_unit(symbol) {
+            Some(unit) => unit,
+            None => return None,
+        };
+        return Some(Quantity {
+            magnitude: Value::Integer(BigInt::one()),
+            dimension,
+            unit: vec![(symbol.to_string(), 1)],
+            factor,
+        });
+    }
+
+    // A plain number, as a quantity without a dimension
+    pub fn dimensionless(value: Value) -> Quantity {
+        Quantity { magnitude: value, dimension: Dimension::NONE, unit: vec![], factor: BigRational::one() }
+    }
+
+    // The number of units, as in 5 for `5 km`
+    pub fn value(&self) -> &Value {
+        &self.magnitude
+    }
+
+    // The magnitude as a plain number, which it is once the units have cancelled out
+    pub fn into_value(self) -> Value {
+        self.magnitude
+    }
+
+    pub fn unit_name(&self) -> String {
+        format_terms(&self.unit, true)
+    }
+
+    // The same quantity in the unit written out by the given function, like `to_decimal_string`
+    pub fn write(&self, write_magnitude: impl Fn(&Value) -> String) -> String {
+        format!("{} {}", write_magnitude(&self.magnitude), self.unit_name())
+    }
+
+    pub fn negate(&self) -> Result<Quantity, EvaluationError> {
+        match self.magnitude.clone().apply_unary(&TokenType::Negation) {
+            Ok(magnitude) => Ok(Quantity { magnitude, ..self.clone() }),
+            Err(error) => Err(error),
+        }
+    }
+
+    // Sums and differences are written in the unit of the left operand
+    pub fn add(&self, other: &Quantity, operator: &TokenType, settings: Settings) -> Result<Quantity, EvaluationError> {
+        let other = match other.convert(self, settings) {
+            Ok(other) => other,
+            Err(error) => return Err(error),
+        };
+        match self.magnitude.clone().apply_binary(operator, other.magnitude, settings) {
+            Ok(magnitude) => Ok(Quantity { magnitude, ..self.clone() }),
+            Err(error) => Err(error),
+        }
+    }
+
+    // Compares the other quantity with this one once it is in the same unit
+    pub fn compare(&self, other: &Quantity, operator: &TokenType, settings: Settings) -> Result<Value, EvaluationError> {
+        match other.convert(self, settings) {
+            Ok(other) => self.magnitude.clone().apply_binary(operator, other.magnitude, settings),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn multiply(&self, other: &Quantity, settings: Settings) -> Result<Quantity, EvaluationError> {
+        let mut unit = self.unit.clone();
+        for (symbol, power) in &other.unit {
+            match unit.iter_mut().find(|(existing, _)| existing == symbol) {
+                Some((_, existing)) => *existing += power,
+                None => unit.push((symbol.clone(), *power)),
+            }
+        }
+        unit.retain(|(_, power)| *power != 0);
+        let magnitude = match self.magnitude.clone().apply_binary(&TokenType::Multiplication, other.magnitude.clone(), settings) {
+            Ok(magnitude) => magnitude,
+            Err(error) => return Err(error),
+        };
+        let result = Quantity {
+            magnitude,
+            dimension: self.dimension.multiply(&other.dimension),
+            unit,
+            factor: &self.factor * &other.factor,
+        };
+        return result.without_cancelled_units(settings);
+    }
+
+    pub fn power(&self, exponent: i32, settings: Settings) -> Result<Quantity, EvaluationError> {
+        let size = (self.factor.numer().bits() + self.factor.denom().bits()) * exponent.unsigned_abs() as u64;
+        if size > MAX_EXACT_POWER_BITS {
+            return Err(EvaluationError::ResultTooLarge);
+        }
+        let magnitude = match self.magnitude.clone().apply_binary(&TokenType::Exponentiation, Value::Integer(exponent.into()), settings) {
+            Ok(magnitude) => magnitude,
+            Err(error) => return Err(error),
+        };
+        let result = Quantity {
+            magnitude,
+            dimension: self.dimension.power(exponent),
+            unit: self.unit.iter().map(|(symbol, power)| (symbol.clone(), power * exponent)).collect(),
+            factor: self.factor.pow(exponent),
+        };
+        return result.without_cancelled_units(settings);
+    }
+
+    // The same quantity written in the unit of the target, which must have the same dimension
+    pub fn convert(&self, target: &Quantity, settings: Settings) -> Result<Quantity, EvaluationError> {
+        match target.check_dimension(self) {
+            Some(error) => return Err(error),
+            None => (),
+        }
+        let scale = Value::from_rational(&self.factor / &target.factor);
+        match self.magnitude.clone().apply_binary(&TokenType::Multiplication, scale, settings) {
+            Ok(magnitude) => Ok(Quantity { magnitude, ..target.clone() }),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn check_dimension(&self, other: &Quantity) -> Option<EvaluationError> {
+        if self.dimension != other.dimension {
+            return Some(EvaluationError::DimensionMismatch {
+                expected: self.dimension.to_string(),
+                found: other.dimension.to_string(),
+            });
+        }
+        return None;
+    }
+
+    // Units that cancel out to a plain number, like `km/m`, are folded into the magnitude
+    fn without_cancelled_units(self, settings: Settings) -> Result<Quantity, EvaluationError> {
+        if !self.dimension.is_dimensionless() {
+            return Ok(self);
+        }
+        match self.magnitude.apply_binary(&TokenType::Multiplication, Value::from_rational(self.factor), settings) {
+            Ok(magnitude) => Ok(Quantity::dimensionless(magnitude)),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+// Quantities are equal when they are the same amount, whatever units they are written in
+impl PartialEq for Quantity {
+    fn eq(&self, other: &Quantity) -> bool {
+        let settings = Settings { angle_mode: AngleMode::Radians, precision: None, word_size: None };
+        match other.convert(self, settings) {
+            Ok(other) => self.magnitude == other.magnitude,
+            Err(_) => false,
+        }
+    }
+}
+
+// Writes symbols with their powers, as in `m s^-2`, or `m/s^2` when dividing
+fn format_terms(terms: &[(String, i32)], as_fraction: bool) -> String {
+    let write = |terms: Vec<(&String, i32)>| -> String {
+        terms.iter()
+            .map(|(symbol, power)| if *power == 1 { symbol.to_string() } else { format!("{}^{}", symbol, power) })
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+
+    let numerator: Vec<(&String, i32)> = terms.iter().filter(|(_, power)| *power > 0).map(|(symbol, power)| (symbol, *power)).collect();
+    let denominator: Vec<(&String, i32)> = terms.iter().filter(|(_, power)| *power < 0).map(|(symbol, power)| (symbol, -power)).collect();
+    if !as_fraction || numerator.is_empty() || denominator.is_empty() {
+        return write(terms.iter().map(|(symbol, power)| (symbol, *power)).collect());
+    }
+    if denominator.len() == 1 {
+        return format!("{}/{}", write(numerator), write(denominator));
+    }
+    return format!("{}/({})", write(numerator), write(denominator));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: Settings = Settings { angle_mode: AngleMode::Radians, precision: None, word_size: None };
+
+    #[test]
+    fn test_find_unit_with_prefix() {
+        let (factor, dimension) = find_unit("km").unwrap();
+        assert_eq!(factor, BigRational::from_integer(1000.into()));
+        assert_eq!(dimension, LENGTH);
+        assert_eq!(find_unit("min").unwrap().0, BigRational::from_integer(60.into()));
+        assert_eq!(find_unit("mm").unwrap().0, BigRational::new(1.into(), 1000.into()));
+        assert!(find_unit("kmin").is_none());
+        assert!(find_unit("x").is_none());
+    }
+
+    #[test]
+    fn test_unit_name() {
+        let hours = Quantity::from_unit("h").unwrap().power(-1, SETTINGS).unwrap();
+        let speed = Quantity::from_unit("km").unwrap().multiply(&hours, SETTINGS).unwrap();
+        assert_eq!(speed.unit_name(), "km/h");
+        let frequency = Quantity::from_unit("s").unwrap().power(-1, SETTINGS).unwrap();
+        assert_eq!(frequency.unit_name(), "s^-1");
+    }
+}
@@ -0,0 +1,1186 @@
+use crate::errors::EvaluationError;
+use crate::evaluator::{AngleMode, Lambda, Settings, WordSize};
+use crate::lexer::TokenType;
+use crate::matrix::Matrix;
+use crate::precision::{self, Rounding};
+use crate::units::Quantity;
+use bigdecimal::BigDecimal;
+use libm::tgamma;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+use std::fmt;
+use std::rc::Rc;
+
+// Numbers are kept as exact integers and fractions for as long as possible, and become floats
+// once an operation can no longer be represented exactly, like `sqrt(2)` or `sin(1)`
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(f64),
+    Integer(BigInt),
+    // Never a whole number, see `Value::from_rational`
+    Rational(BigRational),
+    // Used in place of floats when a precision has been set
+    Decimal(BigDecimal),
+    // Always has a non-zero imaginary part, see `Value::from_complex`
+    Complex(Complex64),
+    // Never dimensionless, see `Value::from_quantity`. Boxed, as its magnitude is a value.
+    Quantity(Box<Quantity>),
+    Boolean(bool),
+    // A function written as a lambda, which can be stored and passed around like a number
+    Lambda(Rc<Lambda>),
+    // Operators and functions on lists apply to each element, see `list_binary`
+    List(Vec<Value>),
+    // Never empty, see `Value::from_list`
+    Matrix(Matrix),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &str {
+        match self {
+            Value::Number(_) | Value::Integer(_) | Value::Rational(_) | Value::Decimal(_) => "number",
+            Value::Complex(_) => "complex number",
+            Value::Quantity(_) => "quantity",
+            Value::Boolean(_) => "boolean",
+            Value::Lambda(_) => "function",
+            Value::List(_) => "list",
+            Value::Matrix(_) => "matrix",
+        }
+    }
+
+    pub fn as_number(&self) -> Result<f64, EvaluationError> {
+        match self {
+            Value::Number(value) => Ok(*value),
+            Value::Integer(value) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            Value::Rational(value) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            Value::Decimal(value) => Ok(value.to_f64().unwrap_or(f64::NAN)),
+            Value::Complex(_) => Err(self.mismatch("real number")),
+            Value::Quantity(_) | Value::Boolean(_) | Value::Lambda(_) | Value::List(_) | Value::Matrix(_) => {
+                Err(self.mismatch("number"))
+            }
+        }
+    }
+
+    // Any number as a complex one, or None for booleans
+    pub fn as_complex(&self) -> Option<Complex64> {
+        match self {
+            Value::Complex(value) => Some(*value),
+            Value::Boolean(_) => None,
+            _ => match self.as_number() {
+                Ok(value) => Some(Complex64::new(value, 0.0)),
+                Err(_) => None,
+            },
+        }
+    }
+
+    // Real numbers as quantities without a dimension, or None for anything else
+    pub fn as_quantity(&self) -> Option<Quantity> {
+        match self {
+            Value::Quantity(value) => Some((**value).clone()),
+            Value::Complex(_) | Value::Boolean(_) => None,
+            Value::Number(_) | Value::Integer(_) | Value::Rational(_) | Value::Decimal(_) => {
+                Some(Quantity::dimensionless(self.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    // Quantities whose units cancel out, like `km/m`, become plain numbers
+    pub fn from_quantity(value: Quantity) -> Value {
+        if value.dimension.is_dimensionless() {
+            return value.into_value();
+        }
+        return Value::Quantity(Box::new(value));
+    }
+
+    // Complex results that land on the real line become real numbers again
+    pub fn from_complex(value: Complex64) -> Value {
+        if value.im == 0.0 {
+            return Value::Number(value.re);
+        }
+        return Value::Complex(value);
+    }
+
+    // Exact numbers as fractions, or None for anything that is not exact
+    pub fn as_rational(&self) -> Option<BigRational> {
+        match self {
+            Value::Integer(value) => Some(BigRational::from_integer(value.clone())),
+            Value::Rational(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    // Whole numbers as integers, for the bitwise operators, or None for anything else
+    pub fn as_integer(&self) -> Option<BigInt> {
+        match self {
+            Value::Integer(value) => Some(value.clone()),
+            Value::Number(value) if value.is_finite() && value.fract() == 0.0 => BigInt::from_f64(*value),
+            Value::Decimal(value) if value.is_integer() => Some(value.with_scale(0).into_bigint_and_exponent().0),
+            _ => None,
+        }
+    }
+
+    // Integers wrapped around into the word, if there is one. Everything else is left as it is.
+    pub fn wrap(self, word_size: Option<WordSize>) -> Value {
+        match (self, word_size) {
+            (Value::Integer(value), Some(word_size)) => Value::Integer(word_size.wrap(&value)),
+            (Value::List(values), Some(_)) => {
+                Value::List(values.into_iter().map(|value| value.wrap(word_size)).collect())
+            }
+            (Value::Matrix(matrix), Some(_)) => match matrix.map(|value| Ok(value.clone().wrap(word_size))) {
+                Ok(matrix) => Value::Matrix(matrix),
+                Err(_) => Value::Matrix(matrix),
+            },
+            (value, _) => value,
+        }
+    }
+
+    // Lists whose elements are all lists of the same length become matrices, with those lists as
+    // their rows, unless any of the rows holds something that cannot be in a matrix
+    pub fn from_list(values: Vec<Value>) -> Value {
+        let columns = match values.first() {
+            Some(Value::List(row)) if !row.is_empty() => row.len(),
+            _ => return Value::List(values),
+        };
+        let is_matrix = values.iter().all(|value| match value {
+            Value::List(row) => {
+                row.len() == columns
+                    && row.iter().all(|element| !matches!(element, Value::Lambda(_) | Value::List(_) | Value::Matrix(_)))
+            }
+            _ => false,
+        });
+        if !is_matrix {
+            return Value::List(values);
+        }
+        let rows: Vec<Vec<Value>> = values
+            .into_iter()
+            .map(|value| match value {
+                Value::List(row) => row,
+                value => vec![value],
+            })
+            .collect();
+        return Value::Matrix(Matrix::new(rows));
+    }
+
+    // Whole fractions become integers, so that later operations can stay on integers
+    pub fn from_rational(value: BigRational) -> Value {
+        if value.is_integer() {
+            return Value::Integer(value.to_integer());
+        }
+        return Value::Rational(value);
+    }
+
+    // Exact and arbitrary-precision numbers as decimals with the given number of digits,
+    // or None for floats, which have already lost their precision
+    pub fn as_decimal(&self, digits: u64) -> Option<BigDecimal> {
+        match self {
+            Value::Integer(value) => Some(BigDecimal::from(value.clone())),
+            Value::Rational(value) => Some(precision::from_rational(value, digits)),
+            Value::Decimal(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    // A condition holds when it is true, or any number other than 0 (NaN is also false)
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(value) => *value != 0.0 && !value.is_nan(),
+            Value::Integer(value) => !value.is_zero(),
+            Value::Rational(value) => !value.is_zero(),
+            Value::Decimal(value) => !value.is_zero(),
+            Value::Complex(value) => !value.is_zero(),
+            Value::Quantity(value) => value.value().is_truthy(),
+            Value::Boolean(value) => *value,
+            Value::Lambda(_) => true,
+            Value::List(values) => !values.is_empty(),
+            Value::Matrix(_) => true,
+        }
+    }
+
+    // Exact results that are not whole numbers are written as `numerator/denominator`
+    pub fn to_fraction_string(&self) -> String {
+        match self {
+            Value::Rational(value) if !value.is_integer() => format!("{}/{}", value.numer(), value.denom()),
+            Value::Quantity(value) => value.write(|value| value.to_fraction_string()),
+            Value::List(values) => write_list(values.iter().map(|value| value.to_fraction_string())),
+            Value::Matrix(matrix) => matrix.write(|value| value.to_fraction_string()),
+            _ => self.to_string(),
+        }
+    }
+
+    // Fractions without a finite decimal are written to the given number of significant digits
+    pub fn to_decimal_string(&self, digits: u64) -> String {
+        match self {
+            Value::Rational(value) if exact_decimal(value).is_none() => {
+                precision::from_rational(value, digits).normalized().to_plain_string()
+            }
+            // Complex numbers are computed with floats, so they are only rounded to the precision
+            // when it is below the digits a float holds
+            Value::Complex(value) => {
+                let digits = digits.min(FLOAT_DIGITS) as usize;
+                let round = |x: f64| -> f64 { format!("{:.*e}", digits - 1, x).parse().unwrap_or(x) };
+                Value::Complex(Complex64::new(round(value.re), round(value.im))).to_string()
+            }
+            Value::Quantity(value) => value.write(|value| value.to_decimal_string(digits)),
+            Value::List(values) => write_list(values.iter().map(|value| value.to_decimal_string(digits))),
+            Value::Matrix(matrix) => matrix.write(|value| value.to_decimal_string(digits)),
+            _ => self.to_string(),
+        }
+    }
+
+    // Complex numbers in polar form as `r ∠ θ`, with the angle in the given mode
+    pub fn to_polar_string(&self, angle_mode: AngleMode) -> String {
+        match self {
+            Value::Complex(value) => format!("{} ∠ {}", value.norm(), angle_mode.from_radians(value.arg())),
+            Value::List(values) => write_list(values.iter().map(|value| value.to_polar_string(angle_mode))),
+            Value::Matrix(matrix) => matrix.write(|value| value.to_polar_string(angle_mode)),
+            _ => self.to_string(),
+        }
+    }
+
+    // Integers in the given base with its prefix, like `0xFF`. Negative integers in a word
+    // are written as their two's complement, the way the word holds them.
+    pub fn to_base_string(&self, base: Base, word_size: Option<WordSize>) -> String {
+        let value = match self {
+            Value::Integer(value) => value,
+            Value::List(values) => {
+                return write_list(values.iter().map(|value| value.to_base_string(base, word_size)));
+            }
+            Value::Matrix(matrix) => return matrix.write(|value| value.to_base_string(base, word_size)),
+            _ => return self.to_string(),
+        };
+        let (radix, prefix) = match base {
+            Base::Binary => (2, "0b"),
+            Base::Octal => (8, "0o"),
+            Base::Decimal => return self.to_string(),
+            Base::Hexadecimal => (16, "0x"),
+        };
+        match word_size {
+            Some(word_size) if value.is_negative() => {
+                let unsigned = value + (BigInt::one() << word_size.bits);
+                return format!("{}{}", prefix, unsigned.to_str_radix(radix).to_uppercase());
+            }
+            _ => (),
+        }
+        let sign = if value.is_negative() { "-" } else { "" };
+        return format!("{}{}{}", sign, prefix, value.abs().to_str_radix(radix).to_uppercase());
+    }
+}
+
+// The base that the programmer mode shows integers in
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Base {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+
+impl Base {
+    pub fn name(&self) -> &str {
+        match self {
+            Base::Binary => "binary",
+            Base::Octal => "octal",
+            Base::Decimal => "decimal",
+            Base::Hexadecimal => "hexadecimal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Base> {
+        match name {
+            "bin" | "binary" => Some(Base::Binary),
+            "oct" | "octal" => Some(Base::Octal),
+            "dec" | "decimal" => Some(Base::Decimal),
+            "hex" | "hexadecimal" => Some(Base::Hexadecimal),
+            _ => None,
+        }
+    }
+}
+
+// The operators. Each one checks the types of its operands, and computes its result in the
+// most exact representation that both operands share. A new type of value adds its own arms
+// here, and the lexer and evaluator stay as they are.
+impl Value {
+    pub fn apply_unary(self, operator: &TokenType) -> Result<Value, EvaluationError> {
+        if let Value::List(values) = self {
+            let mut results: Vec<Value> = Vec::with_capacity(values.len());
+            for value in values {
+                match value.apply_unary(operator) {
+                    Ok(result) => results.push(result),
+                    Err(error) => return Err(error),
+                }
+            }
+            return Ok(Value::List(results));
+        }
+        if let Value::Matrix(matrix) = self {
+            return match matrix.map(|value| value.clone().apply_unary(operator)) {
+                Ok(matrix) => Ok(Value::Matrix(matrix)),
+                Err(error) => Err(error),
+            };
+        }
+        match operator {
+            TokenType::Negation => match self {
+                Value::Number(value) => Ok(Value::Number(-value)),
+                Value::Integer(value) => Ok(Value::Integer(-value)),
+                Value::Rational(value) => Ok(Value::Rational(-value)),
+                Value::Decimal(value) => Ok(Value::Decimal(-value)),
+                Value::Complex(value) => Ok(Value::Complex(-value)),
+                Value::Quantity(value) => match value.negate() {
+                    Ok(value) => Ok(Value::Quantity(Box::new(value))),
+                    Err(error) => Err(error),
+                },
+                Value::Boolean(_) | Value::Lambda(_) | Value::List(_) | Value::Matrix(_) => Err(self.mismatch("number")),
+            },
+            TokenType::Not => Ok(Value::Boolean(!self.is_truthy())),
+            // `~x` is `-x - 1`, the two's complement of x with every bit flipped
+            TokenType::BitwiseNot => match self.as_integer() {
+                Some(value) => Ok(Value::Integer(-value - BigInt::one())),
+                None => Err(self.mismatch("integer")),
+            },
+            _ => return Err(EvaluationError::InvalidOperation),
+        }
+    }
+
+    pub fn apply_postfix(self, operator: &TokenType, settings: Settings) -> Result<Value, EvaluationError> {
+        if let Value::List(values) = self {
+            let mut results: Vec<Value> = Vec::with_capacity(values.len());
+            for value in values {
+                match value.apply_postfix(operator, settings) {
+                    Ok(result) => results.push(result),
+                    Err(error) => return Err(error),
+                }
+            }
+            return Ok(Value::List(results));
+        }
+        if let Value::Matrix(matrix) = self {
+            return match matrix.map(|value| value.clone().apply_postfix(operator, settings)) {
+                Ok(matrix) => Ok(Value::Matrix(matrix)),
+                Err(error) => Err(error),
+            };
+        }
+
+        // An explicit unit converts the angle into the current mode, overriding it
+        if let TokenType::AngleUnit(unit) = operator {
+            if *unit == settings.angle_mode && self.type_name() == "number" {
+                return Ok(self);
+            }
+            // Degrees and gradians are a rational multiple of each other
+            if let Some(angle) = self.as_rational()
+                && let (Some(from), Some(to)) = (unit.full_turn(), settings.angle_mode.full_turn())
+            {
+                return Ok(Value::from_rational(angle * to / from));
+            }
+            if let Some(digits) = settings.precision
+                && let Some(angle) = self.as_decimal(digits + precision::GUARD_DIGITS)
+            {
+                let radians = unit.to_radians_decimal(&angle, digits + precision::GUARD_DIGITS);
+                return Ok(Value::Decimal(settings.angle_mode.from_radians_decimal(&radians, digits)));
+            }
+        }
+
+        // Factorials of integers are exact, up to a size where they take too long to compute
+        if let Value::Integer(n) = &self
+            && let Some(n) = n.to_u64()
+            && n <= MAX_EXACT_FACTORIAL
+        {
+            match operator {
+                TokenType::Exclamation => return Ok(Value::Integer(exact_factorial(n, 1))),
+                TokenType::DoubleExclamation => return Ok(Value::Integer(exact_factorial(n, 2))),
+                _ => (),
+            }
+        }
+
+        let value = match self.as_number() {
+            Ok(value) => value,
+            Err(error) => return Err(error),
+        };
+        let result = match operator {
+            TokenType::Exclamation => factorial(value),
+            TokenType::DoubleExclamation => double_factorial(value),
+            TokenType::AngleUnit(unit) => Ok(settings.angle_mode.from_radians(unit.to_radians(value))),
+            _ => return Err(EvaluationError::InvalidOperation),
+        };
+        match result {
+            Ok(result) if result.is_infinite() => Err(EvaluationError::ResultTooLarge),
+            Ok(result) => Ok(Value::Number(result)),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn apply_binary(self, operator: &TokenType, other: Value, settings: Settings) -> Result<Value, EvaluationError> {
+        match operator {
+            TokenType::Equality | TokenType::NotEqual => {
+                // Real and complex numbers can be compared with each other, but not with booleans
+                if matches!(self, Value::Boolean(_)) != matches!(other, Value::Boolean(_)) {
+                    return Err(other.mismatch(self.type_name()));
+                }
+                let equal = self == other;
+                return Ok(Value::Boolean(if *operator == TokenType::Equality { equal } else { !equal }));
+            }
+            TokenType::And => return Ok(Value::Boolean(self.is_truthy() && other.is_truthy())),
+            TokenType::Or => return Ok(Value::Boolean(self.is_truthy() || other.is_truthy())),
+            _ => (),
+        }
+
+        // A matrix with a list multiplies it as a vector, so this comes before element-wise lists
+        if matches!(self, Value::Matrix(_)) || matches!(other, Value::Matrix(_)) {
+            return matrix_binary(operator, self, other, settings);
+        }
+        if matches!(self, Value::List(_)) || matches!(other, Value::List(_)) {
+            return list_binary(operator, self, other, settings);
+        }
+
+        // Every other operator works on numbers
+        match (&self, &other) {
+            (Value::Boolean(_) | Value::Lambda(_), _) => return Err(self.mismatch("number")),
+            (_, Value::Boolean(_) | Value::Lambda(_)) => return Err(other.mismatch("number")),
+            _ if is_bitwise(operator) => return bitwise_binary(operator, &self, &other),
+            (Value::Quantity(_), _) | (_, Value::Quantity(_)) => return quantity_binary(operator, &self, &other, settings),
+            _ if *operator == TokenType::Conversion => return quantity_binary(operator, &self, &other, settings),
+            (Value::Complex(_), _) | (_, Value::Complex(_)) => return complex_binary(operator, &self, &other),
+            (Value::Integer(a), Value::Integer(b)) => match integer_binary(operator, a, b) {
+                Some(result) => return result,
+                None => (),
+            },
+            _ => (),
+        }
+
+        if let (Some(a), Some(b)) = (self.as_rational(), other.as_rational()) {
+            match exact_binary(operator, &a, &b) {
+                Some(result) => return result,
+                None => (),
+            }
+        }
+
+        if let Some(digits) = settings.precision
+            && let (Some(a), Some(b)) = (
+                self.as_decimal(digits + precision::GUARD_DIGITS),
+                other.as_decimal(digits + precision::GUARD_DIGITS),
+            )
+        {
+            match decimal_binary(operator, &a, &b, digits) {
+                Some(result) => return result,
+                None => (),
+            }
+        }
+
+        match (self.as_number(), other.as_number()) {
+            (Ok(a), Ok(b)) => float_binary(operator, a, b),
+            (Err(error), _) | (_, Err(error)) => Err(error),
+        }
+    }
+
+    // The length of a list along each of its dimensions, written like `[2, 3]`, or `[]` for
+    // anything that is not a list or matrix
+    pub fn shape(&self) -> String {
+        if let Value::Matrix(matrix) = self {
+            return matrix.shape();
+        }
+        let mut dimensions: Vec<String> = vec![];
+        let mut value = self;
+        while let Value::List(values) = value {
+            dimensions.push(values.len().to_string());
+            match values.first() {
+                Some(first) => value = first,
+                None => break,
+            }
+        }
+        return format!("[{}]", dimensions.join(", "));
+    }
+
+    // The element at an index, where negative indices count back from the end, so `v[-1]` is the
+    // last element. A matrix is indexed by its rows.
+    pub fn index(&self, index: &Value) -> Result<Value, EvaluationError> {
+        let values = match self {
+            Value::List(values) => values,
+            Value::Matrix(matrix) => return Value::List(matrix.row_lists()).index(index),
+            _ => return Err(self.mismatch("list")),
+        };
+        let index = match index.as_integer() {
+            Some(index) => index,
+            None => return Err(index.mismatch("integer")),
+        };
+        match list_position(&index, values.len()) {
+            Some(position) if position < values.len() => Ok(values[position].clone()),
+            _ => Err(EvaluationError::IndexOutOfRange { index, length: values.len() }),
+        }
+    }
+
+    // The elements from start up to, but not including, end. Either end may be left out, and
+    // ends outside the list are moved to its edges, so a slice is never out of range.
+    pub fn slice(&self, start: Option<Value>, end: Option<Value>) -> Result<Value, EvaluationError> {
+        let values = match self {
+            Value::List(values) => values,
+            // A slice of the rows of a matrix is a matrix of those rows
+            Value::Matrix(matrix) => {
+                return match Value::List(matrix.row_lists()).slice(start, end) {
+                    Ok(Value::List(rows)) => Ok(Value::from_list(rows)),
+                    result => result,
+                };
+            }
+            _ => return Err(self.mismatch("list")),
+        };
+        let mut bounds = [0, values.len()];
+        for (bound, value) in bounds.iter_mut().zip([start, end]) {
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            let index = match value.as_integer() {
+                Some(index) => index,
+                None => return Err(value.mismatch("integer")),
+            };
+            *bound = match list_position(&index, values.len()) {
+                Some(position) => position.min(values.len()),
+                None => 0,
+            };
+        }
+        let [start, end] = bounds;
+        if start >= end {
+            return Ok(Value::List(vec![]));
+        }
+        return Ok(Value::List(values[start..end].to_vec()));
+    }
+
+    fn mismatch(&self, expected: &str) -> EvaluationError {
+        EvaluationError::TypeMismatch {
+            expected: expected.to_string(),
+            found: self.type_name().to_string(),
+        }
+    }
+}
+
+// The significant digits that are enough to write any float exactly
+const FLOAT_DIGITS: u64 = 17;
+
+// Powers whose exact result would need more bits than this are computed with floats, and are
+// too large if they overflow them
+pub(crate) const MAX_EXACT_POWER_BITS: u64 = 1 << 20;
+
+// Factorials of larger integers are computed with floats, where they are too large
+const MAX_EXACT_FACTORIAL: u64 = 10_000;
+
+// Lists of the same length are combined element by element, and a list with anything else
+// applies the operator between each of its elements and that value, so `[1, 2] * 3` is `[3, 6]`
+fn list_binary(operator: &TokenType, operand_1: Value, operand_2: Value, settings: Settings) -> Result<Value, EvaluationError> {
+    let pairs: Vec<(Value, Value)> = match (operand_1, operand_2) {
+        (Value::List(a), Value::List(b)) => {
+            if a.len() != b.len() {
+                return Err(EvaluationError::ShapeMismatch {
+                    expected: Value::List(a).shape(),
+                    found: Value::List(b).shape(),
+                });
+            }
+            a.into_iter().zip(b).collect()
+        }
+        (Value::List(a), b) => a.into_iter().map(|a| (a, b.clone())).collect(),
+        (a, Value::List(b)) => b.into_iter().map(|b| (a.clone(), b)).collect(),
+        _ => return Err(EvaluationError::InvalidOperation),
+    };
+    let mut results: Vec<Value> = Vec::with_capacity(pairs.len());
+    for (a, b) in pairs {
+        match a.apply_binary(operator, b, settings) {
+            Ok(result) => results.push(result),
+            Err(error) => return Err(error),
+        }
+    }
+    return Ok(Value::List(results));
+}
+
+// The position of an index in a list of the given length, counting negative indices back from
+// the end, or None if it is before the start. It may be past the end.
+fn list_position(index: &BigInt, length: usize) -> Option<usize> {
+    if index.is_negative() {
+        return (BigInt::from(length) + index).to_usize();
+    }
+    return Some(index.to_usize().unwrap_or(usize::MAX));
+}
+
+// `*` between matrices, or between a matrix and a list, is the matrix product, where a list on
+// the right is a column and a list on the left is a row. `A^n` multiplies A by itself n times.
+// Matrices of the same shape are otherwise combined element by element, and a matrix with a
+// number applies the operator between each of its elements and that number.
+fn matrix_binary(operator: &TokenType, operand_1: Value, operand_2: Value, settings: Settings) -> Result<Value, EvaluationError> {
+    let result = match (operator, &operand_1, &operand_2) {
+        (TokenType::Multiplication, Value::Matrix(a), Value::Matrix(b)) => a.multiply(b, settings),
+        (TokenType::Exponentiation, Value::Matrix(a), b) if b.type_name() == "number" => a.power(b, settings),
+        // A matrix times a list gives a list
+        (TokenType::Multiplication, Value::Matrix(a), Value::List(b)) => {
+            return match a.multiply(&Matrix::from_column(b.clone()), settings) {
+                Ok(product) => Ok(Value::List(product.elements().to_vec())),
+                Err(error) => Err(error),
+            };
+        }
+        (TokenType::Multiplication, Value::List(a), Value::Matrix(b)) => {
+            return match Matrix::from_row(a.clone()).multiply(b, settings) {
+                Ok(product) => Ok(Value::List(product.elements().to_vec())),
+                Err(error) => Err(error),
+            };
+        }
+        _ => return matrix_elementwise(operator, operand_1, operand_2, settings),
+    };
+    match result {
+        Ok(matrix) => Ok(Value::Matrix(matrix)),
+        Err(error) => Err(error),
+    }
+}
+
+fn matrix_elementwise(operator: &TokenType, operand_1: Value, operand_2: Value, settings: Settings) -> Result<Value, EvaluationError> {
+    let is_elementwise = !matches!(operator, TokenType::Multiplication | TokenType::Division | TokenType::Exponentiation);
+    let result = match (&operand_1, &operand_2) {
+        (Value::Matrix(a), Value::Matrix(b)) if is_elementwise => {
+            a.zip(b, |a, b| a.clone().apply_binary(operator, b.clone(), settings))
+        }
+        (Value::Matrix(_), Value::Matrix(_) | Value::List(_) | Value::Lambda(_)) => return Err(operand_2.mismatch("number")),
+        (Value::List(_) | Value::Lambda(_), Value::Matrix(_)) => return Err(operand_1.mismatch("number")),
+        (_, Value::Matrix(_)) if *operator == TokenType::Exponentiation => return Err(operand_2.mismatch("number")),
+        (Value::Matrix(a), b) => a.map(|a| a.clone().apply_binary(operator, b.clone(), settings)),
+        (a, Value::Matrix(b)) => b.map(|b| a.clone().apply_binary(operator, b.clone(), settings)),
+        _ => return Err(EvaluationError::InvalidOperation),
+    };
+    match result {
+        Ok(matrix) => Ok(Value::Matrix(matrix)),
+        Err(error) => Err(error),
+    }
+}
+
+// Arithmetic on two integers that stays on integers. Returns None when the result is a
+// fraction instead, which is for divisions with a remainder and negative exponents.
+fn integer_binary(operator: &TokenType, a: &BigInt, b: &BigInt) -> Option<Result<Value, EvaluationError>> {
+    match operator {
+        TokenType::Addition => Some(Ok(Value::Integer(a + b))),
+        TokenType::Subtraction => Some(Ok(Value::Integer(a - b))),
+        TokenType::Multiplication => Some(Ok(Value::Integer(a * b))),
+        TokenType::Division => {
+            if b.is_zero() {
+                return Some(Err(EvaluationError::DivisionByZero));
+            }
+            if !(a % b).is_zero() {
+                return None;
+            }
+            Some(Ok(Value::Integer(a / b)))
+        }
+        TokenType::Exponentiation => {
+            if b.is_negative() {
+                return None;
+            }
+            let exponent = match b.to_u32() {
+                Some(exponent) => exponent,
+                None => return None,
+            };
+            if a.bits() * exponent as u64 > MAX_EXACT_POWER_BITS {
+                return None;
+            }
+            Some(Ok(Value::Integer(a.pow(exponent))))
+        }
+        TokenType::LessThan => Some(Ok(Value::Boolean(a < b))),
+        TokenType::LessThanOrEqual => Some(Ok(Value::Boolean(a <= b))),
+        TokenType::GreaterThan => Some(Ok(Value::Boolean(a > b))),
+        TokenType::GreaterThanOrEqual => Some(Ok(Value::Boolean(a >= b))),
+        _ => None,
+    }
+}
+
+fn is_bitwise(operator: &TokenType) -> bool {
+    matches!(
+        operator,
+        TokenType::BitwiseAnd
+            | TokenType::BitwiseOr
+            | TokenType::BitwiseXor
+            | TokenType::ShiftLeft
+            | TokenType::ShiftRight
+    )
+}
+
+// The bitwise operators, which only work on whole numbers. Negative integers behave as an
+// infinitely long two's complement, until they are wrapped into a word.
+fn bitwise_binary(operator: &TokenType, operand_1: &Value, operand_2: &Value) -> Result<Value, EvaluationError> {
+    let a = match operand_1.as_integer() {
+        Some(value) => value,
+        None => return Err(operand_1.mismatch("integer")),
+    };
+    let b = match operand_2.as_integer() {
+        Some(value) => value,
+        None => return Err(operand_2.mismatch("integer")),
+    };
+    match operator {
+        TokenType::BitwiseAnd => Ok(Value::Integer(a & b)),
+        TokenType::BitwiseOr => Ok(Value::Integer(a | b)),
+        TokenType::BitwiseXor => Ok(Value::Integer(a ^ b)),
+        TokenType::ShiftLeft | TokenType::ShiftRight => {
+            let symbol = if *operator == TokenType::ShiftLeft { "<<" } else { ">>" };
+            // Shifts further than the exact powers go would not fit in memory
+            let shift = match b.to_u64() {
+                Some(shift) if shift <= MAX_EXACT_POWER_BITS => shift,
+                _ => return Err(EvaluationError::DomainError {
+                    function: symbol.to_string(),
+                    value: b.to_f64().unwrap_or(f64::NAN),
+                }),
+            };
+            if *operator == TokenType::ShiftLeft {
+                return Ok(Value::Integer(a << shift));
+            }
+            return Ok(Value::Integer(a >> shift));
+        }
+        _ => Err(EvaluationError::InvalidOperation),
+    }
+}
+
+// Exact arithmetic on two fractions. Returns None when the result has to be computed with
+// floats instead, such as for non-integer exponents.
+fn exact_binary(operator: &TokenType, a: &BigRational, b: &BigRational) -> Option<Result<Value, EvaluationError>> {
+    match operator {
+        TokenType::Addition => Some(Ok(Value::from_rational(a + b))),
+        TokenType::Subtraction => Some(Ok(Value::from_rational(a - b))),
+        TokenType::Multiplication => Some(Ok(Value::from_rational(a * b))),
+        TokenType::Division => {
+            if b.is_zero() {
+                return Some(Err(EvaluationError::DivisionByZero));
+            }
+            Some(Ok(Value::from_rational(a / b)))
+        }
+        TokenType::Exponentiation => {
+            // A negative power of 0 divides by 0, like `0^-1 = 1/0`
+            if a.is_zero() && b.is_negative() {
+                return Some(Err(EvaluationError::DivisionByZero));
+            }
+            if !b.is_integer() {
+                return None;
+            }
+            let exponent = match b.to_i32() {
+                Some(exponent) => exponent,
+                None => return None,
+            };
+            let size = (a.numer().bits() + a.denom().bits()) * exponent.unsigned_abs() as u64;
+            if size > MAX_EXACT_POWER_BITS {
+                return None;
+            }
+            Some(Ok(Value::from_rational(a.pow(exponent))))
+        }
+        TokenType::LessThan => Some(Ok(Value::Boolean(a < b))),
+        TokenType::LessThanOrEqual => Some(Ok(Value::Boolean(a <= b))),
+        TokenType::GreaterThan => Some(Ok(Value::Boolean(a > b))),
+        TokenType::GreaterThanOrEqual => Some(Ok(Value::Boolean(a >= b))),
+        _ => None,
+    }
+}
+
+// Arithmetic to the given number of significant digits. Returns None when the result has
+// to be computed with floats instead, which is for fractional powers of negative numbers.
+fn decimal_binary(operator: &TokenType, a: &BigDecimal, b: &BigDecimal, digits: u64) -> Option<Result<Value, EvaluationError>> {
+    match operator {
+        TokenType::Addition => Some(Ok(Value::Decimal((a + b).round_to(digits)))),
+        TokenType::Subtraction => Some(Ok(Value::Decimal((a - b).round_to(digits)))),
+        TokenType::Multiplication => Some(Ok(Value::Decimal((a * b).round_to(digits)))),
+        TokenType::Division => {
+            if b.is_zero() {
+                return Some(Err(EvaluationError::DivisionByZero));
+            }
+            Some(Ok(Value::Decimal(precision::divide(a, b, digits))))
+        }
+        TokenType::Exponentiation => {
+            if (a.is_zero() && b.is_negative()) || (a.is_negative() && !b.is_integer()) {
+                return None;
+            }
+            // A decimal can hold a huge exponent, but not print one, so powers are held to the
+            // size of the exact ones
+            let size = b.to_f64().unwrap_or(f64::INFINITY) * decimal_log10(a) * std::f64::consts::LOG2_10;
+            if size > MAX_EXACT_POWER_BITS as f64 {
+                return Some(Err(EvaluationError::ResultTooLarge));
+            }
+            Some(Ok(Value::Decimal(precision::power(a, b, digits))))
+        }
+        TokenType::LessThan => Some(Ok(Value::Boolean(a < b))),
+        TokenType::LessThanOrEqual => Some(Ok(Value::Boolean(a <= b))),
+        TokenType::GreaterThan => Some(Ok(Value::Boolean(a > b))),
+        TokenType::GreaterThanOrEqual => Some(Ok(Value::Boolean(a >= b))),
+        _ => None,
+    }
+}
+
+// The base 10 logarithm of the magnitude of a decimal, which may be too large for a float
+fn decimal_log10(x: &BigDecimal) -> f64 {
+    match x.abs().to_f64() {
+        Some(value) if value.is_finite() && value > 0.0 => value.log10(),
+        _ => x.order_of_magnitude() as f64,
+    }
+}
+
+// Arithmetic that overflows gives an error rather than an infinite result
+fn float_binary(operator: &TokenType, a: f64, b: f64) -> Result<Value, EvaluationError> {
+    let result = match operator {
+        TokenType::Exponentiation => {
+            if a == 0.0 && b < 0.0 {
+                return Err(EvaluationError::DivisionByZero);
+            }
+            // Fractional powers of negative numbers are complex, as in `(-8)^(1/3)`
+            if a < 0.0 && b.is_finite() && b.fract() != 0.0 {
+                let power = Complex64::new(a, 0.0).powc(Complex64::new(b, 0.0));
+                if power.is_infinite() {
+                    return Err(EvaluationError::ResultTooLarge);
+                }
+                return Ok(Value::from_complex(power));
+            }
+            Ok(Value::Number(a.powf(b)))
+        }
+        TokenType::Multiplication => Ok(Value::Number(a * b)),
+        TokenType::Division => {
+            if b != 0.0 {
+                return Ok(Value::Number(a / b));
+            } else {
+                return Err(EvaluationError::DivisionByZero);
+            }
+        }
+        TokenType::Addition => Ok(Value::Number(a + b)),
+        TokenType::Subtraction => Ok(Value::Number(a - b)),
+        TokenType::LessThan => Ok(Value::Boolean(a < b)),
+        TokenType::LessThanOrEqual => Ok(Value::Boolean(a <= b)),
+        TokenType::GreaterThan => Ok(Value::Boolean(a > b)),
+        TokenType::GreaterThanOrEqual => Ok(Value::Boolean(a >= b)),
+        _ => return Err(EvaluationError::InvalidOperation),
+    };
+    match result {
+        Ok(Value::Number(result)) if result.is_infinite() => Err(EvaluationError::ResultTooLarge),
+        result => result,
+    }
+}
+
+// Arithmetic where at least one operand is complex. Complex numbers have no order, so
+// comparing them is a type mismatch.
+fn complex_binary(operator: &TokenType, operand_1: &Value, operand_2: &Value) -> Result<Value, EvaluationError> {
+    let (a, b) = match (operand_1.as_complex(), operand_2.as_complex()) {
+        (Some(a), Some(b)) => (a, b),
+        (None, _) => return Err(operand_1.mismatch("number")),
+        (_, None) => return Err(operand_2.mismatch("number")),
+    };
+
+    let result = match operator {
+        TokenType::Addition => a + b,
+        TokenType::Subtraction => a - b,
+        TokenType::Multiplication => a * b,
+        TokenType::Division => {
+            if b.is_zero() {
+                return Err(EvaluationError::DivisionByZero);
+            }
+            a / b
+        }
+        TokenType::Exponentiation => {
+            if a.is_zero() {
+                if b.re > 0.0 {
+                    return Ok(Value::Number(0.0));
+                }
+                return Err(EvaluationError::DivisionByZero);
+            }
+            // Integer powers are repeated multiplication, which keeps `i^2` at exactly -1
+            if b.im == 0.0 && b.re.fract() == 0.0 && b.re.abs() <= i32::MAX as f64 {
+                a.powi(b.re as i32)
+            } else {
+                a.powc(b)
+            }
+        }
+        TokenType::LessThan
+        | TokenType::LessThanOrEqual
+        | TokenType::GreaterThan
+        | TokenType::GreaterThanOrEqual => {
+            let complex = if operand_1.type_name() == "number" { operand_2 } else { operand_1 };
+            return Err(complex.mismatch("real number"));
+        }
+        _ => return Err(EvaluationError::InvalidOperation),
+    };
+    Ok(Value::from_complex(result))
+}
+
+// Arithmetic where at least one operand is a quantity. Plain numbers count as quantities
+// without a dimension, so they can scale a quantity but not be added to it.
+fn quantity_binary(operator: &TokenType, operand_1: &Value, operand_2: &Value, settings: Settings) -> Result<Value, EvaluationError> {
+    let a = match operand_1.as_quantity() {
+        Some(a) => a,
+        None => return Err(operand_1.mismatch("number")),
+    };
+
+    // The unit of a power would need fractional exponents unless the exponent is whole
+    if *operator == TokenType::Exponentiation {
+        let exponent = match operand_2 {
+            Value::Quantity(_) => return Err(operand_2.mismatch("number")),
+            _ => match operand_2.as_number() {
+                Ok(exponent) => exponent,
+                Err(error) => return Err(error),
+            },
+        };
+        if exponent.fract() != 0.0 || exponent.abs() > i32::MAX as f64 {
+            return Err(operand_2.mismatch("whole number"));
+        }
+        return match a.power(exponent as i32, settings) {
+            Ok(result) => Ok(Value::from_quantity(result)),
+            Err(error) => Err(error),
+        };
+    }
+
+    let b = match operand_2.as_quantity() {
+        Some(b) => b,
+        None => return Err(operand_2.mismatch("number")),
+    };
+    let result = match operator {
+        TokenType::Addition | TokenType::Subtraction => a.add(&b, operator, settings),
+        TokenType::Multiplication => a.multiply(&b, settings),
+        TokenType::Division => {
+            if matches!(b.value().as_number(), Ok(value) if value == 0.0) {
+                return Err(EvaluationError::DivisionByZero);
+            }
+            match b.power(-1, settings) {
+                Ok(b) => a.multiply(&b, settings),
+                Err(error) => Err(error),
+            }
+        }
+        TokenType::Conversion => a.convert(&b, settings),
+        TokenType::LessThan
+        | TokenType::LessThanOrEqual
+        | TokenType::GreaterThan
+        | TokenType::GreaterThanOrEqual => return a.compare(&b, operator, settings),
+        _ => return Err(EvaluationError::InvalidOperation),
+    };
+    match result {
+        Ok(result) => Ok(Value::from_quantity(result)),
+        Err(error) => Err(error),
+    }
+}
+
+// n(n - step)(n - 2 step)... down to 1, which is n! for a step of 1 and n!! for a step of 2
+fn exact_factorial(n: u64, step: u64) -> BigInt {
+    let mut result = BigInt::from(1);
+    let mut factor = n;
+    while factor > 1 {
+        result *= factor;
+        factor -= step;
+    }
+    result
+}
+
+// n! for non-negative integers, extended to the reals through the gamma function as Γ(n + 1)
+fn factorial(value: f64) -> Result<f64, EvaluationError> {
+    if value < 0.0 && value.fract() == 0.0 {
+        return Err(EvaluationError::DomainError { function: "factorial".to_string(), value });
+    }
+    if value.fract() != 0.0 {
+        return Ok(tgamma(value + 1.0));
+    }
+
+    let mut result: f64 = 1.0;
+    let mut n = 2.0;
+    while n <= value && result.is_finite() {
+        result *= n;
+        n += 1.0;
+    }
+    Ok(result)
+}
+
+// n!! = n(n - 2)(n - 4)..., only defined for integers from -1 and up
+fn double_factorial(value: f64) -> Result<f64, EvaluationError> {
+    if value < -1.0 || value.fract() != 0.0 {
+        return Err(EvaluationError::DomainError { function: "double factorial".to_string(), value });
+    }
+
+    let mut result: f64 = 1.0;
+    let mut n = value;
+    while n > 1.0 && result.is_finite() {
+        result *= n;
+        n -= 2.0;
+    }
+    Ok(result)
+}
+
+// Numbers are equal when their values are, whether they are exact or not
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Integer(_) | Value::Rational(_), Value::Integer(_) | Value::Rational(_)) => {
+                self.as_rational() == other.as_rational()
+            }
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Boolean(_), _) | (_, Value::Boolean(_)) => false,
+            // A function is only equal to itself
+            (Value::Lambda(a), Value::Lambda(b)) => Rc::ptr_eq(a, b),
+            (Value::Lambda(_), _) | (_, Value::Lambda(_)) => false,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::List(_), _) | (_, Value::List(_)) => false,
+            (Value::Matrix(a), Value::Matrix(b)) => a == b,
+            (Value::Matrix(_), _) | (_, Value::Matrix(_)) => false,
+            (Value::Quantity(a), Value::Quantity(b)) => a == b,
+            (Value::Quantity(_), _) | (_, Value::Quantity(_)) => false,
+            (Value::Complex(_), _) | (_, Value::Complex(_)) => self.as_complex() == other.as_complex(),
+            _ => match (self.as_number(), other.as_number()) {
+                (Ok(a), Ok(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(value) => write!(f, "{}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Rational(value) => match exact_decimal(value) {
+                Some(decimal) => write!(f, "{}", decimal),
+                None => write!(f, "{}", value.to_f64().unwrap_or(f64::NAN)),
+            },
+            Value::Decimal(value) => write!(f, "{}", value.normalized().to_plain_string()),
+            Value::Complex(value) => {
+                // A coefficient of 1 is left out, as in `2 - i`
+                let imaginary = if value.im.abs() == 1.0 { "i".to_string() } else { format!("{}i", value.im.abs()) };
+                if value.re == 0.0 {
+                    let sign = if value.im < 0.0 { "-" } else { "" };
+                    return write!(f, "{}{}", sign, imaginary);
+                }
+                let sign = if value.im < 0.0 { "-" } else { "+" };
+                write!(f, "{} {} {}", value.re, sign, imaginary)
+            }
+            Value::Quantity(value) => write!(f, "{}", value.write(|value| value.to_string())),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Lambda(lambda) if lambda.params.is_empty() => write!(f, "<function>"),
+            Value::Lambda(lambda) => write!(f, "<function of {}>", lambda.params.join(", ")),
+            Value::List(values) => write!(f, "{}", write_list(values.iter().map(|value| value.to_string()))),
+            Value::Matrix(matrix) => write!(f, "{}", matrix.write(|value| value.to_string())),
+        }
+    }
+}
+
+fn write_list(elements: impl Iterator<Item = String>) -> String {
+    return format!("[{}]", elements.collect::<Vec<String>>().join(", "));
+}
+
+// Writes a fraction as a decimal if it has a finite one, which is when the denominator
+// has no prime factors other than 2 and 5
+fn exact_decimal(value: &BigRational) -> Option<String> {
+    let two = BigInt::from(2);
+    let five = BigInt::from(5);
+    let mut denominator = value.denom().clone();
+    let mut twos: u32 = 0;
+    let mut fives: u32 = 0;
+    while (&denominator % &two).is_zero() {
+        denominator /= &two;
+        twos += 1;
+    }
+    while (&denominator % &five).is_zero() {
+        denominator /= &five;
+        fives += 1;
+    }
+    if !denominator.is_one() {
+        return None;
+    }
+
+    let places = twos.max(fives);
+    let scaled = value.numer() * BigInt::from(10).pow(places) / value.denom();
+    let sign = if scaled.is_negative() { "-" } else { "" };
+    let digits = scaled.abs().to_string();
+    if places == 0 {
+        return Some(format!("{}{}", sign, digits));
+    }
+    let digits = format!("{:0>width$}", digits, width = places as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - places as usize);
+    return Some(format!("{}{}.{}", sign, whole, fraction));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: Settings = Settings { angle_mode: AngleMode::Radians, precision: None, word_size: None };
+
+    #[test]
+    fn test_binary_promotes_to_the_shared_type() {
+        let integer = Value::Integer(BigInt::from(3));
+        let half = Value::Rational(BigRational::new(1.into(), 2.into()));
+        let sum = integer.clone().apply_binary(&TokenType::Addition, half, SETTINGS).unwrap();
+        assert_eq!(sum, Value::Rational(BigRational::new(7.into(), 2.into())));
+
+        let product = integer.apply_binary(&TokenType::Multiplication, Value::Number(0.5), SETTINGS).unwrap();
+        assert!(matches!(product, Value::Number(_)));
+        assert_eq!(product, Value::Number(1.5));
+    }
+
+    #[test]
+    fn test_binary_type_mismatch() {
+        let result = Value::Integer(BigInt::from(1)).apply_binary(&TokenType::Addition, Value::Boolean(true), SETTINGS);
+        assert_eq!(result, Err(EvaluationError::TypeMismatch {
+            expected: "number".to_string(),
+            found: "boolean".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_unary_type_mismatch() {
+        let result = Value::Boolean(true).apply_unary(&TokenType::Negation);
+        assert_eq!(result, Err(EvaluationError::TypeMismatch {
+            expected: "number".to_string(),
+            found: "boolean".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_bitwise_type_mismatch() {
+        let result = Value::Integer(BigInt::from(1)).apply_binary(&TokenType::BitwiseAnd, Value::Number(1.5), SETTINGS);
+        assert_eq!(result, Err(EvaluationError::TypeMismatch {
+            expected: "integer".to_string(),
+            found: "number".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_to_base_string() {
+        let word_size = WordSize { bits: 8, signed: true };
+        assert_eq!(Value::Integer(BigInt::from(255)).to_base_string(Base::Hexadecimal, None), "0xFF");
+        assert_eq!(Value::Integer(BigInt::from(-5)).to_base_string(Base::Binary, None), "-0b101");
+        assert_eq!(Value::Integer(BigInt::from(-1)).to_base_string(Base::Hexadecimal, Some(word_size)), "0xFF");
+        assert_eq!(Value::Integer(BigInt::from(8)).to_base_string(Base::Octal, Some(word_size)), "0o10");
+    }
+
+    #[test]
+    fn test_complex_decimal_string() {
+        let value = Value::Complex(Complex64::new(1.0 / 3.0, std::f64::consts::PI));
+        assert_eq!(value.to_decimal_string(5), "0.33333 + 3.1416i");
+        assert_eq!(value.to_decimal_string(50), value.to_string());
+    }
+
+    fn list(values: &[i64]) -> Value {
+        Value::List(values.iter().map(|&value| Value::Integer(BigInt::from(value))).collect())
+    }
+
+    #[test]
+    fn test_list_binary_broadcasts() {
+        let result = list(&[1, 2]).apply_binary(&TokenType::Multiplication, Value::Integer(BigInt::from(3)), SETTINGS);
+        assert_eq!(result, Ok(list(&[3, 6])));
+
+        let result = list(&[1, 2]).apply_binary(&TokenType::Addition, list(&[1, 2, 3]), SETTINGS);
+        assert_eq!(result, Err(EvaluationError::ShapeMismatch {
+            expected: "[2]".to_string(),
+            found: "[3]".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_index_and_slice() {
+        let values = list(&[1, 2, 3, 4]);
+        assert_eq!(values.index(&Value::Integer(BigInt::from(-1))), Ok(Value::Integer(BigInt::from(4))));
+        assert_eq!(values.index(&Value::Integer(BigInt::from(4))), Err(EvaluationError::IndexOutOfRange {
+            index: BigInt::from(4),
+            length: 4,
+        }));
+        assert_eq!(values.slice(Some(Value::Integer(BigInt::from(1))), Some(Value::Integer(BigInt::from(-1)))), Ok(list(&[2, 3])));
+        assert_eq!(values.slice(Some(Value::Integer(BigInt::from(3))), Some(Value::Integer(BigInt::from(1)))), Ok(list(&[])));
+        assert_eq!(values.slice(None, Some(Value::Integer(BigInt::from(10)))), Ok(values.clone()));
+    }
+
+    #[test]
+    fn test_from_list_makes_matrices() {
+        let rows = vec![list(&[1, 2]), list(&[3, 4])];
+        assert!(matches!(Value::from_list(rows), Value::Matrix(_)));
+        let ragged = vec![list(&[1, 2]), list(&[3])];
+        assert!(matches!(Value::from_list(ragged), Value::List(_)));
+        assert!(matches!(Value::from_list(vec![list(&[])]), Value::List(_)));
+    }
+}
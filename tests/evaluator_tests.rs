@@ -1,5 +1,11 @@
-use terminal_calculator::lexer::tokenise;
+use terminal_calculator::lexer::{tokenise, Span};
 use terminal_calculator::parser::construct_ast;
+use terminal_calculator::evaluator::{AngleMode, Environment, EvalResult, WordSize};
+use terminal_calculator::value::Value;
+use terminal_calculator::errors::EvaluationError;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
 
 // Evaluate a basic AST
 #[test]
@@ -13,11 +19,13 @@ fn test_evaluate_basic() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 8.0);
+    assert_eq!(result, Value::Number(8.0));
 }
 
 // Evaluate an expression with implicit multiplication
@@ -32,12 +40,14 @@ fn test_evaluate_implicit_multiplication() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
 
-    assert_eq!(result, 27.0);
+    assert_eq!(result, Value::Number(27.0));
 }
 
 // Evaluate an expression with different operator precedence
@@ -52,11 +62,13 @@ fn test_evaluate_precedence() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 11.0);
+    assert_eq!(result, Value::Number(11.0));
 }
 
 // Evaluate an expression with parentheses
@@ -71,11 +83,13 @@ fn test_evaluate_parentheses() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 16.0);
+    assert_eq!(result, Value::Number(16.0));
 }
 
 // Evaluate an expression with exponentiation
@@ -90,11 +104,13 @@ fn test_evaluate_exponentiation() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 9.0);
+    assert_eq!(result, Value::Number(9.0));
 }
 
 // Evaluate an expression with unary negation
@@ -109,11 +125,13 @@ fn test_evaluate_unary_negation() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 2.0);
+    assert_eq!(result, Value::Number(2.0));
 }
 
 // Evaluate an expression with a single number
@@ -128,11 +146,13 @@ fn test_evaluate_single_number() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 42.0);
+    assert_eq!(result, Value::Number(42.0));
 }
 
 #[test]
@@ -146,8 +166,26 @@ fn test_evaluate_division_by_zero() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = ast.evaluate();
-    assert!(result.is_err());
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::DivisionByZero.at(Span::new(0, 5))));
+}
+
+// Evaluate a negative power of zero, which divides by zero like `1 / 0`
+#[test]
+fn test_evaluate_negative_power_of_zero() {
+    let input = "0^-1";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::DivisionByZero.at(Span::new(0, 4))));
 }
 
 // Evaluate an expression with a function
@@ -162,11 +200,13 @@ fn test_evaluate_function() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 0.0);
+    assert_eq!(result, Value::Number(0.0));
 }
 // Evaluate an expression with a function and arguments
 #[test]
@@ -180,11 +220,13 @@ fn test_evaluate_function_with_args() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, 1.0);
+    assert_eq!(result, Value::Number(1.0));
 }
 
 // Evaluate an expression with negation
@@ -199,11 +241,13 @@ fn test_evaluate_negation() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
-        Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
     };
-    assert_eq!(result, -8.0);
+    assert_eq!(result, Value::Number(-8.0));
 }
 
 // Evaluate an expression with a negation of a function
@@ -218,9 +262,1622 @@ fn test_evaluate_negation_of_function() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    let result = match ast.evaluate() {
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(-1.0));
+}
+
+// Evaluate a function call with the wrong number of arguments
+#[test]
+fn test_evaluate_function_arity_mismatch() {
+    let input = "sin(1, 2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::ArityMismatch {
+        function: "sin".to_string(),
+        min: 1,
+        max: 1,
+        found: 2,
+    }.at(Span::new(0, 9))));
+}
+
+// Evaluate the factorial of an integer
+#[test]
+fn test_evaluate_factorial() {
+    let input = "5!";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(120.0));
+}
+
+// Evaluate the factorial of a real number through the gamma function
+#[test]
+fn test_evaluate_factorial_of_real() {
+    let input = "0.5!";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    match result {
+        Value::Number(value) => assert!((value - std::f64::consts::PI.sqrt() / 2.0).abs() < 1e-12),
+        _ => panic!("Expected a number, found {:?}", result),
+    }
+}
+
+// Evaluate the factorial of a negative integer, and returns an error
+#[test]
+fn test_evaluate_factorial_of_negative_integer() {
+    let input = "(-3)!";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::DomainError {
+        function: "factorial".to_string(),
+        value: -3.0,
+    }.at(Span::new(0, 5))));
+}
+
+// Evaluate a double factorial
+#[test]
+fn test_evaluate_double_factorial() {
+    let input = "7!!";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(105.0));
+}
+
+// Evaluate a conditional expression
+#[test]
+fn test_evaluate_conditional() {
+    let input = "-4 < 0 ? 4 : -4";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(4.0));
+}
+
+// Evaluate a conditional expression whose untaken branch would be an error
+#[test]
+fn test_evaluate_conditional_is_lazy() {
+    let input = "0 ? 1 / 0 : 2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(2.0));
+}
+
+// Evaluate a comparison, which gives a boolean
+#[test]
+fn test_evaluate_comparison() {
+    let input = "1 + 1 == 2 && 3 < 2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Boolean(false));
+}
+
+// Evaluate a logical or whose right operand would be an error
+#[test]
+fn test_evaluate_logical_short_circuit() {
+    let input = "true || 1 / 0";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Boolean(true));
+}
+
+// Evaluate arithmetic on a boolean, and returns an error
+#[test]
+fn test_evaluate_arithmetic_on_boolean() {
+    let input = "(1 < 2) + 1";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::TypeMismatch {
+        expected: "number".to_string(),
+        found: "boolean".to_string(),
+    }.at(Span::new(0, 11))));
+}
+
+// Evaluate a sequence of statements sharing one environment
+#[test]
+fn test_evaluate_statement_sequence() {
+    let input = "r = 3; h = 10; r^2 h";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(90.0));
+    assert_eq!(environment.get_variable("h"), Some(&Value::Number(10.0)));
+}
+
+// Evaluate a call to a user-defined function whose parameter shadows a variable
+#[test]
+fn test_evaluate_user_function() {
+    let input = "x = 100; f(x, y) = x^2 + y; f(3, 4) + x";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(113.0));
+}
+
+// Evaluate a recursive user-defined function
+#[test]
+fn test_evaluate_recursive_user_function() {
+    let input = "fib(n) = n < 2 ? n : fib(n - 1) + fib(n - 2); fib(10)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(55.0));
+}
+
+// Evaluate unbounded recursion, and returns an error once the depth limit is reached
+#[test]
+fn test_evaluate_recursion_limit() {
+    let input = "f(n) = f(n + 1); f(0)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    environment.set_max_call_depth(10);
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::RecursionLimitExceeded(10).at(Span::new(17, 21))));
+}
+
+// Evaluate recursion past the default depth limit, which reports an error rather than exhausting the stack
+#[test]
+fn test_evaluate_default_recursion_limit() {
+    let input = "f(n) = n < 1 ? 0 : f(n-1) + 1; f(300)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::RecursionLimitExceeded(256).at(Span::new(31, 37))));
+}
+
+// Evaluate expressions nested far deeper than the native stack could hold one frame per level for
+#[test]
+fn test_evaluate_deeply_nested() {
+    let inputs = [
+        (format!("{}1", "-".repeat(10001)), -1.0),
+        (format!("{}1{}", "(1 + ".repeat(10000), ")".repeat(10000)), 10001.0),
+    ];
+    for (input, expected) in inputs {
+        let tokens = match tokenise(input) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        let mut environment = Environment::new();
+        let result = match ast.evaluate(&mut environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+            other => panic!("Unexpected result: {:?}", other),
+        };
+        assert_eq!(result, Value::Number(expected));
+    }
+}
+
+// Evaluate a call whose body fails, where the error is located at the call because the body
+// was written in an earlier input
+#[test]
+fn test_evaluate_error_in_function_body() {
+    let inputs = ["f(x) = 1 / x", "2 + f(0)"];
+    let mut environment = Environment::new();
+    let mut result = EvalResult::Error(EvaluationError::InvalidOperation);
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        result = ast.evaluate(&mut environment);
+    }
+    assert_eq!(result, EvalResult::Error(EvaluationError::DivisionByZero.at(Span::new(4, 8))));
+}
+
+// Evaluate a function definition named after a constant, and returns an error located at it
+#[test]
+fn test_evaluate_function_definition_of_constant() {
+    let input = "pi(x) = x";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::CannotAssignAConstant("pi".to_string()).at(Span::new(0, 9))));
+}
+
+// Evaluate an assignment to the imaginary unit, and returns an error located at it
+#[test]
+fn test_evaluate_assignment_to_imaginary_unit() {
+    let input = "i = 3";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::CannotAssignAConstant("i".to_string()).at(Span::new(0, 5))));
+}
+
+// Evaluate a call on a variable, which is implicit multiplication
+#[test]
+fn test_evaluate_call_on_variable() {
+    let input = "x = 2; x(3 + 1)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(8.0));
+}
+
+// Evaluate functions from the elementary function library
+#[test]
+fn test_evaluate_elementary_functions() {
+    let input = "sqrt(16) + log(8, 2) + max(1, 5, 3) + mod(-7, 3) + abs(-2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(16.0));
+}
+
+// Evaluate a logarithm outside its domain, and returns an error
+#[test]
+fn test_evaluate_logarithm_of_zero() {
+    let input = "ln(0)";
+    let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
-        Err(error) => panic!("EvaluationError: {:?}", error),
+        Err(error) => panic!("LexerError: {:?}", error),
     };
-    assert_eq!(result, -1.0);
-}
\ No newline at end of file
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::DomainError {
+        function: "ln".to_string(),
+        value: 0.0,
+    }.at(Span::new(0, 5))));
+}
+
+// Evaluate a modulo by zero, and returns an error
+#[test]
+fn test_evaluate_modulo_by_zero() {
+    let input = "mod(5, 0)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::DivisionByZero.at(Span::new(0, 9))));
+}
+
+// Evaluate inverse and hyperbolic trigonometric functions
+#[test]
+fn test_evaluate_inverse_and_hyperbolic_trigonometry() {
+    let input = "asin(1) + acos(1) + atan2(0, 1) + tanh(0) + acosh(1)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(std::f64::consts::FRAC_PI_2));
+}
+
+// Evaluate an arcsine outside its domain, and returns an error
+#[test]
+fn test_evaluate_arcsine_out_of_domain() {
+    let input = "asin(2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::DomainError {
+        function: "asin".to_string(),
+        value: 2.0,
+    }.at(Span::new(0, 7))));
+}
+
+// Evaluate the tangent at a pole, and returns an error
+#[test]
+fn test_evaluate_tangent_at_pole() {
+    let input = "tan(3pi/2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    environment.init_consts();
+    let result = ast.evaluate(&mut environment);
+    let error = match result {
+        EvalResult::Error(error) => error,
+        _ => panic!("Expected an error, found {:?}", result),
+    };
+    assert!(matches!(error.kind(), EvaluationError::Undefined { function, .. } if function == "tan"));
+}
+
+// Evaluate the tangent away from its poles
+#[test]
+fn test_evaluate_tangent_away_from_pole() {
+    let input = "tan(2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(2.0_f64.tan()));
+}
+
+// Evaluate the tangent and secant of large arguments, which are not poles however close to one
+// their remainder is
+#[test]
+fn test_evaluate_large_arguments_away_from_pole() {
+    let cases = [
+        ("tan(1e300)", 1e300_f64.tan()),
+        ("sec(1e20)", 1.0 / 1e20_f64.cos()),
+        ("tan(1e300 * 1.0)", 1e300_f64.tan()),
+    ];
+    for (input, expected) in cases {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        let mut environment = Environment::new();
+        let result = match ast.evaluate(&mut environment) {
+            EvalResult::Value(result) => result,
+            EvalResult::Error(error) => panic!("EvaluationError in {}: {:?}", input, error),
+            other => panic!("Unexpected result: {:?}", other),
+        };
+        assert_eq!(result, Value::Number(expected), "{}", input);
+    }
+}
+
+// Evaluate trigonometry in degrees, where inverse functions also return degrees
+#[test]
+fn test_evaluate_degree_mode() {
+    let input = "sin(90) + asin(1)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    environment.set_angle_mode(AngleMode::Degrees);
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(91.0));
+}
+
+// Evaluate trigonometry on exact angles in degrees and gradians, which is reduced to a turn and
+// exact at multiples of 30 and 45 degrees, also when the unit is written after the angle
+#[test]
+fn test_evaluate_exact_angles() {
+    let inputs = [
+        (AngleMode::Degrees, "sin(30)"),
+        (AngleMode::Degrees, "cos(420)"),
+        (AngleMode::Degrees, "tan(-45)"),
+        (AngleMode::Degrees, "cot(90)"),
+        (AngleMode::Gradians, "cos(100)"),
+        (AngleMode::Gradians, "sec(400)"),
+        (AngleMode::Radians, "sin(30°)"),
+        (AngleMode::Radians, "cos(100grad)"),
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for (mode, input) in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        environment.set_angle_mode(mode);
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], Value::Rational(BigRational::new(1.into(), 2.into())));
+    assert_eq!(results[1], Value::Rational(BigRational::new(1.into(), 2.into())));
+    assert_eq!(results[2], Value::Integer(BigInt::from(-1)));
+    assert_eq!(results[3], Value::Integer(BigInt::from(0)));
+    assert_eq!(results[4], Value::Integer(BigInt::from(0)));
+    assert_eq!(results[5], Value::Integer(BigInt::from(1)));
+    assert_eq!(results[6], Value::Rational(BigRational::new(1.into(), 2.into())));
+    assert_eq!(results[7], Value::Integer(BigInt::from(0)));
+}
+
+// Evaluate an angle with an explicit unit, which overrides the angle mode
+#[test]
+fn test_evaluate_angle_unit_suffix() {
+    let input = "2 * 90°";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Number(std::f64::consts::PI));
+}
+
+// Evaluate decimal arithmetic, which stays exact
+#[test]
+fn test_evaluate_exact_decimal_addition() {
+    let input = "0.1 + 0.2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Rational(BigRational::new(3.into(), 10.into())));
+    assert_eq!(result.to_string(), "0.3");
+}
+
+// Evaluate a fraction that is multiplied back into a whole number
+#[test]
+fn test_evaluate_exact_fraction() {
+    let input = "1/3 * 3";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Rational(BigRational::from_integer(1.into())));
+}
+
+// Evaluate a fraction that has no finite decimal, and shows it both ways
+#[test]
+fn test_evaluate_fraction_display() {
+    let input = "2/6";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result.to_fraction_string(), "1/3");
+    assert_eq!(result.to_string(), "0.3333333333333333");
+}
+
+// Evaluate an irrational root, which falls back to floating point
+#[test]
+fn test_evaluate_irrational_falls_back_to_float() {
+    let input = "sqrt(2) + sqrt(9/4)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert!(matches!(result, Value::Number(_)));
+    assert_eq!(result, Value::Number(std::f64::consts::SQRT_2 + 1.5));
+}
+
+// Evaluate an irrational root to a set precision instead of as a float
+#[test]
+fn test_evaluate_with_precision() {
+    let input = "sqrt(2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    environment.set_precision(Some(30));
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result.to_string(), "1.41421356237309504880168872421");
+}
+
+// Evaluate constants, which are computed to the precision that has been set
+#[test]
+fn test_evaluate_constants_with_precision() {
+    let input = "2pi";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    environment.init_consts();
+    environment.set_precision(Some(40));
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result.to_string(), "6.283185307179586476925286766559005768394");
+}
+
+// Evaluate a fraction to a set precision, which is only cut off when it is printed
+#[test]
+fn test_evaluate_fraction_with_precision() {
+    let input = "1/3";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    environment.set_precision(Some(25));
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(result) => result,
+        EvalResult::Error(error) => panic!("EvaluationError: {:?}", error),
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(result, Value::Rational(BigRational::new(1.into(), 3.into())));
+    assert_eq!(result.to_decimal_string(25), "0.3333333333333333333333333");
+}
+
+// Evaluate the square root of a negative number, and returns an imaginary number
+#[test]
+fn test_evaluate_square_root_of_negative_number() {
+    let input = "sqrt(-4)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result, Value::Complex(Complex64::new(0.0, 2.0)));
+    assert_eq!(result.to_string(), "2i");
+}
+
+// Evaluate complex arithmetic, where a real result becomes a real number again
+#[test]
+fn test_evaluate_complex_arithmetic() {
+    let input = "(2 + 3i) * (2 - 3i) + i^2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Value(Value::Number(12.0)));
+}
+
+// Evaluate the complex functions, and formats the result
+#[test]
+fn test_evaluate_complex_functions() {
+    let input = "conj(2 + 3i) + abs(3 + 4i) * re(1 - i) + im(2i)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result.to_string(), "9 - 3i");
+}
+
+// Evaluate a fractional power of a negative number, and returns the principal root
+#[test]
+fn test_evaluate_fractional_power_of_negative_number() {
+    let input = "(-4)^(1/2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(Value::Complex(value)) => value,
+        other => panic!("Expected a complex number, found {:?}", other),
+    };
+    assert!(result.re.abs() < 1e-15);
+    assert!((result.im - 2.0).abs() < 1e-15);
+}
+
+// Evaluate an ordering of complex numbers, and returns an error
+#[test]
+fn test_evaluate_complex_comparison() {
+    let input = "i < 1";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::TypeMismatch {
+        expected: "real number".to_string(),
+        found: "complex number".to_string(),
+    }.at(Span::new(0, 5))));
+}
+
+// Writes a complex number in polar form, with the angle in degrees
+#[test]
+fn test_complex_polar_form() {
+    let value = Value::Complex(Complex64::new(0.0, 2.0));
+    assert_eq!(value.to_polar_string(AngleMode::Degrees), "2 ∠ 90");
+}
+
+// Evaluate a power too large for a float, and returns the exact integer
+#[test]
+fn test_evaluate_big_integer_power() {
+    let input = "2^100 + 1";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    let expected: BigInt = "1267650600228229401496703205377".parse().unwrap();
+    assert_eq!(result, Value::Integer(expected));
+}
+
+// Evaluate a large factorial, and returns the exact integer
+#[test]
+fn test_evaluate_exact_factorial() {
+    let input = "30!";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result.to_string(), "265252859812191058636308480000000");
+}
+// Evaluate powers, factorials and functions past what can be held exactly or as a float, which return an error
+// Evaluate results past what can be held exactly or as a float, which return an error
+// located at the expression instead of an infinite result
+#[test]
+fn test_evaluate_result_too_large() {
+    let inputs = ["100000!", "(10^1000)!", "2^(2^30)", "3^3^3^3", "exp(1000)", "sinh(1000)", "cosh(-1000)"];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        let result = ast.evaluate(&mut environment);
+        assert_eq!(result, EvalResult::Error(EvaluationError::ResultTooLarge.at(Span::new(0, input.len()))));
+    }
+}
+
+// Evaluate integer divisions, which stay integers only when there is no remainder
+#[test]
+fn test_evaluate_integer_division() {
+    let inputs = ["12 / 4", "7 / 2"];
+    let mut results: Vec<Value> = vec![];
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        let mut environment = Environment::new();
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert!(matches!(results[0], Value::Integer(_)));
+    assert_eq!(results[0], Value::Integer(BigInt::from(3)));
+    assert_eq!(results[1], Value::Rational(BigRational::new(7.into(), 2.into())));
+}
+
+// Evaluate a unit conversion, and returns the quantity in the target unit
+#[test]
+fn test_evaluate_unit_conversion() {
+    let input = "36 km/h to m/s";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(Value::Quantity(quantity)) => quantity,
+        other => panic!("Expected a quantity, found {:?}", other),
+    };
+    assert_eq!(result.value(), &Value::Integer(BigInt::from(10)));
+    assert_eq!(result.unit_name(), "m/s");
+}
+
+// Evaluate arithmetic and conversions on quantities, which stay exact
+#[test]
+fn test_evaluate_exact_quantities() {
+    let inputs = [
+        ("0.1 m + 0.2 m", "0.3 m"),
+        ("0.1 m + 0.2 m == 0.3 m", "true"),
+        ("1 ft to inch", "12 inch"),
+        ("1 m == 100 cm", "true"),
+        ("10^30 m * 10", "10000000000000000000000000000000 m"),
+    ];
+    for (input, expected) in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        let mut environment = Environment::new();
+        let result = match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => value,
+            other => panic!("Expected a value, found {:?}", other),
+        };
+        assert_eq!(result.to_string(), expected, "{}", input);
+    }
+}
+
+// Evaluate a quantity divided by a quantity, where the units bind to their numbers
+#[test]
+fn test_evaluate_quantity_division() {
+    let input = "100 km / 2 h";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result.to_string(), "50 km/h");
+}
+
+// Evaluate units after operands other than numbers, and user functions named like units
+#[test]
+fn test_evaluate_units_after_operands() {
+    let inputs = [
+        ("(2 + 3) m", "5 m"),
+        ("x = 2; x m", "2 m"),
+        ("sqrt(4) m", "2 m"),
+        ("g(x) = x + 1; 2g(3)", "8"),
+        ("s(x) = x; 3 s(2)", "6"),
+    ];
+    for (input, expected) in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        let mut environment = Environment::new();
+        let result = match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => value,
+            other => panic!("Expected a value, found {:?}", other),
+        };
+        assert_eq!(result.to_string(), expected, "{}", input);
+    }
+}
+
+// Evaluate an addition of quantities with different dimensions, and returns an error
+#[test]
+fn test_evaluate_dimension_mismatch() {
+    let input = "3 m + 2 s";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    let error = match result {
+        EvalResult::Error(error) => error,
+        _ => panic!("Expected an error, found {:?}", result),
+    };
+    assert!(matches!(error.kind(), EvaluationError::DimensionMismatch { .. }));
+    assert_eq!(error.span(), Some(Span::new(0, 9)));
+}
+
+// Evaluate a variable named like a unit, which shadows the unit
+#[test]
+fn test_evaluate_variable_shadows_unit() {
+    let inputs = ["h = 10", "3 h"];
+    let mut environment = Environment::new();
+    let mut results: Vec<EvalResult> = vec![];
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        results.push(ast.evaluate(&mut environment));
+    }
+    match &results[1] {
+        EvalResult::Value(value) => assert_eq!(value, &Value::Integer(BigInt::from(30))),
+        other => panic!("Expected a value, found {:?}", other),
+    };
+}
+
+// Evaluate bitwise operators on base literals, and returns the exact integer
+#[test]
+fn test_evaluate_bitwise() {
+    let input = "(0xF0 | 0b1010) & ~0o2 xor 1 << 8";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result, Value::Integer(BigInt::from(0x1F8)));
+}
+
+// Evaluate an overflow in a fixed word size, which wraps around
+#[test]
+fn test_evaluate_word_size_wraps() {
+    let inputs = ["127 + 1", "~0"];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for (input, signed) in inputs.into_iter().zip([true, false]) {
+        environment.set_word_size(Some(WordSize { bits: 8, signed }));
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], Value::Integer(BigInt::from(-128)));
+    assert_eq!(results[1], Value::Integer(BigInt::from(255)));
+}
+
+// Evaluate numbers in scientific notation, which are exact
+#[test]
+fn test_evaluate_exponent_literals() {
+    let input = "1.5e-9 * 2E+9";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result, Value::Integer(BigInt::from(3)));
+}
+
+// Evaluate a lambda stored in a variable and called like a function
+#[test]
+fn test_evaluate_lambda() {
+    let input = "f = x -> x^2 + 1; f(3)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result, Value::Integer(BigInt::from(10)));
+}
+
+// Evaluate lambdas that capture variables as they were when the lambda was created
+#[test]
+fn test_evaluate_lambda_captures_variables() {
+    let inputs = [
+        "a = 2; g = x -> a * x; a = 3; g(1)",
+        "add(n) = x -> x + n; h = add(10); h(5)",
+        "curry = x -> y -> x - y; c = curry(10); c(3)",
+        "fact = n -> n <= 1 ? 1 : n * fact(n - 1); fact(5)",
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], Value::Integer(BigInt::from(2)));
+    assert_eq!(results[1], Value::Integer(BigInt::from(15)));
+    assert_eq!(results[2], Value::Integer(BigInt::from(7)));
+    assert_eq!(results[3], Value::Integer(BigInt::from(120)));
+}
+
+// Evaluate lambdas called where they are written or as they are returned, calls on values that
+// are not functions, and a built-in function passed as a value
+#[test]
+fn test_evaluate_lambda_call() {
+    let inputs = [
+        "(x -> x + 1)(2)",
+        "((x, y) -> x * y)(3, 4)",
+        "(() -> 42)()",
+        "adder(a) = x -> x + a; adder(1)(2)",
+        "(1 + 2)(3 + 4)",
+        "map(sin, [0])",
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], Value::Integer(BigInt::from(3)));
+    assert_eq!(results[1], Value::Integer(BigInt::from(12)));
+    assert_eq!(results[2], Value::Integer(BigInt::from(42)));
+    assert_eq!(results[3], Value::Integer(BigInt::from(3)));
+    assert_eq!(results[4], Value::Integer(BigInt::from(21)));
+    assert_eq!(results[5], Value::List(vec![Value::Integer(BigInt::from(0))]));
+}
+
+// Evaluate a call on a parameter holding a lambda, which shadows a function with the same name
+#[test]
+fn test_evaluate_parameter_shadows_function() {
+    let input = "sq(x) = x^2; g(sq) = sq(3); g(x -> 100)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = match ast.evaluate(&mut environment) {
+        EvalResult::Value(value) => value,
+        other => panic!("Expected a value, found {:?}", other),
+    };
+    assert_eq!(result, Value::Integer(BigInt::from(100)));
+}
+
+// Evaluate the higher-order built-ins, with lambdas and a named function
+#[test]
+fn test_evaluate_higher_order_functions() {
+    let inputs = [
+        "sum(k -> k^2, 1, 10)",
+        "sum(k -> 1/k, 1, 4)",
+        "square(x) = x^2; integrate(square, 0, 3)",
+        "solve(x -> x^2 - 2, 1)",
+        "solve(x -> cos(x) - x, 0, 1)",
+        "map(x -> 2x, 21)",
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], Value::Integer(BigInt::from(385)));
+    assert_eq!(results[1], Value::Rational(BigRational::new(25.into(), 12.into())));
+    assert!((results[2].as_number().unwrap() - 9.0).abs() < 1e-10);
+    assert!((results[3].as_number().unwrap() - 2f64.sqrt()).abs() < 1e-12);
+    assert!((results[4].as_number().unwrap() - 0.7390851332151607).abs() < 1e-12);
+    assert_eq!(results[5], Value::Integer(BigInt::from(42)));
+}
+
+// Evaluate a root search on an interval where the function does not change sign
+#[test]
+fn test_evaluate_solve_without_root() {
+    let input = "solve(x -> x^2 + 1, 0, 1)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut environment = Environment::new();
+    let result = ast.evaluate(&mut environment);
+    assert_eq!(result, EvalResult::Error(EvaluationError::NotConverged("solve".to_string()).at(Span::new(0, 25))));
+}
+
+fn integers(values: &[i64]) -> Value {
+    Value::List(values.iter().map(|&value| Value::Integer(BigInt::from(value))).collect())
+}
+
+// Evaluate arithmetic, indexing and slicing on lists
+#[test]
+fn test_evaluate_lists() {
+    let inputs = [
+        "v = [1, 2, 3]; v * 2 + [10, 20, 30]",
+        "v[-1]",
+        "v[1:]",
+        "-v",
+        "map(x -> x^2, v)",
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], integers(&[12, 24, 36]));
+    assert_eq!(results[1], Value::Integer(BigInt::from(3)));
+    assert_eq!(results[2], integers(&[2, 3]));
+    assert_eq!(results[3], integers(&[-1, -2, -3]));
+    assert_eq!(results[4], integers(&[1, 4, 9]));
+}
+
+// Evaluate the functions on lists, and a scalar function applied to each element
+#[test]
+fn test_evaluate_list_functions() {
+    let inputs = [
+        "len([1, 2, 3])",
+        "dot([1, 2, 3], [4, 5, 6])",
+        "cross([1, 0, 0], [0, 1, 0])",
+        "norm([3, 4])",
+        "sum([1, 2, 3])",
+        "max([3, 9, 2])",
+        "sin([0, pi/2])",
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    environment.init_consts();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], Value::Integer(BigInt::from(3)));
+    assert_eq!(results[1], Value::Integer(BigInt::from(32)));
+    assert_eq!(results[2], integers(&[0, 0, 1]));
+    assert_eq!(results[3], Value::Integer(BigInt::from(5)));
+    assert_eq!(results[4], Value::Integer(BigInt::from(6)));
+    assert_eq!(results[5], Value::Integer(BigInt::from(9)));
+    assert_eq!(results[6], Value::List(vec![Value::Number(0.0), Value::Number(1.0)]));
+}
+
+// Evaluate lists of different lengths, and an index past the end of a list
+#[test]
+fn test_evaluate_list_errors() {
+    let inputs = ["[1, 2] + [1, 2, 3]", "cross([1, 2], [3, 4])", "[1, 2][2]"];
+    let mut errors: Vec<EvaluationError> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Error(error) => errors.push(error),
+            other => panic!("Expected an error, found {:?}", other),
+        };
+    }
+    assert_eq!(errors[0], EvaluationError::ShapeMismatch {
+        expected: "[2]".to_string(),
+        found: "[3]".to_string(),
+    }.at(Span::new(0, 18)));
+    assert_eq!(errors[1], EvaluationError::ShapeMismatch {
+        expected: "[3]".to_string(),
+        found: "[2]".to_string(),
+    }.at(Span::new(0, 21)));
+    assert_eq!(errors[2], EvaluationError::IndexOutOfRange { index: BigInt::from(2), length: 2 }.at(Span::new(0, 9)));
+}
+
+fn matrix(rows: &[&[i64]]) -> Value {
+    Value::from_list(rows.iter().map(|row| integers(row)).collect())
+}
+
+// Evaluate matrix literals and the arithmetic on them, where `*` is the matrix product
+#[test]
+fn test_evaluate_matrices() {
+    let inputs = [
+        "A = [1, 2; 3, 4]; A == [[1, 2], [3, 4]]",
+        "A * [5, 6; 7, 8]",
+        "A * [1, 1]",
+        "A^2 - A * A",
+        "A + 1",
+        "A[1][0]",
+        "transpose([1, 2, 3; 4, 5, 6])",
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    assert_eq!(results[0], Value::Boolean(true));
+    assert_eq!(results[1], matrix(&[&[19, 22], &[43, 50]]));
+    assert_eq!(results[2], integers(&[3, 7]));
+    assert_eq!(results[3], matrix(&[&[0, 0], &[0, 0]]));
+    assert_eq!(results[4], matrix(&[&[2, 3], &[4, 5]]));
+    assert_eq!(results[5], Value::Integer(BigInt::from(3)));
+    assert_eq!(results[6], matrix(&[&[1, 4], &[2, 5], &[3, 6]]));
+}
+
+// Evaluate the determinant, inverse, rank, linear systems and eigenvalues, exactly where the
+// elements are exact
+#[test]
+fn test_evaluate_linear_algebra() {
+    let inputs = [
+        "det([1, 2; 3, 4])",
+        "inv([2, 0; 0, 4])",
+        "rank([1, 2, 3; 2, 4, 6; 1, 0, 1])",
+        "solve([2, 1; 1, 3], [3, 5])",
+        "eig([2, 1; 1, 2])",
+        "eig([2, 0, 0; 0, 3, 4; 0, 4, 9])",
+        "eig([0, -1; 1, 0])",
+    ];
+    let mut results: Vec<Value> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Value(value) => results.push(value),
+            other => panic!("Expected a value, found {:?}", other),
+        };
+    }
+    let quarter = Value::Rational(BigRational::new(1.into(), 4.into()));
+    let half = Value::Rational(BigRational::new(1.into(), 2.into()));
+    assert_eq!(results[0], Value::Integer(BigInt::from(-2)));
+    assert_eq!(results[1], Value::from_list(vec![
+        Value::List(vec![half, Value::Integer(BigInt::from(0))]),
+        Value::List(vec![Value::Integer(BigInt::from(0)), quarter]),
+    ]));
+    assert_eq!(results[2], Value::Integer(BigInt::from(2)));
+    assert_eq!(results[3], Value::List(vec![
+        Value::Rational(BigRational::new(4.into(), 5.into())),
+        Value::Rational(BigRational::new(7.into(), 5.into())),
+    ]));
+    assert_eq!(results[4], integers(&[3, 1]));
+    assert_eq!(results[5], integers(&[11, 2, 1]));
+    assert_eq!(results[6], Value::List(vec![
+        Value::Complex(Complex64::new(0.0, 1.0)),
+        Value::Complex(Complex64::new(0.0, -1.0)),
+    ]));
+}
+
+// Evaluate matrices whose shapes do not fit, and a singular matrix
+#[test]
+fn test_evaluate_matrix_errors() {
+    let inputs = ["[1, 2; 3]", "[1, 2; 3, 4] * [1, 2, 3]", "inv([1, 2; 2, 4])", "det([1, 2, 3])"];
+    let mut errors: Vec<EvaluationError> = vec![];
+    let mut environment = Environment::new();
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        let ast = match construct_ast(&tokens) {
+            Ok(result) => result,
+            Err(error) => panic!("ParseError: {:?}", error),
+        };
+        match ast.evaluate(&mut environment) {
+            EvalResult::Error(error) => errors.push(error),
+            other => panic!("Expected an error, found {:?}", other),
+        };
+    }
+    assert_eq!(errors[0], EvaluationError::ShapeMismatch {
+        expected: "[2]".to_string(),
+        found: "[1]".to_string(),
+    }.at(Span::new(0, 9)));
+    assert_eq!(errors[1], EvaluationError::ShapeMismatch {
+        expected: "[2, 1]".to_string(),
+        found: "[3, 1]".to_string(),
+    }.at(Span::new(0, 24)));
+    assert_eq!(errors[2], EvaluationError::SingularMatrix.at(Span::new(0, 17)));
+    assert_eq!(errors[3], EvaluationError::TypeMismatch {
+        expected: "matrix".to_string(),
+        found: "list".to_string(),
+    }.at(Span::new(0, 14)));
+}
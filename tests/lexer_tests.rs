@@ -1,20 +1,22 @@
-use terminal_calculator::lexer::{tokenise, Token, TokenType};
-use terminal_calculator::evaluator::Function;
+use terminal_calculator::lexer::{tokenise, tokenise_in_locale, tokenise_with_errors, Span, Token, TokenType};
+use terminal_calculator::errors::LexerError;
+use terminal_calculator::locale::Locale;
+use terminal_calculator::evaluator::{AngleMode, Function};
 
 // Tokenises a basic input
 #[test]
 fn test_tokeniser_basic() {
     let input = "3 + 5 * (2 - 8)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "3".to_string()},
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "5".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Subtraction, lexeme: "-".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "8".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(8, 9) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(9, 10) },
+        Token { token_type: TokenType::Subtraction, lexeme: "-".to_string(), span: Span::new(11, 12) },
+        Token { token_type: TokenType::Number, lexeme: "8".to_string(), span: Span::new(13, 14) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(14, 15) },
     ];
 
     let tokens = match tokenise(input.to_string()) {
@@ -30,7 +32,7 @@ fn test_tokeniser_basic() {
 fn test_tokeniser_single_number() {
     let input = "42";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "42".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "42".to_string(), span: Span::new(0, 2) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -44,13 +46,13 @@ fn test_tokeniser_single_number() {
 fn test_tokeniser_with_implicit_multiplication() {
     let input = "3(4 + 5)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "4".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "5".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Number, lexeme: "4".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(7, 8) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -63,10 +65,10 @@ fn test_tokeniser_with_implicit_multiplication() {
 fn test_tokeniser_function() {
     let input = "sin(2)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(0, 3) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(5, 6) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -79,12 +81,12 @@ fn test_tokeniser_function() {
 fn test_tokeniser_function_with_implicit_multiplication() {
     let input = "2cos(0)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Cos), lexeme: "cos".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "0".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Keyword(Function::Cos), lexeme: "cos".to_string(), span: Span::new(1, 4) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "0".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(6, 7) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -97,9 +99,9 @@ fn test_tokeniser_function_with_implicit_multiplication() {
 fn test_tokeniser_identifier_with_implicit_multiplication() {
     let input = "2x";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "x".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(1, 2) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -110,7 +112,7 @@ fn test_tokeniser_identifier_with_implicit_multiplication() {
 
 // Tokenises an expression with an unexpected token and returns an error.
 #[test]
-#[should_panic(expected = "LexerError: InvalidIdentifier(\"@\")")]
+#[should_panic(expected = "LexerError: [InvalidIdentifier(\"@\", Span { start: 6, end: 7 })]")]
 fn test_tokenise_expression_unexpected_token() {
     let input = "3 + 5 @ 2";
     let _tokens = match tokenise(input.to_string()) {
@@ -123,10 +125,10 @@ fn test_tokenise_expression_unexpected_token() {
 fn test_tokenise_function() {
     let input = "sin(3)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(0, 3) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(5, 6) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -139,13 +141,13 @@ fn test_tokenise_function() {
 fn test_tokenise_nested_functions() {
     let input = "sin(cos(3))";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Cos), lexeme: "cos".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(0, 3) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Keyword(Function::Cos), lexeme: "cos".to_string(), span: Span::new(4, 7) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(8, 9) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(9, 10) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(10, 11) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -158,12 +160,12 @@ fn test_tokenise_nested_functions() {
 fn test_tokenise_function_with_implicit_multiplication() {
     let input = "2sin(3)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(1, 4) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(6, 7) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -176,11 +178,11 @@ fn test_tokenise_function_with_implicit_multiplication() {
 fn test_tokenise_function_with_negation() {
     let input = "-sin(3)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Negation, lexeme: "-".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Negation, lexeme: "-".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(1, 4) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(6, 7) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -193,13 +195,13 @@ fn test_tokenise_function_with_negation() {
 fn test_tokenise_function_with_implicit_multiplication_and_negation() {
     let input = "-2sin(3)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Negation, lexeme: "-".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Negation, lexeme: "-".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(2, 2) },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(2, 5) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(7, 8) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -212,15 +214,15 @@ fn test_tokenise_function_with_implicit_multiplication_and_negation() {
 fn test_tokenise_function_with_implicit_multiplication_and_negation_with_parentheses() {
     let input = "-2(sin(3))";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Negation, lexeme: "-".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Negation, lexeme: "-".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(2, 2) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(3, 6) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(8, 9) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(9, 10) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -233,17 +235,17 @@ fn test_tokenise_function_with_implicit_multiplication_and_negation_with_parenth
 fn test_tokenise_function_with_implicit_multiplication_and_negation_with_parentheses_and_addition() {
     let input = "-2(sin(3) + 5)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Negation, lexeme: "-".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "5".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Negation, lexeme: "-".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(2, 2) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(3, 6) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(8, 9) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(10, 11) },
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(12, 13) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(13, 14) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -256,9 +258,9 @@ fn test_tokenise_function_with_implicit_multiplication_and_negation_with_parenth
 fn test_tokenise_unicode_identifier() {
     let input = "π + 5";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Identifier, lexeme: "π".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "5".to_string() },
+        Token { token_type: TokenType::Identifier, lexeme: "π".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(4, 5) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -271,9 +273,9 @@ fn test_tokenise_unicode_identifier() {
 fn test_tokenise_identifiers_in_operations() {
     let input = "x + y";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Identifier, lexeme: "x".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "y".to_string() },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Identifier, lexeme: "y".to_string(), span: Span::new(4, 5) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -286,13 +288,13 @@ fn test_tokenise_identifiers_in_operations() {
 fn test_tokenise_identifier_with_implicit_multiplication() {
     let input = "2x + 3y";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "x".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "y".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(6, 6) },
+        Token { token_type: TokenType::Identifier, lexeme: "y".to_string(), span: Span::new(6, 7) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -305,13 +307,13 @@ fn test_tokenise_identifier_with_implicit_multiplication() {
 fn test_tokenise_identifier_with_implicit_multiplication_and_parentheses() {
     let input = "2(x + y)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "x".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "y".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Identifier, lexeme: "y".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(7, 8) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -324,16 +326,16 @@ fn test_tokenise_identifier_with_implicit_multiplication_and_parentheses() {
 fn test_tokenise_identifier_with_implicit_multiplication_and_parentheses_and_function() {
     let input = "2(sin(x + y))";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "x".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "y".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Keyword(Function::Sin), lexeme: "sin".to_string(), span: Span::new(2, 5) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(8, 9) },
+        Token { token_type: TokenType::Identifier, lexeme: "y".to_string(), span: Span::new(10, 11) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(11, 12) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(12, 13) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -346,9 +348,9 @@ fn test_tokenise_identifier_with_implicit_multiplication_and_parentheses_and_fun
 fn test_tokenise_identifiers_with_underscore_in_operations() {
     let input = "x_1 + y_2";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Identifier, lexeme: "x_1".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "y_2".to_string() },
+        Token { token_type: TokenType::Identifier, lexeme: "x_1".to_string(), span: Span::new(0, 3) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Identifier, lexeme: "y_2".to_string(), span: Span::new(6, 9) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -361,13 +363,13 @@ fn test_tokenise_identifiers_with_underscore_in_operations() {
 fn test_tokenise_identifiers_with_underscore_with_implicit_multiplication() {
     let input = "2x_1 + 3y_2";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "x_1".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "y_2".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Identifier, lexeme: "x_1".to_string(), span: Span::new(1, 4) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(8, 8) },
+        Token { token_type: TokenType::Identifier, lexeme: "y_2".to_string(), span: Span::new(8, 11) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -380,13 +382,13 @@ fn test_tokenise_identifiers_with_underscore_with_implicit_multiplication() {
 fn test_tokenise_identifiers_with_underscore_with_implicit_multiplication_and_parentheses() {
     let input = "2(x_1 + y_2)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "x_1".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "y_2".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Identifier, lexeme: "x_1".to_string(), span: Span::new(2, 5) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Identifier, lexeme: "y_2".to_string(), span: Span::new(8, 11) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(11, 12) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -399,9 +401,9 @@ fn test_tokenise_identifiers_with_underscore_with_implicit_multiplication_and_pa
 fn test_tokenise_identifiers_beginning_with_underscore() {
     let input = "_x + _y";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Identifier, lexeme: "_x".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "_y".to_string() },
+        Token { token_type: TokenType::Identifier, lexeme: "_x".to_string(), span: Span::new(0, 2) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Identifier, lexeme: "_y".to_string(), span: Span::new(5, 7) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -414,13 +416,13 @@ fn test_tokenise_identifiers_beginning_with_underscore() {
 fn test_tokenise_identifiers_beginning_with_underscore_with_implicit_multiplication() {
     let input = "2_x + 3_y";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "_x".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "_y".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Identifier, lexeme: "_x".to_string(), span: Span::new(1, 3) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(7, 7) },
+        Token { token_type: TokenType::Identifier, lexeme: "_y".to_string(), span: Span::new(7, 9) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -433,13 +435,13 @@ fn test_tokenise_identifiers_beginning_with_underscore_with_implicit_multiplicat
 fn test_tokenise_identifiers_beginning_with_underscore_with_implicit_multiplication_and_parentheses() {
     let input = "2(_x + _y)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "_x".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "_y".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Identifier, lexeme: "_x".to_string(), span: Span::new(2, 4) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Identifier, lexeme: "_y".to_string(), span: Span::new(7, 9) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(9, 10) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -448,19 +450,19 @@ fn test_tokenise_identifiers_beginning_with_underscore_with_implicit_multiplicat
     assert_eq!(tokens, expected_tokens);
 }
 
+// The parser decides whether `_(...)` is a function call or implicit multiplication
 #[test]
 fn test_underscore_outside_parenthesis() {
     let input = "2_(3 + 5)";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Identifier, lexeme: "_".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "3".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "5".to_string() },
-        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Identifier, lexeme: "_".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(8, 9) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -473,9 +475,9 @@ fn test_underscore_outside_parenthesis() {
 fn test_tokenise_decimal_number() {
     let input = "3.14 + 2.71";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "3.14".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2.71".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "3.14".to_string(), span: Span::new(0, 4) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Number, lexeme: "2.71".to_string(), span: Span::new(7, 11) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -488,9 +490,9 @@ fn test_tokenise_decimal_number() {
 fn test_tokenise_decimal_leading_dot() {
     let input = ".5 + 2";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: ".5".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
+        Token { token_type: TokenType::Number, lexeme: ".5".to_string(), span: Span::new(0, 2) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(5, 6) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -503,9 +505,9 @@ fn test_tokenise_decimal_leading_dot() {
 fn test_tokenise_decimal_trailing_dot() {
     let input = "3. + 2";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "3.".to_string() },
-        Token { token_type: TokenType::Addition, lexeme: "+".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "3.".to_string(), span: Span::new(0, 2) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(5, 6) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
@@ -518,15 +520,421 @@ fn test_tokenise_decimal_trailing_dot() {
 fn test_tokenise_decimal_with_exponent() {
     let input = "3.14*10^2";
     let expected_tokens = vec![
-        Token { token_type: TokenType::Number, lexeme: "3.14".to_string() },
-        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "10".to_string() },
-        Token { token_type: TokenType::Exponentiation, lexeme: "^".to_string() },
-        Token { token_type: TokenType::Number, lexeme: "2".to_string() },
+        Token { token_type: TokenType::Number, lexeme: "3.14".to_string(), span: Span::new(0, 4) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "10".to_string(), span: Span::new(5, 7) },
+        Token { token_type: TokenType::Exponentiation, lexeme: "^".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(8, 9) },
     ];
     let tokens = match tokenise(input.to_string()) {
         Ok(result) => result,
         Err(error) => panic!("LexerError: {:?}", error),
     };
     assert_eq!(tokens, expected_tokens);
-}
\ No newline at end of file
+}
+#[test]
+fn test_tokenise_factorial() {
+    let input = "5! - 3!!";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Exclamation, lexeme: "!".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Subtraction, lexeme: "-".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::DoubleExclamation, lexeme: "!!".to_string(), span: Span::new(6, 8) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+#[test]
+fn test_tokenise_subtraction_after_parenthesis() {
+    let input = "(1) - 2";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Number, lexeme: "1".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Subtraction, lexeme: "-".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(6, 7) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+#[test]
+fn test_tokenise_comparison_and_conditional() {
+    let input = "x <= 0 ? -x : x";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::LessThanOrEqual, lexeme: "<=".to_string(), span: Span::new(2, 4) },
+        Token { token_type: TokenType::Number, lexeme: "0".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Question, lexeme: "?".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::Negation, lexeme: "-".to_string(), span: Span::new(9, 10) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(10, 11) },
+        Token { token_type: TokenType::Colon, lexeme: ":".to_string(), span: Span::new(12, 13) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(14, 15) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+#[test]
+fn test_tokenise_negation_after_assignment() {
+    let input = "x = -4";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Equals, lexeme: "=".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Negation, lexeme: "-".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "4".to_string(), span: Span::new(5, 6) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+#[test]
+fn test_tokenise_logical_operators() {
+    let input = "not a == b && c != d || true";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Not, lexeme: "not".to_string(), span: Span::new(0, 3) },
+        Token { token_type: TokenType::Identifier, lexeme: "a".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Equality, lexeme: "==".to_string(), span: Span::new(6, 8) },
+        Token { token_type: TokenType::Identifier, lexeme: "b".to_string(), span: Span::new(9, 10) },
+        Token { token_type: TokenType::And, lexeme: "&&".to_string(), span: Span::new(11, 13) },
+        Token { token_type: TokenType::Identifier, lexeme: "c".to_string(), span: Span::new(14, 15) },
+        Token { token_type: TokenType::NotEqual, lexeme: "!=".to_string(), span: Span::new(16, 18) },
+        Token { token_type: TokenType::Identifier, lexeme: "d".to_string(), span: Span::new(19, 20) },
+        Token { token_type: TokenType::Or, lexeme: "||".to_string(), span: Span::new(21, 23) },
+        Token { token_type: TokenType::Boolean, lexeme: "true".to_string(), span: Span::new(24, 28) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+#[test]
+fn test_tokenise_angle_units() {
+    let input = "30° - 2rad";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "30".to_string(), span: Span::new(0, 2) },
+        Token { token_type: TokenType::AngleUnit(AngleMode::Degrees), lexeme: "°".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Subtraction, lexeme: "-".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::AngleUnit(AngleMode::Radians), lexeme: "rad".to_string(), span: Span::new(7, 10) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise a complex number, where `3i` is split into a multiplication by the imaginary unit
+#[test]
+fn test_tokenise_imaginary_unit() {
+    let input = "2+3i - i";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(3, 3) },
+        Token { token_type: TokenType::ImaginaryUnit, lexeme: "i".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Subtraction, lexeme: "-".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::ImaginaryUnit, lexeme: "i".to_string(), span: Span::new(7, 8) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise a unit conversion, where unit names after numbers and units are units
+#[test]
+fn test_tokenise_units() {
+    let input = "5 km/h to m/s";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Unit, lexeme: "km".to_string(), span: Span::new(2, 4) },
+        Token { token_type: TokenType::Division, lexeme: "/".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Unit, lexeme: "h".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Conversion, lexeme: "to".to_string(), span: Span::new(7, 9) },
+        Token { token_type: TokenType::Unit, lexeme: "m".to_string(), span: Span::new(10, 11) },
+        Token { token_type: TokenType::Division, lexeme: "/".to_string(), span: Span::new(11, 12) },
+        Token { token_type: TokenType::Unit, lexeme: "s".to_string(), span: Span::new(12, 13) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise unit names after a parenthesis and a variable, which are units too
+#[test]
+fn test_tokenise_units_after_operands() {
+    let input = "(2) m + x s";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Unit, lexeme: "m".to_string(), span: Span::new(4, 5) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(8, 9) },
+        Token { token_type: TokenType::Unit, lexeme: "s".to_string(), span: Span::new(10, 11) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise a unit name followed by a parenthesis, which is a function call and not a unit
+#[test]
+fn test_tokenise_unit_name_called() {
+    let input = "2g(3)";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(1, 1) },
+        Token { token_type: TokenType::Identifier, lexeme: "g".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::Number, lexeme: "3".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(4, 5) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise base literals and bitwise operators, where a single `&` or `|` is bitwise
+#[test]
+fn test_tokenise_bitwise() {
+    let input = "0xFF & ~0b10 | 0o7 << 2 xor x";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "0xFF".to_string(), span: Span::new(0, 4) },
+        Token { token_type: TokenType::BitwiseAnd, lexeme: "&".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::BitwiseNot, lexeme: "~".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::Number, lexeme: "0b10".to_string(), span: Span::new(8, 12) },
+        Token { token_type: TokenType::BitwiseOr, lexeme: "|".to_string(), span: Span::new(13, 14) },
+        Token { token_type: TokenType::Number, lexeme: "0o7".to_string(), span: Span::new(15, 18) },
+        Token { token_type: TokenType::ShiftLeft, lexeme: "<<".to_string(), span: Span::new(19, 21) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(22, 23) },
+        Token { token_type: TokenType::BitwiseXor, lexeme: "xor".to_string(), span: Span::new(24, 27) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(28, 29) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise numbers in scientific notation, where a lone `e` is still the constant
+#[test]
+fn test_tokenise_exponent_literals() {
+    let input = "6.022e23 * 1.5E-9 - 2e";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "6.022e23".to_string(), span: Span::new(0, 8) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(9, 10) },
+        Token { token_type: TokenType::Number, lexeme: "1.5E-9".to_string(), span: Span::new(11, 17) },
+        Token { token_type: TokenType::Subtraction, lexeme: "-".to_string(), span: Span::new(18, 19) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(20, 21) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(21, 21) },
+        Token { token_type: TokenType::Identifier, lexeme: "e".to_string(), span: Span::new(21, 22) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise numbers with underscores between their digits
+#[test]
+fn test_tokenise_digit_separators() {
+    let input = "1_000_000 + 0xFF_FF";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "1_000_000".to_string(), span: Span::new(0, 9) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(10, 11) },
+        Token { token_type: TokenType::Number, lexeme: "0xFF_FF".to_string(), span: Span::new(12, 19) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenise numbers in the US and European formats, where a comma followed by a space
+// still separates arguments
+#[test]
+fn test_tokenise_locale() {
+    let inputs = [("max(1,234.5, 6)", Locale::Us), ("max(1.234,5, 6)", Locale::Eu)];
+    for (input, locale) in inputs {
+        let expected_tokens = vec![
+            Token { token_type: TokenType::Keyword(Function::Max), lexeme: "max".to_string(), span: Span::new(0, 3) },
+            Token { token_type: TokenType::LeftParenthesis, lexeme: "(".to_string(), span: Span::new(3, 4) },
+            Token { token_type: TokenType::Number, lexeme: "1234.5".to_string(), span: Span::new(4, 11) },
+            Token { token_type: TokenType::Comma, lexeme: ",".to_string(), span: Span::new(11, 12) },
+            Token { token_type: TokenType::Number, lexeme: "6".to_string(), span: Span::new(13, 14) },
+            Token { token_type: TokenType::RightParenthesis, lexeme: ")".to_string(), span: Span::new(14, 15) },
+        ];
+        let tokens = match tokenise_in_locale(input.to_string(), locale) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        assert_eq!(tokens, expected_tokens);
+    }
+}
+
+// Tokenises an input with implicit multiplication, where the added token has an empty span
+// at the place it was added
+#[test]
+fn test_tokenise_spans() {
+    let input = "2πr ^ 2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let spans: Vec<Span> = tokens.iter().map(|token| token.span).collect();
+    assert_eq!(spans, vec![
+        Span::new(0, 1),
+        Span::new(1, 1),
+        Span::new(1, 3),
+        Span::new(4, 5),
+        Span::new(6, 7),
+    ]);
+    assert!(tokens[1].is_implicit());
+    assert!(!tokens[0].is_implicit());
+}
+
+// Tokenises an input with several problems, and returns every one of them
+#[test]
+fn test_tokenise_reports_every_error() {
+    let input = "3 @ 2 + 4$ - #";
+    let errors = match tokenise(input.to_string()) {
+        Ok(result) => panic!("Expected errors, found {:?}", result),
+        Err(errors) => errors,
+    };
+    assert_eq!(errors, vec![
+        LexerError::InvalidIdentifier("@".to_string(), Span::new(2, 3)),
+        LexerError::InvalidIdentifier("$".to_string(), Span::new(9, 10)),
+        LexerError::InvalidIdentifier("#".to_string(), Span::new(13, 14)),
+    ]);
+}
+
+// Tokenises past a part that cannot be read, which is left as an invalid token
+#[test]
+fn test_tokenise_with_errors_recovers() {
+    let input = "2@ + 1.5";
+    let (tokens, errors) = tokenise_with_errors(input.to_string(), Locale::Eu);
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Invalid, lexeme: "@".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Invalid, lexeme: "1.".to_string(), span: Span::new(5, 7) },
+        Token { token_type: TokenType::Number, lexeme: "5".to_string(), span: Span::new(7, 8) },
+    ];
+    assert_eq!(tokens, expected_tokens);
+    assert_eq!(errors, vec![
+        LexerError::InvalidIdentifier("@".to_string(), Span::new(1, 2)),
+        LexerError::InvalidToken("1.".to_string(), Span::new(5, 7)),
+    ]);
+}
+
+// Tokenises base prefixes without valid digits after them, which are invalid tokens
+#[test]
+fn test_tokenise_malformed_base_literals() {
+    let input = "0b102 + 0xG + 0x + 0x_FF";
+    let (tokens, errors) = tokenise_with_errors(input.to_string(), Locale::Plain);
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Invalid, lexeme: "0b102".to_string(), span: Span::new(0, 5) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Invalid, lexeme: "0xG".to_string(), span: Span::new(8, 11) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(12, 13) },
+        Token { token_type: TokenType::Invalid, lexeme: "0x".to_string(), span: Span::new(14, 16) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(17, 18) },
+        Token { token_type: TokenType::Invalid, lexeme: "0x_FF".to_string(), span: Span::new(19, 24) },
+    ];
+    assert_eq!(tokens, expected_tokens);
+    assert_eq!(errors, vec![
+        LexerError::InvalidToken("0b102".to_string(), Span::new(0, 5)),
+        LexerError::InvalidToken("0xG".to_string(), Span::new(8, 11)),
+        LexerError::InvalidToken("0x".to_string(), Span::new(14, 16)),
+        LexerError::InvalidToken("0x_FF".to_string(), Span::new(19, 24)),
+    ]);
+}
+
+// Tokenises digit separators that are doubled, trailing or start an exponent, which are invalid
+#[test]
+fn test_tokenise_misplaced_digit_separators() {
+    let input = "1__0 + 1_ + 1e_3";
+    let (tokens, errors) = tokenise_with_errors(input.to_string(), Locale::Plain);
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Invalid, lexeme: "1__0".to_string(), span: Span::new(0, 4) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Invalid, lexeme: "1_".to_string(), span: Span::new(7, 9) },
+        Token { token_type: TokenType::Addition, lexeme: "+".to_string(), span: Span::new(10, 11) },
+        Token { token_type: TokenType::Invalid, lexeme: "1e_3".to_string(), span: Span::new(12, 16) },
+    ];
+    assert_eq!(tokens, expected_tokens);
+    assert_eq!(errors, vec![
+        LexerError::InvalidToken("1__0".to_string(), Span::new(0, 4)),
+        LexerError::InvalidToken("1_".to_string(), Span::new(7, 9)),
+        LexerError::InvalidToken("1e_3".to_string(), Span::new(12, 16)),
+    ]);
+}
+
+// Tokenises an arrow, which is never a subtraction or negation, even after an operand
+#[test]
+fn test_tokenise_arrow() {
+    let input = "x->-x";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::Arrow, lexeme: "->".to_string(), span: Span::new(1, 3) },
+        Token { token_type: TokenType::Negation, lexeme: "-".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Identifier, lexeme: "x".to_string(), span: Span::new(4, 5) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
+
+// Tokenises a bracket after a name as an index, and after a number as implicit multiplication
+#[test]
+fn test_tokenise_brackets() {
+    let input = "v[0] 2[1]";
+    let expected_tokens = vec![
+        Token { token_type: TokenType::Identifier, lexeme: "v".to_string(), span: Span::new(0, 1) },
+        Token { token_type: TokenType::LeftBracket, lexeme: "[".to_string(), span: Span::new(1, 2) },
+        Token { token_type: TokenType::Number, lexeme: "0".to_string(), span: Span::new(2, 3) },
+        Token { token_type: TokenType::RightBracket, lexeme: "]".to_string(), span: Span::new(3, 4) },
+        Token { token_type: TokenType::Number, lexeme: "2".to_string(), span: Span::new(5, 6) },
+        Token { token_type: TokenType::Multiplication, lexeme: "*".to_string(), span: Span::new(6, 6) },
+        Token { token_type: TokenType::LeftBracket, lexeme: "[".to_string(), span: Span::new(6, 7) },
+        Token { token_type: TokenType::Number, lexeme: "1".to_string(), span: Span::new(7, 8) },
+        Token { token_type: TokenType::RightBracket, lexeme: "]".to_string(), span: Span::new(8, 9) },
+    ];
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    assert_eq!(tokens, expected_tokens);
+}
@@ -1,7 +1,8 @@
 use terminal_calculator::parser::{construct_ast, AstNode};
-use terminal_calculator::lexer::{tokenise, TokenType};
+use terminal_calculator::lexer::{tokenise, Span, TokenType};
 use terminal_calculator::evaluator::Function;
 use terminal_calculator::errors::ParseError;
+use num_rational::BigRational;
 
 // Parses a basic expression
 #[test]
@@ -17,8 +18,9 @@ fn test_parse_expression_basic() {
     };
     assert_eq!(ast, AstNode::BinaryOp {
         operator: TokenType::Addition,
-        operand_1: Box::new(AstNode::Number(3.0)),
-        operand_2: Box::new(AstNode::Number(5.0)),
+        operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+        operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+        span: Span::new(0, 5),
     });
 }
 
@@ -34,7 +36,7 @@ fn test_parse_single_number() {
         Ok(result) => result,
         Err(error) => panic!("ParseError: {:?}", error),
     };
-    assert_eq!(ast, AstNode::Number(42.0));
+    assert_eq!(ast, AstNode::Number(BigRational::from_integer(42.into())));
 }
 
 // Parses an expression containing operators of different precedence
@@ -53,18 +55,22 @@ fn test_parse_expression_precedence() {
         operator: TokenType::Subtraction,
         operand_1: Box::new(AstNode::BinaryOp {
             operator: TokenType::Addition,
-            operand_1: Box::new(AstNode::Number(3.0)),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
             operand_2: Box::new(AstNode::BinaryOp {
                 operator: TokenType::Multiplication,
-                operand_1: Box::new(AstNode::Number(5.0)),
-                operand_2: Box::new(AstNode::Number(2.0)),
+                operand_1: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                span: Span::new(4, 9),
             }),
+            span: Span::new(0, 9),
         }),
         operand_2: Box::new(AstNode::BinaryOp {
             operator: TokenType::Division,
-            operand_1: Box::new(AstNode::Number(8.0)),
-            operand_2: Box::new(AstNode::Number(4.0)),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(8.into()))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(4.into()))),
+            span: Span::new(12, 17),
         }),
+        span: Span::new(0, 17),
     });
 }
 
@@ -82,16 +88,19 @@ fn test_parse_expression_with_parentheses() {
     };
     assert_eq!(ast, AstNode::BinaryOp {
         operator: TokenType::Addition,
-        operand_1: Box::new(AstNode::Number(3.0)),
+        operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
         operand_2: Box::new(AstNode::BinaryOp {
             operator: TokenType::Multiplication,
-            operand_1: Box::new(AstNode::Number(5.0)),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
             operand_2: Box::new(AstNode::BinaryOp {
                 operator: TokenType::Subtraction,
-                operand_1: Box::new(AstNode::Number(2.0)),
-                operand_2: Box::new(AstNode::Number(8.0)),
+                operand_1: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(8.into()))),
+                span: Span::new(9, 14),
             }),
+            span: Span::new(4, 15),
         }),
+        span: Span::new(0, 15),
     });
 }
 
@@ -109,12 +118,14 @@ fn test_parse_expression_with_implicit_multiplication() {
     };
     assert_eq!(ast, AstNode::BinaryOp {
         operator: TokenType::Multiplication,
-        operand_1: Box::new(AstNode::Number(3.0)),
+        operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
         operand_2: Box::new(AstNode::BinaryOp {
             operator: TokenType::Addition,
-            operand_1: Box::new(AstNode::Number(4.0)),
-            operand_2: Box::new(AstNode::Number(5.0)),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(4.into()))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+            span: Span::new(2, 7),
         }),
+        span: Span::new(0, 8),
     });
 }
 
@@ -134,9 +145,11 @@ fn test_parse_expression_with_unary_operator() {
         operator: TokenType::Addition,
         operand_1: Box::new(AstNode::UnaryOp {
             operator: TokenType::Negation,
-            operand: Box::new(AstNode::Number(3.0)),
+            operand: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+            span: Span::new(0, 2),
         }),
-        operand_2: Box::new(AstNode::Number(5.0)),
+        operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+        span: Span::new(0, 6),
     });
 }
 
@@ -156,9 +169,11 @@ fn test_parse_expression_with_unary_operator_and_parentheses() {
         operator: TokenType::Negation,
         operand: Box::new(AstNode::BinaryOp {
             operator: TokenType::Addition,
-            operand_1: Box::new(AstNode::Number(3.0)),
-            operand_2: Box::new(AstNode::Number(5.0)),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+            span: Span::new(2, 7),
         }),
+        span: Span::new(0, 8),
     });
 }
 
@@ -176,12 +191,14 @@ fn test_parse_expression_with_exponentiation() {
     };
     assert_eq!(ast, AstNode::BinaryOp {
         operator: TokenType::Exponentiation,
-        operand_1: Box::new(AstNode::Number(2.0)),
+        operand_1: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
         operand_2: Box::new(AstNode::BinaryOp {
             operator: TokenType::Exponentiation,
-            operand_1: Box::new(AstNode::Number(3.0)),
-            operand_2: Box::new(AstNode::Number(2.0)),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+            span: Span::new(4, 9),
         }),
+        span: Span::new(0, 9),
     });
 }
 
@@ -195,7 +212,10 @@ fn test_parse_expression_missing_token_at_end() {
     };
     let ast = construct_ast(&tokens);
     assert!(ast.is_err());
-    assert_eq!(ast.unwrap_err(), ParseError::UnexpectedEndOfInput);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::UnexpectedEndOfInput {
+        expected: "an operand after `+`".to_string(),
+        span: Span::new(3, 3),
+    }]);
 }
 
 // Parses an expression missing a closing parenthesis, and returns an error
@@ -208,7 +228,10 @@ fn test_parse_expression_missing_closing_parenthesis() {
     };
     let ast = construct_ast(&tokens);
     assert!(ast.is_err());
-    assert_eq!(ast.unwrap_err(), ParseError::MissingClosingParenthesis);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::MissingClosingParenthesis {
+        opening: Span::new(4, 5),
+        found: Span::new(10, 10),
+    }]);
 }
 
 #[test] 
@@ -224,7 +247,8 @@ fn test_parse_expression_with_builtin_function() {
     };
     assert_eq!(ast, AstNode::Function {
         function: TokenType::Keyword(Function::Sin),
-        args: Box::new(AstNode::Number(3.14)),
+        args: vec![AstNode::Number(BigRational::new(314.into(), 100.into()))],
+        span: Span::new(0, 9),
     });
 }
 
@@ -243,8 +267,10 @@ fn test_parse_expression_with_negation_and_builtin_function() {
         operator: TokenType::Negation,
         operand: Box::new(AstNode::Function {
             function: TokenType::Keyword(Function::Sin),
-            args: Box::new(AstNode::Number(3.14)),
+            args: vec![AstNode::Number(BigRational::new(314.into(), 100.into()))],
+            span: Span::new(1, 10),
         }),
+        span: Span::new(0, 10),
     });
 }
 
@@ -261,11 +287,13 @@ fn test_parse_expression_with_function_and_parentheses() {
     };
     assert_eq!(ast, AstNode::Function {
         function: TokenType::Keyword(Function::Sin),
-        args: Box::new(AstNode::BinaryOp {
+        args: vec![AstNode::BinaryOp {
             operator: TokenType::Addition,
-            operand_1: Box::new(AstNode::Number(3.0)),
-            operand_2: Box::new(AstNode::Number(5.0)),
-        }),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+            span: Span::new(4, 9),
+        }],
+        span: Span::new(0, 10),
     });
 }
 
@@ -284,13 +312,16 @@ fn test_parse_expression_with_function_and_implicit_multiplication() {
         operator: TokenType::Multiplication,
         operand_1: Box::new(AstNode::Function {
             function: TokenType::Keyword(Function::Sin),
-            args: Box::new(AstNode::Number(3.0)),
+            args: vec![AstNode::Number(BigRational::from_integer(3.into()))],
+            span: Span::new(0, 6),
         }),
         operand_2: Box::new(AstNode::BinaryOp {
             operator: TokenType::Addition,
-            operand_1: Box::new(AstNode::Number(4.0)),
-            operand_2: Box::new(AstNode::Number(5.0)),
+            operand_1: Box::new(AstNode::Number(BigRational::from_integer(4.into()))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+            span: Span::new(7, 12),
         }),
+        span: Span::new(0, 13),
     });
 }
 
@@ -307,10 +338,12 @@ fn test_parse_expression_with_function_and_unary_operator() {
     };
     assert_eq!(ast, AstNode::Function {
         function: TokenType::Keyword(Function::Sin),
-        args: Box::new(AstNode::UnaryOp {
+        args: vec![AstNode::UnaryOp {
             operator: TokenType::Negation,
-            operand: Box::new(AstNode::Number(3.0)),
-        }),
+            operand: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+            span: Span::new(4, 6),
+        }],
+        span: Span::new(0, 7),
     });
 }
 
@@ -327,14 +360,17 @@ fn test_parse_expression_with_function_and_unary_operator_and_parentheses() {
     };
     assert_eq!(ast, AstNode::Function {
         function: TokenType::Keyword(Function::Sin),
-        args: Box::new(AstNode::UnaryOp {
+        args: vec![AstNode::UnaryOp {
             operator: TokenType::Negation,
             operand: Box::new(AstNode::BinaryOp {
                 operator: TokenType::Addition,
-                operand_1: Box::new(AstNode::Number(3.0)),
-                operand_2: Box::new(AstNode::Number(5.0)),
+                operand_1: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(5.into()))),
+                span: Span::new(6, 11),
             }),
-        }),
+            span: Span::new(4, 12),
+        }],
+        span: Span::new(0, 13),
     });
 }
 
@@ -351,9 +387,557 @@ fn test_parse_expression_with_nested_functions() {
     };
     assert_eq!(ast, AstNode::Function {
         function: TokenType::Keyword(Function::Sin),
-        args: Box::new(AstNode::Function {
+        args: vec![AstNode::Function {
             function: TokenType::Keyword(Function::Cos),
-            args: Box::new(AstNode::Number(3.0)),
+            args: vec![AstNode::Number(BigRational::from_integer(3.into()))],
+            span: Span::new(4, 10),
+        }],
+        span: Span::new(0, 11),
+    });
+}
+#[test]
+fn test_parse_expression_with_multiple_function_arguments() {
+    let input = "sin(1, 2 + 3, x)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Function {
+        function: TokenType::Keyword(Function::Sin),
+        args: vec![
+            AstNode::Number(BigRational::from_integer(1.into())),
+            AstNode::BinaryOp {
+                operator: TokenType::Addition,
+                operand_1: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+                span: Span::new(7, 12),
+            },
+            AstNode::Variable("x".to_string(), Span::new(14, 15)),
+        ],
+        span: Span::new(0, 16),
+    });
+}
+
+// Parses a function call whose argument list is never closed, and returns an error
+#[test]
+fn test_parse_function_arguments_missing_closing_parenthesis() {
+    let input = "sin(1, 2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::MissingClosingParenthesis {
+        opening: Span::new(3, 4),
+        found: Span::new(8, 8),
+    }]);
+}
+
+// Parses semicolon-separated statements into a sequence
+#[test]
+fn test_parse_statement_sequence() {
+    let input = "r = 3; r * 2;";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Sequence(vec![
+        AstNode::Assignment {
+            name: "r".to_string(),
+            value: Box::new(AstNode::Number(BigRational::from_integer(3.into()))),
+            span: Span::new(0, 5),
+        },
+        AstNode::BinaryOp {
+            operator: TokenType::Multiplication,
+            operand_1: Box::new(AstNode::Variable("r".to_string(), Span::new(7, 8))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+            span: Span::new(7, 12),
+        },
+    ]));
+}
+
+// Parses an empty statement between semicolons, and returns an error
+#[test]
+fn test_parse_empty_statement() {
+    let input = "1;;2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::UnexpectedToken {
+        expected: "an expression after `;`".to_string(),
+        found: ";".to_string(),
+        span: Span::new(2, 3),
+    }]);
+}
+
+// Parses a function definition with several parameters
+#[test]
+fn test_parse_function_definition() {
+    let input = "f(x, y) = x^2 + y";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::FunctionDefinition {
+        name: "f".to_string(),
+        params: vec!["x".to_string(), "y".to_string()],
+        body: Box::new(AstNode::BinaryOp {
+            operator: TokenType::Addition,
+            operand_1: Box::new(AstNode::BinaryOp {
+                operator: TokenType::Exponentiation,
+                operand_1: Box::new(AstNode::Variable("x".to_string(), Span::new(10, 11))),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(2.into()))),
+                span: Span::new(10, 13),
+            }),
+            operand_2: Box::new(AstNode::Variable("y".to_string(), Span::new(16, 17))),
+            span: Span::new(10, 17),
+        }),
+        span: Span::new(0, 17),
+    });
+}
+
+// Parses an identifier followed by parentheses as a call
+#[test]
+fn test_parse_call_on_identifier() {
+    let input = "f(3, 4)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Call {
+        name: "f".to_string(),
+        args: vec![AstNode::Number(BigRational::from_integer(3.into())), AstNode::Number(BigRational::from_integer(4.into()))],
+        span: Span::new(0, 7),
+    });
+}
+
+// Parses a function definition whose parameter is not a name, and returns an error
+#[test]
+fn test_parse_function_definition_invalid_parameter() {
+    let input = "f(2) = 3";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::InvalidParameterList(Span::new(1, 4))]);
+}
+
+// Parses an expression and keeps the span of each node, where a binary operation covers
+// both operands and their parentheses
+#[test]
+fn test_parse_spans() {
+    let input = "-x! * (y + 1)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::BinaryOp {
+        operator: TokenType::Multiplication,
+        operand_1: Box::new(AstNode::UnaryOp {
+            operator: TokenType::Negation,
+            operand: Box::new(AstNode::PostfixOp {
+                operator: TokenType::Exclamation,
+                operand: Box::new(AstNode::Variable("x".to_string(), Span::new(1, 2))),
+                span: Span::new(1, 3),
+            }),
+            span: Span::new(0, 3),
+        }),
+        operand_2: Box::new(AstNode::BinaryOp {
+            operator: TokenType::Addition,
+            operand_1: Box::new(AstNode::Variable("y".to_string(), Span::new(7, 8))),
+            operand_2: Box::new(AstNode::Number(BigRational::from_integer(1.into()))),
+            span: Span::new(7, 12),
+        }),
+        span: Span::new(0, 13),
+    });
+}
+
+// Parses an input with several errors, recovering at `,`, `)` and `;` to report all of them
+#[test]
+fn test_parse_recovers_from_errors() {
+    let input = "max(1 +, 2) * (3 * ); 4 5";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let errors = match construct_ast(&tokens) {
+        Ok(result) => panic!("Expected errors, found {:?}", result),
+        Err(errors) => errors,
+    };
+    assert_eq!(errors, vec![
+        ParseError::UnexpectedToken {
+            expected: "an operand after `+`".to_string(),
+            found: ",".to_string(),
+            span: Span::new(7, 8),
+        },
+        ParseError::UnexpectedToken {
+            expected: "an operand after `*`".to_string(),
+            found: ")".to_string(),
+            span: Span::new(19, 20),
+        },
+        ParseError::UnexpectedTokensAtEnd {
+            found: "5".to_string(),
+            span: Span::new(24, 25),
+        },
+    ]);
+}
+
+// Parses a parenthesised expression with a token where the `)` should be
+#[test]
+fn test_parse_unexpected_token_in_parentheses() {
+    let input = "(1 2) + 3";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::UnexpectedToken {
+        expected: "`)`".to_string(),
+        found: "2".to_string(),
+        span: Span::new(3, 4),
+    }]);
+}
+
+// Parses a lambda, whose body reaches as far as it can
+#[test]
+fn test_parse_lambda() {
+    let input = "(x, y) -> x + y";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Lambda {
+        params: vec!["x".to_string(), "y".to_string()],
+        body: Box::new(AstNode::BinaryOp {
+            operator: TokenType::Addition,
+            operand_1: Box::new(AstNode::Variable("x".to_string(), Span::new(10, 11))),
+            operand_2: Box::new(AstNode::Variable("y".to_string(), Span::new(14, 15))),
+            span: Span::new(10, 15),
+        }),
+        span: Span::new(0, 15),
+    });
+}
+
+// Parses a lambda in parentheses followed by arguments, which calls it rather than multiplying
+#[test]
+fn test_parse_lambda_call() {
+    let input = "(x -> x + 1)(2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Apply {
+        callee: Box::new(AstNode::Lambda {
+            params: vec!["x".to_string()],
+            body: Box::new(AstNode::BinaryOp {
+                operator: TokenType::Addition,
+                operand_1: Box::new(AstNode::Variable("x".to_string(), Span::new(6, 7))),
+                operand_2: Box::new(AstNode::Number(BigRational::from_integer(1.into()))),
+                span: Span::new(6, 11),
+            }),
+            span: Span::new(1, 11),
+        }),
+        args: vec![AstNode::Number(BigRational::from_integer(2.into()))],
+        span: Span::new(0, 15),
+    });
+}
+
+// Parses a call followed by arguments, which calls what it gives rather than multiplying
+#[test]
+fn test_parse_call_of_call() {
+    let input = "f(3)(2)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Apply {
+        callee: Box::new(AstNode::Call {
+            name: "f".to_string(),
+            args: vec![AstNode::Number(BigRational::from_integer(3.into()))],
+            span: Span::new(0, 4),
         }),
+        args: vec![AstNode::Number(BigRational::from_integer(2.into()))],
+        span: Span::new(0, 7),
     });
-}
\ No newline at end of file
+}
+
+// Parses a built-in function without arguments, which is a lambda calling it
+#[test]
+fn test_parse_function_as_value() {
+    let input = "map(sin, v)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Call {
+        name: "map".to_string(),
+        args: vec![
+            AstNode::Lambda {
+                params: vec!["x".to_string()],
+                body: Box::new(AstNode::Function {
+                    function: TokenType::Keyword(Function::Sin),
+                    args: vec![AstNode::Variable("x".to_string(), Span::new(4, 7))],
+                    span: Span::new(4, 7),
+                }),
+                span: Span::new(4, 7),
+            },
+            AstNode::Variable("v".to_string(), Span::new(9, 10)),
+        ],
+        span: Span::new(0, 11),
+    });
+}
+
+// Parses a lambda passed as an argument, which ends at the comma
+#[test]
+fn test_parse_lambda_argument() {
+    let input = "sum(k -> k, 1, 3)";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Call {
+        name: "sum".to_string(),
+        args: vec![
+            AstNode::Lambda {
+                params: vec!["k".to_string()],
+                body: Box::new(AstNode::Variable("k".to_string(), Span::new(9, 10))),
+                span: Span::new(4, 10),
+            },
+            AstNode::Number(BigRational::from_integer(1.into())),
+            AstNode::Number(BigRational::from_integer(3.into())),
+        ],
+        span: Span::new(0, 17),
+    });
+}
+
+// Fails to parse an arrow after something that is not a parameter list
+#[test]
+fn test_parse_lambda_invalid_parameter() {
+    let input = "(x + 1) -> 2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::InvalidParameterList(Span::new(0, 7))]);
+}
+
+// Parses functions and lambdas that name a parameter twice, and returns an error
+#[test]
+fn test_parse_duplicate_parameters() {
+    let inputs = ["f(x, x) = x", "(x, x) -> x"];
+    let mut errors: Vec<Vec<ParseError>> = vec![];
+    for input in inputs {
+        let tokens = match tokenise(input.to_string()) {
+            Ok(result) => result,
+            Err(error) => panic!("LexerError: {:?}", error),
+        };
+        errors.push(construct_ast(&tokens).unwrap_err());
+    }
+    assert_eq!(errors[0], vec![ParseError::InvalidParameterList(Span::new(1, 7))]);
+    assert_eq!(errors[1], vec![ParseError::InvalidParameterList(Span::new(0, 6))]);
+}
+
+// Parses a list literal that is indexed and then sliced
+#[test]
+fn test_parse_list_index_and_slice() {
+    let input = "[1, x][0][1:]";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Slice {
+        operand: Box::new(AstNode::Index {
+            operand: Box::new(AstNode::List {
+                elements: vec![
+                    AstNode::Number(BigRational::from_integer(1.into())),
+                    AstNode::Variable("x".to_string(), Span::new(4, 5)),
+                ],
+                span: Span::new(0, 6),
+            }),
+            index: Box::new(AstNode::Number(BigRational::from_integer(0.into()))),
+            span: Span::new(0, 9),
+        }),
+        start: Some(Box::new(AstNode::Number(BigRational::from_integer(1.into())))),
+        end: None,
+        span: Span::new(0, 13),
+    });
+}
+
+// Fails to parse a list without its closing bracket
+#[test]
+fn test_parse_missing_closing_bracket() {
+    let input = "[1, 2";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![ParseError::MissingClosingBracket {
+        opening: Span::new(0, 1),
+        found: Span::new(5, 5),
+    }]);
+}
+
+// Parses a matrix written row by row, with its rows separated by semicolons
+#[test]
+fn test_parse_matrix() {
+    let input = "[1, 2; 3, x]";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Matrix {
+        rows: vec![
+            vec![
+                AstNode::Number(BigRational::from_integer(1.into())),
+                AstNode::Number(BigRational::from_integer(2.into())),
+            ],
+            vec![
+                AstNode::Number(BigRational::from_integer(3.into())),
+                AstNode::Variable("x".to_string(), Span::new(10, 11)),
+            ],
+        ],
+        span: Span::new(0, 12),
+    });
+}
+
+// Fails to parse a row of a matrix, and still checks the rows after it
+#[test]
+fn test_parse_matrix_with_invalid_row() {
+    let input = "[1, *; 3, 4";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![
+        ParseError::UnexpectedToken {
+            expected: "an expression after `,`".to_string(),
+            found: "*".to_string(),
+            span: Span::new(4, 5),
+        },
+        ParseError::MissingClosingBracket {
+            opening: Span::new(0, 1),
+            found: Span::new(11, 11),
+        },
+    ]);
+}
+
+// Parses input nested far deeper than the native stack could hold one parser frame per level for
+#[test]
+fn test_parse_deeply_nested() {
+    let input = format!("{}1{}", "(".repeat(10000), ")".repeat(10000));
+    let tokens = match tokenise(input) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let mut ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    assert_eq!(ast, AstNode::Number(BigRational::from_integer(1.into())));
+
+    let input = format!("{}1", "-".repeat(10000));
+    let tokens = match tokenise(input) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    let mut depth = 0;
+    while let AstNode::UnaryOp {operator: TokenType::Negation, operand, ..} = ast {
+        ast = *operand;
+        depth += 1;
+    }
+    assert_eq!(depth, 10000);
+    assert_eq!(ast, AstNode::Number(BigRational::from_integer(1.into())));
+
+    // A right associative operator nests its right operand without parsing a new operand first
+    let input = format!("{}1", "1^".repeat(10000));
+    let tokens = match tokenise(input) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    ast = match construct_ast(&tokens) {
+        Ok(result) => result,
+        Err(error) => panic!("ParseError: {:?}", error),
+    };
+    depth = 0;
+    while let AstNode::BinaryOp {operator: TokenType::Exponentiation, operand_2, ..} = ast {
+        ast = *operand_2;
+        depth += 1;
+    }
+    assert_eq!(depth, 10000);
+}
+
+// Fails to parse an angle with two units
+#[test]
+fn test_parse_doubled_angle_unit() {
+    let input = "30°°";
+    let tokens = match tokenise(input.to_string()) {
+        Ok(result) => result,
+        Err(error) => panic!("LexerError: {:?}", error),
+    };
+    let ast = construct_ast(&tokens);
+    assert_eq!(ast.unwrap_err(), vec![
+        ParseError::UnexpectedToken {
+            expected: "an operator after the angle".to_string(),
+            found: "°".to_string(),
+            span: Span::new(3, 4),
+        },
+    ]);
+}